@@ -0,0 +1,133 @@
+//! Line coverage behind `ferrum run --coverage`
+//!
+//! The script is parsed with [`crate::parser::parse_to_ast_with_lines`], so
+//! every statement carries its source line, and `run_stmts` counts a hit each
+//! time a statement starts. The report lists every source line with its hit
+//! count, marking executable lines that never ran with `#####` (as gcov does).
+//! Lines that aren't statements of their own (`else:`, comments, blank
+//! lines) have no count.
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+
+use crate::eval::Stmt;
+
+/// Hit counts per source line, shared by an env and every call made from it
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    hits: Rc<RefCell<HashMap<usize, u64>>>,
+}
+
+impl Coverage {
+    /// Count one execution of the statement on `line`
+    pub fn hit(&self, line: usize) {
+        *self.hits.borrow_mut().entry(line).or_default() += 1;
+    }
+
+    /// Times the statement on `line` has started
+    pub fn hits(&self, line: usize) -> u64 {
+        self.hits.borrow().get(&line).copied().unwrap_or(0)
+    }
+
+    /// Lines in `executable` that never ran
+    pub fn uncovered(&self, executable: &BTreeSet<usize>) -> Vec<usize> {
+        executable.iter().copied().filter(|l| self.hits(*l) == 0).collect()
+    }
+
+    /// Annotated listing of `src` with a summary line on top
+    pub fn report(&self, name: &str, src: &str, executable: &BTreeSet<usize>) -> String {
+        let covered = executable.len() - self.uncovered(executable).len();
+        let percent = if executable.is_empty() { 100.0 } else { covered as f64 * 100.0 / executable.len() as f64 };
+        let mut out = format!("Coverage for {}: {} of {} lines ({:.1}%)\n", name, covered, executable.len(), percent);
+        for (i, text) in src.lines().enumerate() {
+            let line = i + 1;
+            let count = match (executable.contains(&line), self.hits(line)) {
+                (false, _) => String::new(),
+                (true, 0) => "#####".to_string(),
+                (true, n) => n.to_string(),
+            };
+            out.push_str(&format!("{:>6} {:>4} | {}\n", count, line, text));
+        }
+        out
+    }
+}
+
+/// Source lines of every statement in `stmts`, including nested blocks and
+/// function bodies. Needs an AST parsed with line markers.
+pub fn executable_lines(stmts: &[Stmt]) -> BTreeSet<usize> {
+    let mut lines = BTreeSet::new();
+    collect_lines(stmts, &mut lines);
+    lines
+}
+
+fn collect_lines(stmts: &[Stmt], lines: &mut BTreeSet<usize>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Line(n, _) => {
+                lines.insert(*n);
+            }
+            Stmt::If(branches, else_block) => {
+                for (_, body) in branches {
+                    collect_lines(body, lines);
+                }
+                collect_lines(else_block, lines);
+            }
+            Stmt::Else(body) | Stmt::While(_, body) | Stmt::For(_, body) | Stmt::Def(_, body) | Stmt::Class(_, body) | Stmt::Block(_, body) => {
+                collect_lines(body, lines)
+            }
+            Stmt::Try { body, except_body, finally_body, .. } => {
+                collect_lines(body, lines);
+                collect_lines(except_body, lines);
+                collect_lines(finally_body, lines);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Env;
+
+    #[test]
+    fn test_unexecuted_else_branch_is_uncovered() {
+        let src = "x = 5\nif x > 3:\n    y = 1\nelse:\n    y = 2\nfor i in range(0, 3):\n    x = x + i\n";
+        let ast = crate::parser::parse_to_ast_with_lines(src).unwrap();
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let coverage = env.enable_coverage();
+        crate::parser::run_module(&ast, &mut env).unwrap();
+
+        let executable = executable_lines(&ast.body);
+        // `else:` is part of the if statement, not a line of its own
+        assert_eq!(executable, BTreeSet::from([1, 2, 3, 5, 6, 7]));
+        assert_eq!(coverage.uncovered(&executable), vec![5]);
+        assert_eq!(coverage.hits(7), 3);
+
+        let report = coverage.report("t.fm", src, &executable);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "Coverage for t.fm: 5 of 6 lines (83.3%)");
+        assert_eq!(lines[4], "          4 | else:");
+        assert_eq!(lines[5], " #####    5 |     y = 2");
+        assert_eq!(lines[7], "     3    7 |     x = x + i");
+    }
+
+    #[test]
+    fn test_class_method_headers_are_covered() {
+        let src = "class Counter:\n    def __init__(self):\n        self.n = 0\n    def bump(self):\n        self.n = self.n + 1\nc = Counter()\n";
+        let ast = crate::parser::parse_to_ast_with_lines(src).unwrap();
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let coverage = env.enable_coverage();
+        crate::parser::run_module(&ast, &mut env).unwrap();
+
+        let executable = executable_lines(&ast.body);
+        assert_eq!(executable, BTreeSet::from([1, 2, 3, 4, 5, 6]));
+        // both method headers ran with the class statement; only bump's body never did
+        assert_eq!(coverage.uncovered(&executable), vec![5]);
+        assert_eq!(coverage.hits(2), 1);
+        assert_eq!(coverage.hits(4), 1);
+    }
+}
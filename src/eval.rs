@@ -1,25 +1,35 @@
 use anyhow::{anyhow, Result};
 
-use crate::runtime::{Env, Function};
+use crate::runtime::{Env, Function, Param};
 
 /// Statement types in the Ferrum AST
 /// 
 /// Ferrum supports the following statement types:
 /// - Print: Output to stdout
 /// - Assign: Variable assignment
+/// - AugAssign: Compound assignment such as `x += 1`
 /// - Expr: Standalone expression evaluation
 /// - If/Elif/Else: Conditional execution
 /// - While: Looping construct
 /// - For: Iteration over sequences
 /// - Def: Function definition
 /// - Class: Class definition
-/// - Block: Generic code blocks (try/except)
+/// - Try: try/except/finally error handling
+/// - Block: Generic code blocks
+/// - Raise: Raise an error for an enclosing try/except
+/// - Assert: Raise an AssertionError when a condition is false
 /// - Return: Return from function
+/// - Break/Continue: Leave or restart the innermost loop
+/// - Pass: Do nothing
+/// - Yield: Produce a value from a generator function
 /// - Import: Module import
+/// - Line: Source line marker for tracing and coverage
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum Stmt {
     Print(String),
     Assign(String, String),
+    /// `target op= value`, e.g. `xs[i] += 1`, with the operator without its `=`
+    AugAssign(String, String, String),
     Expr(String),
     If(Vec<(String, Vec<Stmt>)>, Vec<Stmt>),
     Else(Vec<Stmt>),
@@ -28,15 +38,112 @@ pub enum Stmt {
     Def(String, Vec<Stmt>),
     Class(String, Vec<Stmt>),
     Block(String, Vec<Stmt>),
+    Try {
+        body: Vec<Stmt>,
+        except_name: Option<String>,
+        except_body: Vec<Stmt>,
+        finally_body: Vec<Stmt>,
+    },
+    Raise(String),
+    Assert(String, Option<String>),
     Return(String),
+    Break,
+    Continue,
+    Pass,
+    Yield(String),
     Import(String),
+    /// Source line number and text (without its indent) of the statement that
+    /// follows; only present in ASTs from [`crate::parser::parse_to_ast_with_lines`]
+    Line(usize, String),
 }
 
-pub fn run_stmts(stmts: &Vec<Stmt>, env: &mut Env) -> Result<()> {
+/// How control leaves a statement: normally, or by `break`, `continue` or
+/// `return`. Loops consume `Break`/`Continue`; `Return` propagates up to the
+/// enclosing function call.
+#[derive(Debug, Clone)]
+pub enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(crate::runtime::Value),
+}
+
+/// Run statements in order, stopping early at the first one that doesn't
+/// complete normally and passing its [`Flow`] to the caller.
+pub fn run_stmts(stmts: &[Stmt], env: &mut Env) -> Result<Flow> {
+    let mut line = None;
     for s in stmts.iter() {
-        exec_stmt(s, env)?;
+        if let Stmt::Line(n, text) = s {
+            line = Some((*n, text.as_str()));
+            continue;
+        }
+        crate::stdlib::signals::dispatch_pending(env)?;
+        if let Some(budget) = &env.budget {
+            budget.charge()?;
+        }
+        if env.is_tracing() {
+            trace_stmt(line, s, env);
+        }
+        if let (Some(coverage), Some((n, _))) = (&env.coverage, line) {
+            coverage.hit(n);
+        }
+        match exec_stmt(s, env)? {
+            Flow::Normal => {}
+            flow => return Ok(flow),
+        }
+    }
+    Ok(Flow::Normal)
+}
+
+/// Longest variable value shown in a trace line before it is cut short
+const TRACE_VALUE_WIDTH: usize = 40;
+
+/// Report `stmt` to the env's trace hook: its line number, the source text
+/// as written, and the current values of the plain variables it mentions.
+/// Without line markers the statement is described from the AST instead.
+/// Nothing is evaluated, so tracing never runs a call twice.
+fn trace_stmt(line: Option<(usize, &str)>, stmt: &Stmt, env: &Env) {
+    use crate::runtime::Value;
+
+    let text = match line {
+        Some((_, source)) => source.to_string(),
+        None => crate::parser::describe(stmt),
+    };
+    let mut values: Vec<String> = Vec::new();
+    let mut quotes = QuoteScan::default();
+    let mut word = String::new();
+    let mut after_dot = false;
+    // a trailing space flushes the last word
+    for c in text.chars().chain([' ']) {
+        let in_string = quotes.step(c);
+        if !in_string && (c.is_alphanumeric() || c == '_') {
+            word.push(c);
+            continue;
+        }
+        // attribute names (`obj.field`) and numbers aren't variables
+        let is_var = !after_dot && word.starts_with(|c: char| !c.is_ascii_digit());
+        if is_var && !values.iter().any(|v| v.starts_with(&format!("{}=", word))) {
+            let shown = match env.get(&word) {
+                Some(Value::Str(s)) => Some(format!("{:?}", s)),
+                Some(v @ (Value::Int(_) | Value::Float(_) | Value::Bool(_) | Value::None | Value::List(_) | Value::Range(..))) => Some(v.to_string()),
+                _ => None,
+            };
+            if let Some(mut shown) = shown {
+                if shown.chars().count() > TRACE_VALUE_WIDTH {
+                    shown = format!("{}...", shown.chars().take(TRACE_VALUE_WIDTH).collect::<String>());
+                }
+                values.push(format!("{}={}", word, shown));
+            }
+        }
+        word.clear();
+        after_dot = !in_string && c == '.';
+    }
+    let location = line.map(|(n, _)| format!("line {}", n)).unwrap_or_else(|| "line ?".to_string());
+    if values.is_empty() {
+        env.trace_line(&format!("[trace] {}: {}", location, text));
+    } else {
+        env.trace_line(&format!("[trace] {}: {}  ({})", location, text, values.join(", ")));
     }
-    Ok(())
 }
 
 /// Public interface to evaluate an expression and return its value
@@ -59,68 +166,164 @@ pub fn eval_expr_str(expr: &str, env: &mut Env) -> Result<crate::runtime::Value>
     eval_expr(expr, env)
 }
 
-fn exec_stmt(stmt: &Stmt, env: &mut Env) -> Result<Option<crate::runtime::Value>> {
+fn exec_stmt(stmt: &Stmt, env: &mut Env) -> Result<Flow> {
     match stmt {
         Stmt::Print(expr) => {
-            let v = eval_expr(expr, env)?;
-            println!("{}", v.to_string());
-            Ok(None)
+            // print(a, b) prints its arguments separated by spaces
+            let parts = split_top_level_commas(expr)
+                .into_iter()
+                .map(|part| eval_expr(part, env).and_then(|v| format_value(&v, env)))
+                .collect::<Result<Vec<_>>>()?;
+            env.write_line(&parts.join(" "));
+            Ok(Flow::Normal)
         }
         Stmt::Assign(lhs, rhs) => {
             let v = eval_expr(rhs, env)?;
-            if lhs.contains('.') {
-                if let Some((obj, attr)) = lhs.split_once('.') {
-                    if env.set_attr(obj, attr, v.clone()).is_ok() {
-                        return Ok(None);
-                    }
+            if has_top_level_comma(lhs) {
+                // unpacking: `a, b = 1, 2` or `x, y = point`
+                let targets = split_top_level_commas(lhs);
+                let values = match v {
+                    crate::runtime::Value::List(items) => items,
+                    crate::runtime::Value::Range(start, end) => (start..end).map(crate::runtime::Value::Int).collect(),
+                    other => return Err(anyhow!("Cannot unpack {} into {}", other.to_string(), lhs)),
+                };
+                if values.len() != targets.len() {
+                    return Err(anyhow!("Expected {} values to unpack, got {}", targets.len(), values.len()));
                 }
+                for (target, value) in targets.into_iter().zip(values) {
+                    assign(target.trim(), value, env)?;
+                }
+            } else {
+                assign(lhs, v, env)?;
+            }
+            Ok(Flow::Normal)
+        }
+        Stmt::AugAssign(lhs, op, rhs) => {
+            // a subscript target's container and index are evaluated once
+            if let Some((base, index)) = split_subscript(lhs) {
+                let container = eval_expr(base, env)?;
+                let key = eval_expr(index, env)?;
+                let current = get_item(container.clone(), key.clone(), env)?;
+                let v = apply_op(op, current, eval_expr(rhs, env)?)?;
+                let updated = set_item(container, key, v, env)?;
+                assign(base.trim(), updated, env)?;
+            } else {
+                let current = eval_expr(lhs, env)?;
+                let v = apply_op(op, current, eval_expr(rhs, env)?)?;
+                assign(lhs, v, env)?;
             }
-            env.set(lhs.clone(), v);
-            Ok(None)
+            Ok(Flow::Normal)
         }
         Stmt::Expr(expr) => {
             let _ = eval_expr(expr, env)?;
-            Ok(None)
+            Ok(Flow::Normal)
         }
         Stmt::If(branches, else_block) => {
             for (cond, body) in branches.iter() {
                 let v = eval_expr(cond, env)?;
                 if v.is_truthy() {
-                    run_stmts(body, env)?;
-                    return Ok(None);
+                    return run_stmts(body, env);
                 }
             }
-            if !else_block.is_empty() {
-                run_stmts(else_block, env)?;
-            }
-            Ok(None)
+            run_stmts(else_block, env)
         }
-        Stmt::Else(body) => {
-            run_stmts(body, env)?;
-            Ok(None)
+        Stmt::Else(body) => run_stmts(body, env),
+        Stmt::Try { body, except_name, except_body, finally_body } => {
+            let result = match run_stmts(body, env) {
+                // the parser never leaves an except body empty, so an empty one means
+                // try/finally and the error propagates after `finally`
+                Err(e) if !except_body.is_empty() => {
+                    if let Some(name) = except_name {
+                        env.set(name.clone(), crate::runtime::Value::Error(e.to_string()));
+                    }
+                    run_stmts(except_body, env)
+                }
+                other => other,
+            };
+            // like Python, a break/continue/return in `finally` wins over the pending outcome
+            match run_stmts(finally_body, env)? {
+                Flow::Normal => result,
+                flow => Ok(flow),
+            }
         }
         Stmt::While(cond, body) => {
             while eval_expr(cond, env)?.is_truthy() {
-                run_stmts(body, env)?;
+                match run_stmts(body, env)? {
+                    Flow::Break => break,
+                    Flow::Return(v) => return Ok(Flow::Return(v)),
+                    Flow::Normal | Flow::Continue => {}
+                }
             }
-            Ok(None)
+            Ok(Flow::Normal)
         }
         Stmt::For(header, body) => {
             // header like "x in range(0,10)" or "x in mylist"
             if let Some((var, rest)) = header.split_once(" in ") {
                 let var = var.trim();
-                let iter = eval_expr(rest.trim().to_string().as_str(), env)?;
+                let iter = match eval_expr(rest.trim().to_string().as_str(), env)? {
+                    // dicts iterate over their keys, in sorted order
+                    crate::runtime::Value::Dict(map) => crate::runtime::Value::List(
+                        crate::stdlib::dictmod::sorted_keys(&map).into_iter().map(crate::runtime::Value::Str).collect(),
+                    ),
+                    other => other,
+                };
                 match iter {
                     crate::runtime::Value::List(vs) => {
                         for val in vs {
                             env.set(var.to_string(), val.clone());
-                            run_stmts(body, env)?;
+                            match run_stmts(body, env)? {
+                                Flow::Break => break,
+                                Flow::Return(v) => return Ok(Flow::Return(v)),
+                                Flow::Normal | Flow::Continue => {}
+                            }
                         }
                     }
                     crate::runtime::Value::Range(start, end) => {
                         for i in start..end {
                             env.set(var.to_string(), crate::runtime::Value::Int(i as i64));
-                            run_stmts(body, env)?;
+                            match run_stmts(body, env)? {
+                                Flow::Break => break,
+                                Flow::Return(v) => return Ok(Flow::Return(v)),
+                                Flow::Normal | Flow::Continue => {}
+                            }
+                        }
+                    }
+                    obj @ crate::runtime::Value::Instance(..) => {
+                        // iterator protocol: __iter__ (optional) then __next__ until StopIteration
+                        let mut iterator = if has_method(&obj, "__iter__") {
+                            invoke_method(env, &obj, "__iter__", vec![])?.0
+                        } else {
+                            obj
+                        };
+                        if !has_method(&iterator, "__next__") {
+                            return Err(anyhow!("for-loop over object without __next__"));
+                        }
+                        // Ok(None) once the iterator is exhausted, Ok(Some(flow)) when the loop is left early
+                        let outcome = loop {
+                            let (val, updated) = match invoke_method(env, &iterator, "__next__", vec![]) {
+                                Ok(res) => res,
+                                Err(e) if is_stop_iteration(&e) => break Ok(None),
+                                Err(e) => break Err(e),
+                            };
+                            iterator = updated;
+                            env.set(var.to_string(), val);
+                            match run_stmts(body, env) {
+                                Ok(Flow::Break) => break Ok(Some(Flow::Normal)),
+                                Ok(Flow::Return(v)) => break Ok(Some(Flow::Return(v))),
+                                Ok(Flow::Normal | Flow::Continue) => {}
+                                Err(e) => break Err(e),
+                            }
+                        };
+                        // an iterator that wasn't run to the end is closed, so e.g. the
+                        // file behind read_file_lines() is released on break or error
+                        if !matches!(outcome, Ok(None)) && has_method(&iterator, "close") {
+                            let closed = invoke_method(env, &iterator, "close", vec![]);
+                            if outcome.is_ok() {
+                                closed?;
+                            }
+                        }
+                        if let Some(Flow::Return(v)) = outcome? {
+                            return Ok(Flow::Return(v));
                         }
                     }
                     _ => {
@@ -128,162 +331,289 @@ fn exec_stmt(stmt: &Stmt, env: &mut Env) -> Result<Option<crate::runtime::Value>
                     }
                 }
             }
-            Ok(None)
+            Ok(Flow::Normal)
         }
         Stmt::Def(sig, body) => {
             // sig like: name(a, b=1)
             if let Some((name, args)) = sig.split_once('(') {
                 let name = name.trim().to_string();
-                let args = args.trim_end_matches(')').to_string();
-                let args_vec: Vec<String> = args
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty())
-                    .collect();
                 let func = Function::User {
-                    params: args_vec,
+                    params: parse_params(args)?,
                     body: body.clone(),
                 };
                 env.set_func(name, func);
             }
-            Ok(None)
+            Ok(Flow::Normal)
         }
         Stmt::Class(name, body) => {
             // collect methods defined inside class body
             let mut methods = std::collections::HashMap::new();
+            let mut line = None;
             for s in body.iter() {
+                if let Stmt::Line(n, _) = s {
+                    line = Some(*n);
+                    continue;
+                }
+                // the body isn't run through run_stmts, so count the method
+                // headers here as a top-level `def` would be
+                if let (Some(coverage), Some(n)) = (&env.coverage, line) {
+                    coverage.hit(n);
+                }
                 if let Stmt::Def(sig, mbody) = s {
                     if let Some((mname, args)) = sig.split_once('(') {
                         let mname = mname.trim().to_string();
-                        let args = args.trim_end_matches(')').to_string();
-                        let args_vec: Vec<String> = args
-                            .split(',')
-                            .map(|ss| ss.trim().to_string())
-                            .filter(|ss| !ss.is_empty())
-                            .collect();
-                        let func = Function::User { params: args_vec, body: mbody.clone() };
+                        let func = Function::User { params: parse_params(args)?, body: mbody.clone() };
                         methods.insert(mname, func);
                     }
                 }
             }
             env.set(name.clone(), crate::runtime::Value::Class(name.clone(), methods));
-            Ok(None)
+            Ok(Flow::Normal)
         }
         Stmt::Return(expr) => {
             let v = eval_expr(expr, env)?;
-            Ok(Some(v))
+            Ok(Flow::Return(v))
+        }
+        Stmt::Raise(expr) => {
+            // `raise StopIteration` keeps its name so a for loop can recognize it
+            let name = call_paren(expr).map_or(expr.trim(), |open| expr[..open].trim());
+            if name == STOP_ITERATION && env.get_func(name).is_none() {
+                return Err(anyhow!(STOP_ITERATION));
+            }
+            // `raise SomeError("msg")` works for any error name: only the message is kept
+            let value = match call_paren(expr) {
+                Some(open) if env.get_func(expr[..open].trim()).is_none() && is_identifier(expr[..open].trim()) => {
+                    eval_expr(&expr[open + 1..expr.len() - 1], env)?
+                }
+                _ => eval_expr(expr, env)?,
+            };
+            Err(anyhow!("{}", value.to_string()))
+        }
+        Stmt::Assert(cond, msg) => {
+            if eval_expr(cond, env)?.is_truthy() {
+                return Ok(Flow::Normal);
+            }
+            match msg {
+                Some(msg) => Err(anyhow!("AssertionError: {}", eval_expr(msg, env)?.to_string())),
+                None => Err(anyhow!("AssertionError")),
+            }
+        }
+        Stmt::Break => Ok(Flow::Break),
+        Stmt::Pass | Stmt::Line(..) => Ok(Flow::Normal),
+        Stmt::Continue => Ok(Flow::Continue),
+        Stmt::Yield(expr) => {
+            let v = if expr.trim().is_empty() { crate::runtime::Value::None } else { eval_expr(expr, env)? };
+            match env.yields.as_mut() {
+                Some(values) => values.push(v),
+                None => return Err(anyhow!("'yield' outside generator function")),
+            }
+            Ok(Flow::Normal)
         }
         Stmt::Import(name) => {
             env.import_module(name)?;
-            Ok(None)
+            Ok(Flow::Normal)
         }
-        _ => Ok(None),
+        _ => Ok(Flow::Normal),
     }
 }
 
-fn eval_expr(expr: &str, env: &mut Env) -> Result<crate::runtime::Value> {
-    let s = expr.trim();
-    // helper: split an expression by a top-level operator (not inside parentheses or quotes)
-    fn split_top_level(s: &str, op: char) -> Option<(&str, &str)> {
-        let mut depth = 0usize;
-        let mut in_quote = false;
-        let mut prev = '\0';
-        for (i, c) in s.char_indices() {
-            if c == '"' && prev != '\\' {
-                in_quote = !in_quote;
-            }
-            if in_quote { prev = c; continue; }
-            if c == '(' { depth += 1; }
-            else if c == ')' { if depth > 0 { depth -= 1; } }
-            else if c == op && depth == 0 {
-                let (l, r) = s.split_at(i);
-                return Some((l.trim(), r[1..].trim()));
+/// Bind `value` to an assignment target: an attribute like `obj.field` or a
+/// plain name
+fn assign(target: &str, value: crate::runtime::Value, env: &mut Env) -> Result<()> {
+    if let Some((base, index)) = split_subscript(target) {
+        // `xs[i] = v` / `d[k] = v`: update the container, then store it back
+        let container = eval_expr(base, env)?;
+        let key = eval_expr(index, env)?;
+        let updated = set_item(container, key, value, env)?;
+        return assign(base.trim(), updated, env);
+    }
+    if let Some((obj, attr)) = target.split_once('.') {
+        if env.set_attr(obj, attr, value.clone()).is_ok() {
+            return Ok(());
+        }
+    }
+    env.set(target.to_string(), value);
+    Ok(())
+}
+
+/// Store `value` at `key` in a list, dict or `__setitem__` instance,
+/// returning the updated container
+fn set_item(container: crate::runtime::Value, key: crate::runtime::Value, value: crate::runtime::Value, env: &mut Env) -> Result<crate::runtime::Value> {
+    use crate::runtime::Value;
+
+    match (container, key) {
+        (Value::Dict(mut map), Value::Str(k)) => {
+            map.insert(k, value);
+            Ok(Value::Dict(map))
+        }
+        (Value::Dict(_), other) => Err(anyhow!("Dict keys must be strings, got {}", other.to_string())),
+        (Value::List(mut items), Value::Int(i)) => {
+            let len = items.len() as i64;
+            let pos = if i < 0 { len + i } else { i };
+            if pos < 0 || pos >= len {
+                return Err(anyhow!("Index {} out of range for length {}", i, len));
             }
-            prev = c;
+            items[pos as usize] = value;
+            Ok(Value::List(items))
         }
-        None
+        (Value::List(_), other) => Err(anyhow!("Indices must be integers, got {}", other.to_string())),
+        (obj @ Value::Instance(..), key) => Ok(invoke_method(env, &obj, "__setitem__", vec![key, value])?.1),
+        (other, _) => Err(anyhow!("Value {} does not support item assignment", other.to_string())),
     }
+}
 
-    // binary + handling (string concat or numeric add)
-    if let Some((left, right)) = split_top_level(s, '+') {
+fn has_top_level_comma(s: &str) -> bool {
+    top_level_indices(s).into_iter().any(|i| s.as_bytes()[i] == b',')
+}
+
+fn eval_expr(expr: &str, env: &mut Env) -> Result<crate::runtime::Value> {
+    let s = expr.trim();
+    // A top-level comma makes a tuple, represented as a list: `1, 2` or `b, a`
+    if has_top_level_comma(s) {
+        let items = split_top_level_commas(s)
+            .into_iter()
+            .map(|item| eval_expr(item, env))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(crate::runtime::Value::List(items));
+    }
+    // Operators are tried loosest-binding first, so each split leaves the
+    // tighter-binding operators inside its operands:
+    //   ternary (a if c else b)  <  or  <  and  <  not  <  comparisons (== != < <= > >= in, not in)
+    //   <  additive (+ -)  <  multiplicative (* / // %)  <  unary (- +)
+    //   <  power (**, right-associative)  <  primary
+    // Primary expressions are groups, literals, subscripts, attribute
+    // access, calls and names.
+    // `a if cond else b` evaluates only the chosen branch; chains nest to the
+    // right, so `a if x else b if y else c` picks a, b or c
+    if let Some((then, rest)) = split_top_level_word(s, "if") {
+        let (cond, otherwise) = split_top_level_word(rest, "else")
+            .ok_or_else(|| anyhow!("Conditional expression without else: {}", s))?;
+        return if eval_expr(cond, env)?.is_truthy() {
+            eval_expr(then, env)
+        } else {
+            eval_expr(otherwise, env)
+        };
+    }
+    if let Some((left, right)) = split_top_level_word(s, "or") {
+        let lv = eval_expr(left, env)?;
+        if lv.is_truthy() {
+            return Ok(lv);
+        }
+        return eval_expr(right, env);
+    }
+    if let Some((left, right)) = split_top_level_word(s, "and") {
+        let lv = eval_expr(left, env)?;
+        if !lv.is_truthy() {
+            return Ok(lv);
+        }
+        return eval_expr(right, env);
+    }
+    if let Some(operand) = s.strip_prefix("not") {
+        if operand.starts_with([' ', '(']) {
+            let v = eval_expr(operand, env)?;
+            return Ok(crate::runtime::Value::Bool(!v.is_truthy()));
+        }
+    }
+    if let Some((left, op, right)) = split_comparison(s) {
         let lv = eval_expr(left, env)?;
         let rv = eval_expr(right, env)?;
-        match (lv, rv) {
-            (crate::runtime::Value::Str(a), crate::runtime::Value::Str(b)) => return Ok(crate::runtime::Value::Str(a + &b)),
-            (crate::runtime::Value::Str(a), other) => return Ok(crate::runtime::Value::Str(a + &other.to_string())),
-            (other, crate::runtime::Value::Str(b)) => return Ok(crate::runtime::Value::Str(other.to_string() + &b)),
-            (crate::runtime::Value::Int(a), crate::runtime::Value::Int(b)) => return Ok(crate::runtime::Value::Int(a + b)),
-            (crate::runtime::Value::Float(a), crate::runtime::Value::Float(b)) => return Ok(crate::runtime::Value::Float(a + b)),
-            (crate::runtime::Value::Int(a), crate::runtime::Value::Float(b)) => return Ok(crate::runtime::Value::Float(a as f64 + b)),
-            (crate::runtime::Value::Float(a), crate::runtime::Value::Int(b)) => return Ok(crate::runtime::Value::Float(a + b as f64)),
-            (a, b) => return Ok(crate::runtime::Value::Str(a.to_string() + &b.to_string())),
-        }
-    }
-
-    // binary - handling (numeric subtraction)
-    if let Some((left, right)) = split_top_level(s, '-') {
-        // Make sure it's not a negative number (check if left side is empty or operator)
-        if !left.is_empty() && !left.ends_with('(') {
-            let lv = eval_expr(left, env)?;
-            let rv = eval_expr(right, env)?;
-            match (lv, rv) {
-                (crate::runtime::Value::Int(a), crate::runtime::Value::Int(b)) => return Ok(crate::runtime::Value::Int(a - b)),
-                (crate::runtime::Value::Float(a), crate::runtime::Value::Float(b)) => return Ok(crate::runtime::Value::Float(a - b)),
-                (crate::runtime::Value::Int(a), crate::runtime::Value::Float(b)) => return Ok(crate::runtime::Value::Float(a as f64 - b)),
-                (crate::runtime::Value::Float(a), crate::runtime::Value::Int(b)) => return Ok(crate::runtime::Value::Float(a - b as f64)),
-                _ => {}
+        let result = match op {
+            "==" => lv == rv,
+            "!=" => lv != rv,
+            "in" => contains(&rv, &lv)?,
+            "not in" => !contains(&rv, &lv)?,
+            _ => {
+                let ord = lv.partial_cmp(&rv).ok_or_else(|| {
+                    anyhow!("Cannot compare {} and {}", lv.to_string(), rv.to_string())
+                })?;
+                match op {
+                    "<" => ord.is_lt(),
+                    "<=" => ord.is_le(),
+                    ">" => ord.is_gt(),
+                    _ => ord.is_ge(),
+                }
             }
-        }
+        };
+        return Ok(crate::runtime::Value::Bool(result));
     }
 
-    // binary * handling (numeric multiplication)
-    if let Some((left, right)) = split_top_level(s, '*') {
+    // additive tier, split at the last operator so `10 - 2 - 3` is `(10 - 2) - 3`
+    if let Some((left, op, right)) = split_last_binary(s, &["+", "-"]) {
         let lv = eval_expr(left, env)?;
         let rv = eval_expr(right, env)?;
-        match (lv, rv) {
-            (crate::runtime::Value::Int(a), crate::runtime::Value::Int(b)) => return Ok(crate::runtime::Value::Int(a * b)),
-            (crate::runtime::Value::Float(a), crate::runtime::Value::Float(b)) => return Ok(crate::runtime::Value::Float(a * b)),
-            (crate::runtime::Value::Int(a), crate::runtime::Value::Float(b)) => return Ok(crate::runtime::Value::Float(a as f64 * b)),
-            (crate::runtime::Value::Float(a), crate::runtime::Value::Int(b)) => return Ok(crate::runtime::Value::Float(a * b as f64)),
-            _ => {}
-        }
+        return binary_op(op, lv, rv);
     }
 
-    // binary / handling (numeric division)
-    if let Some((left, right)) = split_top_level(s, '/') {
+    // multiplicative tier
+    if let Some((left, op, right)) = split_last_binary(s, &["*", "//", "/", "%"]) {
         let lv = eval_expr(left, env)?;
         let rv = eval_expr(right, env)?;
-        match (lv, rv) {
-            (crate::runtime::Value::Int(a), crate::runtime::Value::Int(b)) => {
-                if b == 0 {
-                    return Err(anyhow!("Division by zero"));
-                }
-                return Ok(crate::runtime::Value::Int(a / b));
-            }
-            (crate::runtime::Value::Float(a), crate::runtime::Value::Float(b)) => {
-                if b == 0.0 {
-                    return Err(anyhow!("Division by zero"));
-                }
-                return Ok(crate::runtime::Value::Float(a / b));
-            }
-            (crate::runtime::Value::Int(a), crate::runtime::Value::Float(b)) => {
-                if b == 0.0 {
-                    return Err(anyhow!("Division by zero"));
-                }
-                return Ok(crate::runtime::Value::Float(a as f64 / b));
-            }
-            (crate::runtime::Value::Float(a), crate::runtime::Value::Int(b)) => {
-                if b == 0 {
-                    return Err(anyhow!("Division by zero"));
-                }
-                return Ok(crate::runtime::Value::Float(a / b as f64));
-            }
-            _ => {}
+        return binary_op(op, lv, rv);
+    }
+
+    // unary tier: `-x`, `--4`, `+x`; the operand may itself be unary
+    if let Some(operand) = s.strip_prefix('-') {
+        return match eval_expr(operand, env)? {
+            crate::runtime::Value::Int(i) => i.checked_neg().map(crate::runtime::Value::Int).ok_or_else(|| anyhow!("Integer overflow in -{}", i)),
+            crate::runtime::Value::Float(f) => Ok(crate::runtime::Value::Float(-f)),
+            other => Err(anyhow!("Bad operand for unary -: {}", other.to_string())),
+        };
+    }
+    if let Some(operand) = s.strip_prefix('+') {
+        return match eval_expr(operand, env)? {
+            v @ (crate::runtime::Value::Int(_) | crate::runtime::Value::Float(_)) => Ok(v),
+            other => Err(anyhow!("Bad operand for unary +: {}", other.to_string())),
+        };
+    }
+
+    // power tier, split at the first `**` so `2 ** 3 ** 2` is `2 ** (3 ** 2)`
+    if let Some(i) = top_level_indices(s).into_iter().find(|&i| s[i..].starts_with("**") && i > 0) {
+        let lv = eval_expr(&s[..i], env)?;
+        let rv = eval_expr(&s[i + 2..], env)?;
+        return power(lv, rv);
+    }
+
+    // parenthesized group
+    if s.starts_with('(') && top_level_indices(s) == [0] {
+        return eval_expr(&s[1..s.len() - 1], env);
+    }
+
+    // list literal: [a, b, ...]
+    if s.starts_with('[') && top_level_indices(s) == [0] {
+        let items = split_top_level_commas(&s[1..s.len() - 1])
+            .into_iter()
+            .map(|item| eval_expr(item, env))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(crate::runtime::Value::List(items));
+    }
+
+    // dict literal: {"key": value, ...}
+    if s.starts_with('{') && top_level_indices(s) == [0] {
+        let mut map = std::collections::HashMap::new();
+        for pair in split_top_level_commas(&s[1..s.len() - 1]) {
+            let colon = top_level_indices(pair)
+                .into_iter()
+                .find(|&i| pair[i..].starts_with(':'))
+                .ok_or_else(|| anyhow!("Expected key: value in dict literal, got {}", pair))?;
+            let key = match eval_expr(&pair[..colon], env)? {
+                crate::runtime::Value::Str(k) => k,
+                other => return Err(anyhow!("Dict keys must be strings, got {}", other.to_string())),
+            };
+            let value = eval_expr(&pair[colon + 1..], env)?;
+            map.insert(key, value);
         }
+        return Ok(crate::runtime::Value::Dict(map));
+    }
+
+    // subscript: base[index] or base[start:end]
+    if let Some((base, index)) = split_subscript(s) {
+        let base = eval_expr(base, env)?;
+        return subscript(base, index, env);
     }
+
     // literals
-    if s.starts_with('"') && s.ends_with('"') {
-        return Ok(crate::runtime::Value::Str(s[1..s.len() - 1].to_string()));
+    if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+        return Ok(crate::runtime::Value::Str(unescape(&s[1..s.len() - 1])));
     }
     if s == "True" || s == "true" {
         return Ok(crate::runtime::Value::Bool(true));
@@ -291,6 +621,9 @@ fn eval_expr(expr: &str, env: &mut Env) -> Result<crate::runtime::Value> {
     if s == "False" || s == "false" {
         return Ok(crate::runtime::Value::Bool(false));
     }
+    if s == "None" {
+        return Ok(crate::runtime::Value::None);
+    }
     if let Ok(i) = s.parse::<i64>() {
         return Ok(crate::runtime::Value::Int(i));
     }
@@ -315,6 +648,9 @@ fn eval_expr(expr: &str, env: &mut Env) -> Result<crate::runtime::Value> {
                             return Ok(fv.clone());
                         }
                     }
+                    crate::runtime::Value::Error(msg) if attr == "message" => {
+                        return Ok(crate::runtime::Value::Str(msg.clone()));
+                    }
                     _ => {}
                 }
             }
@@ -322,103 +658,1046 @@ fn eval_expr(expr: &str, env: &mut Env) -> Result<crate::runtime::Value> {
     }
 
     // function call like name(arg1, arg2) or method call obj.method(args)
-    if let Some((fname, _rest)) = s.split_once('(') {
-        if s.ends_with(')') {
-            let fname = fname.trim();
-            let args = &s[fname.len() + 1..s.len() - 1];
-            let args_vec: Vec<crate::runtime::Value> = if args.trim().is_empty() {
-                vec![]
-            } else {
-                args.split(',')
-                    .map(|a| eval_expr(a, env))
-                    .collect::<Result<Vec<_>>>()?
-            };
+    if let Some(open) = call_paren(s) {
+        let fname = s[..open].trim();
+        let args = &s[open + 1..s.len() - 1];
+        let mut args_vec: Vec<crate::runtime::Value> = vec![];
+        let mut kwargs: Vec<(String, crate::runtime::Value)> = vec![];
+        if !args.trim().is_empty() {
+            for arg in split_top_level_commas(args) {
+                match split_keyword_arg(arg) {
+                    (Some(name), value) => {
+                        if kwargs.iter().any(|(k, _)| k == name) {
+                            return Err(anyhow!("Keyword argument repeated: {}", name));
+                        }
+                        kwargs.push((name.to_string(), eval_expr(value, env)?));
+                    }
+                    (None, _) if !kwargs.is_empty() => {
+                        return Err(anyhow!("Positional argument follows keyword argument in {}", s));
+                    }
+                    (None, value) => args_vec.push(eval_expr(value, env)?),
+                }
+            }
+        }
 
-            // method call: obj.method(...)
-            if let Some((obj_name, method_name)) = fname.rsplit_once('.') {
-                let obj_name = obj_name.trim();
-                let method_name = method_name.trim();
-                if let Some(obj_val) = env.get(obj_name) {
-                    match obj_val.clone() {
-                        crate::runtime::Value::Instance(_class_name, _fields, methods) => {
-                            if let Some(func) = methods.get(method_name) {
-                                match func {
-                                    crate::runtime::Function::Native(nf) => {
-                                        // For native methods, prepend self as the first argument
-                                        let mut method_args = vec![obj_val.clone()];
-                                        method_args.extend(args_vec);
-                                        return nf(method_args);
-                                    }
-                                    crate::runtime::Function::User { params, body } => {
-                                        // create child env, bind self and params
-                                        let mut child = Env::new();
+        // method call: obj.method(...)
+        if let Some((obj_name, method_name)) = split_method(fname) {
+            let obj_name = obj_name.trim();
+            let method_name = method_name.trim();
+            // a list in a local variable is changed in place, so `xs.append(v)`
+            // doesn't copy the whole list
+            if let Some(crate::runtime::Value::List(items)) = env.get_local_mut(obj_name) {
+                reject_kwargs(&kwargs, method_name)?;
+                return crate::stdlib::listmod::call_method(items, method_name, args_vec);
+            }
+            // receivers that aren't variables (e.g. "a b".upper()) are evaluated
+            let receiver = match env.get(obj_name) {
+                Some(v) => Some(v.clone()),
+                None if !is_identifier(obj_name) => Some(eval_expr(obj_name, env)?),
+                None => None,
+            };
+            if let Some(obj_val) = receiver {
+                match obj_val.clone() {
+                    crate::runtime::Value::Str(text) => {
+                        reject_kwargs(&kwargs, method_name)?;
+                        return crate::stdlib::strmod::call_method(&text, method_name, args_vec);
+                    }
+                    crate::runtime::Value::Bytes(bytes) => {
+                        reject_kwargs(&kwargs, method_name)?;
+                        return crate::stdlib::strmod::call_bytes_method(&bytes, method_name, args_vec);
+                    }
+                    crate::runtime::Value::List(mut items) => {
+                        reject_kwargs(&kwargs, method_name)?;
+                        let result = crate::stdlib::listmod::call_method(&mut items, method_name, args_vec)?;
+                        // lists are values: store the changed copy back where it came from
+                        if crate::stdlib::listmod::is_mutating(method_name) && is_assignable(obj_name) {
+                            assign(obj_name, crate::runtime::Value::List(items), env)?;
+                        }
+                        return Ok(result);
+                    }
+                    crate::runtime::Value::Instance(_class_name, _fields, methods) => {
+                        if let Some(func) = methods.get(method_name) {
+                            match func {
+                                crate::runtime::Function::Native(nf) => {
+                                    reject_kwargs(&kwargs, method_name)?;
+                                    // For native methods, prepend self as the first argument
+                                    let mut method_args = vec![obj_val.clone()];
+                                    method_args.extend(args_vec);
+                                    return nf(method_args);
+                                }
+                                crate::runtime::Function::NativeEnv(nf) => {
+                                    reject_kwargs(&kwargs, method_name)?;
+                                    let mut method_args = vec![obj_val.clone()];
+                                    method_args.extend(args_vec);
+                                    return nf(env, method_args);
+                                }
+                                crate::runtime::Function::User { params, body } => {
+                                    // call env with self and params bound
+                                    return env.with_call_env(None, |child| {
                                         child.set("self".to_string(), obj_val.clone());
-                                        for (i, p) in params.iter().enumerate() {
-                                            if p == "self" { continue; }
-                                            if let Some(a) = args_vec.get(i) {
-                                                child.set(p.clone(), a.clone());
-                                            } else {
-                                                child.set(p.clone(), crate::runtime::Value::None);
-                                            }
-                                        }
-                                        crate::eval::run_stmts(body, &mut child)?;
-                                        return Ok(crate::runtime::Value::None);
-                                    }
+                                        bind_params(child, params.iter().filter(|(p, _)| p != "self"), args_vec, kwargs)?;
+                                        run_function_body(body, child)
+                                    });
                                 }
-                            } else {
-                                return Err(anyhow!("Method not found: {}", method_name));
+                                crate::runtime::Function::Partial { .. } => return env.call_function_kw(&func.clone(), args_vec, kwargs),
                             }
+                        } else {
+                            return Err(anyhow!("Method not found: {}", method_name));
                         }
-                        crate::runtime::Value::Class(_class_name, methods) => {
-                            // calling class method statically or constructing? try methods
-                            if let Some(func) = methods.get(method_name) {
-                                match func {
-                                    crate::runtime::Function::Native(nf) => return nf(args_vec),
-                                    crate::runtime::Function::User { params, body } => {
-                                        let mut child = Env::new();
-                                        for (i, p) in params.iter().enumerate() {
-                                            if let Some(a) = args_vec.get(i) {
-                                                child.set(p.clone(), a.clone());
-                                            }
-                                        }
-                                        crate::eval::run_stmts(body, &mut child)?;
-                                        return Ok(crate::runtime::Value::None);
-                                    }
+                    }
+                    crate::runtime::Value::Class(_class_name, methods) => {
+                        // calling class method statically or constructing? try methods
+                        if let Some(func) = methods.get(method_name) {
+                            match func {
+                                crate::runtime::Function::User { params, body } => {
+                                    return env.with_call_env(None, |child| {
+                                        bind_params(child, params.iter(), args_vec, kwargs)?;
+                                        run_function_body(body, child)
+                                    });
                                 }
+                                _ => return env.call_function_kw(&func.clone(), args_vec, kwargs),
                             }
                         }
-                        crate::runtime::Value::Dict(map) => {
-                            // allow module-like dicts to expose functions by name (string ref)
-                            if let Some(val) = map.get(method_name) {
-                                match val.clone() {
-                                    crate::runtime::Value::Str(ref fname) => {
-                                        // call the global function with that name
-                                        return env.call(&fname, args_vec);
-                                    }
-                                    other => {
-                                        // if it's a stored callable-like instance, try to call via env.call
-                                        if let crate::runtime::Value::Str(fname) = other {
-                                            return env.call(&fname, args_vec);
-                                        }
-                                    }
-                                }
+                    }
+                    crate::runtime::Value::Dict(mut map) => {
+                        // module-like dicts expose functions by name (string ref)
+                        if let Some(crate::runtime::Value::Str(func_name)) = map.get(method_name) {
+                            if env.get_func(func_name).is_some() {
+                                return env.call_kw(func_name, args_vec, kwargs);
                             }
                         }
-                        _ => {}
+                        reject_kwargs(&kwargs, method_name)?;
+                        let result = crate::stdlib::dictmod::call_method(&mut map, method_name, args_vec)?;
+                        if crate::stdlib::dictmod::is_mutating(method_name) && is_assignable(obj_name) {
+                            assign(obj_name, crate::runtime::Value::Dict(map), env)?;
+                        }
+                        return Ok(result);
                     }
+                    _ => {}
                 }
-                return Err(anyhow!("Undefined method call: {}", fname));
             }
-
-            // regular function
-            return env.call(fname, args_vec);
+            return Err(anyhow!("Undefined method call: {}", fname));
         }
+
+        // regular function
+        return env.call_kw(fname, args_vec, kwargs);
     }
 
     // identifier
     if let Some(v) = env.get(s) {
         return Ok(v.clone());
     }
+    // a bare function name evaluates to the function itself
+    if let Some(f) = env.get_func(s) {
+        return Ok(crate::runtime::Value::Func(s.to_string(), f.clone()));
+    }
 
     Err(anyhow!("Unknown expression: {}", s))
 }
+
+
+/// Parse the parameter list of a `def` header (the text after the opening
+/// paren) into names and optional default expressions.
+pub fn parse_params(args: &str) -> Result<Vec<Param>> {
+    let args = args.trim_end();
+    let args = args.strip_suffix(')').unwrap_or(args);
+    let mut params: Vec<Param> = Vec::new();
+    for part in split_top_level_commas(args) {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let (name, default) = match part.split_once('=') {
+            Some((name, default)) => (name.trim(), Some(default.trim().to_string())),
+            None => (part, None),
+        };
+        if !is_identifier(name) {
+            return Err(anyhow!("Invalid parameter: {}", part));
+        }
+        if default.is_none() && params.iter().any(|(_, d)| d.is_some()) {
+            return Err(anyhow!("Parameter '{}' without a default follows one with a default", name));
+        }
+        params.push((name.to_string(), default));
+    }
+    Ok(params)
+}
+
+/// Bind call arguments to `params` in `env`: positional arguments first, then
+/// keyword arguments by name. A parameter with neither gets its default,
+/// evaluated in `env` so it can use earlier parameters, or None when it has
+/// no default.
+pub fn bind_params<'a>(
+    env: &mut Env,
+    params: impl Iterator<Item = &'a Param>,
+    args: Vec<crate::runtime::Value>,
+    mut kwargs: Vec<(String, crate::runtime::Value)>,
+) -> Result<()> {
+    let mut args = args.into_iter();
+    for (name, default) in params {
+        let keyword = kwargs.iter().position(|(k, _)| k == name).map(|i| kwargs.remove(i).1);
+        let value = match (args.next(), keyword, default) {
+            (Some(_), Some(_), _) => return Err(anyhow!("Got multiple values for argument '{}'", name)),
+            (Some(v), None, _) | (None, Some(v), _) => v,
+            (None, None, Some(expr)) => eval_expr(expr, env)?,
+            (None, None, None) => crate::runtime::Value::None,
+        };
+        env.set(name.clone(), value);
+    }
+    if let Some((name, _)) = kwargs.first() {
+        return Err(anyhow!("Unexpected keyword argument '{}'", name));
+    }
+    Ok(())
+}
+
+fn reject_kwargs(kwargs: &[(String, crate::runtime::Value)], method: &str) -> Result<()> {
+    match kwargs.first() {
+        Some((name, _)) => Err(anyhow!("{}() takes no keyword arguments (got '{}')", method, name)),
+        None => Ok(()),
+    }
+}
+
+/// Split one call argument into `(Some(name), value)` for `name=value`, or
+/// `(None, arg)` for a positional argument. `==`, `<=` etc. are comparisons.
+fn split_keyword_arg(arg: &str) -> (Option<&str>, &str) {
+    for i in top_level_indices(arg) {
+        if arg.as_bytes()[i] == b'=' {
+            let name = arg[..i].trim();
+            if is_identifier(name) && !arg[i + 1..].starts_with('=') {
+                return (Some(name), arg[i + 1..].trim());
+            }
+            break;
+        }
+    }
+    (None, arg)
+}
+
+/// Run a function or method body and produce its result: the value of the
+/// `return` that ended it, or None when it ran off the end.
+pub fn run_function_body(body: &[Stmt], env: &mut Env) -> Result<crate::runtime::Value> {
+    env.check_call_depth()?;
+    // each Ferrum call takes several native frames; grow the stack on demand
+    // so deep (but allowed) recursion can't overflow it
+    match stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || run_stmts(body, env))? {
+        Flow::Return(v) => Ok(v),
+        Flow::Normal => Ok(crate::runtime::Value::None),
+        Flow::Break => Err(anyhow!("'break' outside loop")),
+        Flow::Continue => Err(anyhow!("'continue' outside loop")),
+    }
+}
+
+/// Remaining native stack below which a call continues on a new segment
+const STACK_RED_ZONE: usize = 256 * 1024;
+/// Size of each extra stack segment
+const STACK_SEGMENT: usize = 8 * 1024 * 1024;
+
+/// Error message that ends iteration when raised by `__next__`
+pub const STOP_ITERATION: &str = "StopIteration";
+
+fn is_stop_iteration(e: &anyhow::Error) -> bool {
+    e.to_string().starts_with(STOP_ITERATION)
+}
+
+fn has_method(obj: &crate::runtime::Value, name: &str) -> bool {
+    matches!(obj, crate::runtime::Value::Instance(_, _, methods) if methods.contains_key(name))
+}
+
+/// Format a value for display, calling `__str__` on instances that define
+/// it, including instances nested inside lists.
+pub fn format_value(value: &crate::runtime::Value, env: &mut Env) -> Result<String> {
+    use crate::runtime::Value;
+
+    match value {
+        Value::Instance(..) if has_method(value, "__str__") => {
+            Ok(invoke_method(env, value, "__str__", Vec::new())?.0.to_string())
+        }
+        Value::List(items) => {
+            let parts = items.iter().map(|v| format_value(v, env)).collect::<Result<Vec<_>>>()?;
+            Ok(format!("[{}]", parts.join(", ")))
+        }
+        other => Ok(other.to_string()),
+    }
+}
+
+/// Call method `name` on instance `obj`, returning the result together with
+/// the instance as the method left it, so state changes made through `self`
+/// survive between calls.
+fn invoke_method(env: &mut Env, obj: &crate::runtime::Value, name: &str, args: Vec<crate::runtime::Value>) -> Result<(crate::runtime::Value, crate::runtime::Value)> {
+    let func = match obj {
+        crate::runtime::Value::Instance(_, _, methods) => methods.get(name).cloned(),
+        _ => None,
+    };
+    let func = func.ok_or_else(|| anyhow!("Method not found: {}", name))?;
+    match func {
+        Function::User { params, body } => env.with_call_env(None, |child| {
+            child.set("self".to_string(), obj.clone());
+            bind_params(child, params.iter().filter(|(p, _)| p != "self"), args, Vec::new())?;
+            let result = run_function_body(&body, child)?;
+            let updated = child.get("self").cloned().unwrap_or_else(|| obj.clone());
+            Ok((result, updated))
+        }),
+        Function::Partial { .. } => Ok((env.call_function(&func, args)?, obj.clone())),
+        native => {
+            let mut method_args = vec![obj.clone()];
+            method_args.extend(args);
+            Ok((env.call_function(&native, method_args)?, obj.clone()))
+        }
+    }
+}
+
+/// True when a function body yields, making the function a generator.
+/// Nested function and class definitions are not searched.
+pub fn contains_yield(stmts: &[Stmt]) -> bool {
+    stmts.iter().any(|s| match s {
+        Stmt::Yield(_) => true,
+        Stmt::If(branches, else_block) => {
+            branches.iter().any(|(_, body)| contains_yield(body)) || contains_yield(else_block)
+        }
+        Stmt::Else(body) | Stmt::While(_, body) | Stmt::For(_, body) | Stmt::Block(_, body) => contains_yield(body),
+        Stmt::Try { body, except_body, finally_body, .. } => {
+            contains_yield(body) || contains_yield(except_body) || contains_yield(finally_body)
+        }
+        _ => false,
+    })
+}
+
+/// Tracks whether a left-to-right scan is inside a string literal. Backslash
+/// escapes are honoured, so `"a\\"` ends at its last quote.
+#[derive(Default)]
+pub(crate) struct QuoteScan {
+    in_quote: bool,
+    escaped: bool,
+}
+
+impl QuoteScan {
+    /// Feed the next character; true when it belongs to a string literal,
+    /// including the quotes themselves.
+    pub(crate) fn step(&mut self, c: char) -> bool {
+        if self.in_quote {
+            if self.escaped {
+                self.escaped = false;
+            } else if c == '\\' {
+                self.escaped = true;
+            } else if c == '"' {
+                self.in_quote = false;
+            }
+            true
+        } else {
+            self.in_quote = c == '"';
+            self.in_quote
+        }
+    }
+}
+
+/// Translate the escape sequences `\n`, `\t`, `\r`, `\\`, `\"` and `\0` in the
+/// body of a string literal. Other backslashes are kept as written.
+///
+/// Escapes are processed exactly once, when the literal is evaluated. Anything
+/// that later interpolates into a string (such as a future f-string) must work
+/// on the unescaped text rather than unescaping again.
+pub fn unescape(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('0') => out.push('\0'),
+            Some('\\') => out.push('\\'),
+            Some('"') => out.push('"'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Byte offsets of the characters in `s` that sit at bracket depth 0 and
+/// outside string literals. Opening brackets themselves are included.
+fn top_level_indices(s: &str) -> Vec<usize> {
+    let mut out = Vec::new();
+    let mut depth = 0usize;
+    let mut quotes = QuoteScan::default();
+    for (i, c) in s.char_indices() {
+        if quotes.step(c) { continue; }
+        match c {
+            '(' | '[' | '{' => {
+                if depth == 0 { out.push(i); }
+                depth += 1;
+            }
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => out.push(i),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Split a comma-separated list (call arguments or literal elements) at its
+/// top-level commas, ignoring commas nested in brackets or strings. Blank
+/// entries, such as after a trailing comma, are dropped.
+pub(crate) fn split_top_level_commas(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in top_level_indices(s) {
+        if s[i..].starts_with(',') {
+            parts.push(s[start..i].trim());
+            start = i + 1;
+        }
+    }
+    parts.push(s[start..].trim());
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+/// Split at the first top-level keyword operator such as `and`, which must
+/// be surrounded by whitespace or brackets rather than part of a name.
+fn split_top_level_word<'a>(s: &'a str, word: &str) -> Option<(&'a str, &'a str)> {
+    let is_name = |c: char| c.is_alphanumeric() || c == '_';
+    for i in top_level_indices(s) {
+        if !s[i..].starts_with(word) || i == 0 {
+            continue;
+        }
+        let before = s[..i].chars().next_back();
+        let after = s[i + word.len()..].chars().next();
+        if before.is_some_and(|c| !is_name(c) && c != '.') && after.is_some_and(|c| !is_name(c)) {
+            return Some((s[..i].trim(), s[i + word.len()..].trim()));
+        }
+    }
+    None
+}
+
+/// Split at the first top-level comparison operator, returning the operator
+fn split_comparison(s: &str) -> Option<(&str, &'static str, &str)> {
+    const OPS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+    const WORDS: [&str; 2] = ["not in", "in"];
+    let is_name = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+    for i in top_level_indices(s) {
+        if let Some(op) = OPS.iter().find(|op| s[i..].starts_with(**op)) {
+            return Some((s[..i].trim(), op, s[i + op.len()..].trim()));
+        }
+        // membership words need a left operand and must stand apart from names
+        let word = WORDS.iter().find(|w| {
+            s[i..].starts_with(**w)
+                && s[..i].chars().next_back().is_some_and(|c| !is_name(c))
+                && s[i + w.len()..].chars().next().is_some_and(|c| !is_name(c))
+        });
+        if let Some(word) = word {
+            if !s[..i].trim().is_empty() {
+                return Some((s[..i].trim(), word, s[i + word.len()..].trim()));
+            }
+        }
+    }
+    None
+}
+
+/// Membership for `in`: an equal element of a list, a substring of a
+/// string, a key of a dict or an integer inside a range
+fn contains(container: &crate::runtime::Value, item: &crate::runtime::Value) -> Result<bool> {
+    use crate::runtime::Value;
+
+    match (container, item) {
+        (Value::List(items), _) => Ok(items.contains(item)),
+        (Value::Str(text), Value::Str(sub)) => Ok(text.contains(sub.as_str())),
+        (Value::Dict(map), Value::Str(key)) => Ok(map.contains_key(key)),
+        (Value::Dict(_), _) => Ok(false),
+        (Value::Range(start, end), Value::Int(i)) => Ok(start <= i && i < end),
+        (Value::Range(..), _) => Ok(false),
+        (Value::Str(_), other) => Err(anyhow!("'in <string>' requires a string, got {}", other.to_string())),
+        (other, _) => Err(anyhow!("Value {} does not support 'in'", other.to_string())),
+    }
+}
+
+/// Split at the last top-level binary operator among `ops`, so operators of
+/// the same tier associate to the left. A `+` or `-` with no operand before
+/// it (a sign, as in `2 * -3`) or inside a float exponent (`1e-5`) is not a
+/// binary operator. Longer operators must come before their prefixes in `ops`.
+fn split_last_binary<'a>(s: &'a str, ops: &[&'static str]) -> Option<(&'a str, &'static str, &'a str)> {
+    let indices = top_level_indices(s);
+    for (n, &i) in indices.iter().enumerate().rev() {
+        let Some(op) = ops.iter().find(|op| s[i..].starts_with(**op)) else { continue };
+        // either star of `**` belongs to the power tier
+        if *op == "*" && (s[i + 1..].starts_with('*') || s[..i].ends_with('*')) {
+            continue;
+        }
+        // the second character of a two-character operator like `//`
+        if n > 0 && indices[n - 1] == i - 1 && ops.iter().any(|o| o.len() == 2 && s[i - 1..].starts_with(o)) {
+            continue;
+        }
+        let left = s[..i].trim_end();
+        if left.is_empty() || left.ends_with(['+', '-', '*', '/', '%', '<', '>', '=', '!', '(', ',', '[', '{', ':']) {
+            continue;
+        }
+        if matches!(*op, "+" | "-") && is_exponent_prefix(left) {
+            continue;
+        }
+        if [" and", " or", " not"].iter().any(|w| left.ends_with(w)) || left == "not" {
+            continue;
+        }
+        return Some((left, op, s[i + op.len()..].trim()));
+    }
+    None
+}
+
+/// True when `left` ends in the mantissa of a float literal like `1.5e`
+fn is_exponent_prefix(left: &str) -> bool {
+    let start = left.rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_')).map(|i| i + 1).unwrap_or(0);
+    let token = &left[start..];
+    token.starts_with(|c: char| c.is_ascii_digit())
+        && token.ends_with(['e', 'E'])
+        && token[..token.len() - 1].parse::<f64>().is_ok()
+}
+
+/// `base ** exp`: an Int for Int operands with a non-negative exponent,
+/// otherwise a Float.
+fn power(base: crate::runtime::Value, exp: crate::runtime::Value) -> Result<crate::runtime::Value> {
+    use crate::runtime::Value;
+
+    match (base, exp) {
+        (Value::Int(b), Value::Int(e)) if e >= 0 => u32::try_from(e)
+            .ok()
+            .and_then(|e| b.checked_pow(e))
+            .map(Value::Int)
+            .ok_or_else(|| anyhow!("Integer overflow in {} ** {}", b, e)),
+        (Value::Int(b), Value::Int(e)) => Ok(Value::Float((b as f64).powf(e as f64))),
+        (Value::Int(b), Value::Float(e)) => Ok(Value::Float((b as f64).powf(e))),
+        (Value::Float(b), Value::Int(e)) => Ok(Value::Float(b.powf(e as f64))),
+        (Value::Float(b), Value::Float(e)) => Ok(Value::Float(b.powf(e))),
+        (b, e) => Err(anyhow!("Unsupported operand types for **: {} and {}", b.to_string(), e.to_string())),
+    }
+}
+
+/// `lv op rv` for any binary arithmetic operator, including `**`
+fn apply_op(op: &str, lv: crate::runtime::Value, rv: crate::runtime::Value) -> Result<crate::runtime::Value> {
+    match op {
+        "**" => power(lv, rv),
+        _ => binary_op(op, lv, rv),
+    }
+}
+
+/// Apply an arithmetic operator. `+` concatenates when either side is a
+/// string; otherwise mixed Int/Float operands produce a Float.
+fn binary_op(op: &str, lv: crate::runtime::Value, rv: crate::runtime::Value) -> Result<crate::runtime::Value> {
+    use crate::runtime::Value;
+
+    match (op, lv, rv) {
+        ("+", Value::Str(a), Value::Str(b)) => Ok(Value::Str(a + &b)),
+        ("+", Value::Str(a), other) => Ok(Value::Str(a + &other.to_string())),
+        ("+", other, Value::Str(b)) => Ok(Value::Str(other.to_string() + &b)),
+        (_, Value::Int(_), Value::Int(0)) if matches!(op, "/" | "//" | "%") => Err(anyhow!("Division by zero")),
+        (_, Value::Int(a), Value::Int(b)) => {
+            let result = match op {
+                "+" => a.checked_add(b),
+                "-" => a.checked_sub(b),
+                "*" => a.checked_mul(b),
+                "/" => a.checked_div(b),
+                // a remainder means q was truncated towards zero, so q - 1 can't overflow
+                "//" => a.checked_div(b).map(|q| if a % b != 0 && (a < 0) != (b < 0) { q - 1 } else { q }),
+                _ => a.checked_rem(b).map(|r| if r != 0 && (r < 0) != (b < 0) { r + b } else { r }),
+            };
+            result.map(Value::Int).ok_or_else(|| anyhow!("Integer overflow in {} {} {}", a, op, b))
+        }
+        (_, a @ (Value::Int(_) | Value::Float(_)), b @ (Value::Int(_) | Value::Float(_))) => {
+            let a = match a { Value::Int(i) => i as f64, Value::Float(f) => f, _ => unreachable!() };
+            let b = match b { Value::Int(i) => i as f64, Value::Float(f) => f, _ => unreachable!() };
+            if b == 0.0 && matches!(op, "/" | "//" | "%") {
+                return Err(anyhow!("Division by zero"));
+            }
+            Ok(Value::Float(match op {
+                "+" => a + b,
+                "-" => a - b,
+                "*" => a * b,
+                "/" => a / b,
+                "//" => (a / b).floor(),
+                _ => float_mod(a, b),
+            }))
+        }
+        ("+", a, b) => Ok(Value::Str(a.to_string() + &b.to_string())),
+        (_, a, b) => Err(anyhow!("Unsupported operand types for {}: {} and {}", op, a.to_string(), b.to_string())),
+    }
+}
+
+/// Float remainder with the sign of the divisor (Python's `%`)
+fn float_mod(a: f64, b: f64) -> f64 {
+    let r = a % b;
+    if r != 0.0 && (r < 0.0) != (b < 0.0) { r + b } else { r }
+}
+
+/// Position of the `(` opening the argument list when `s` is a call such as
+/// `f(x)` or `obj.method(a).other(b)`, i.e. the last top-level parenthesis
+/// group, which closes at the end of the expression.
+fn call_paren(s: &str) -> Option<usize> {
+    if !s.ends_with(')') {
+        return None;
+    }
+    top_level_indices(s).into_iter().rfind(|&i| s[i..].starts_with('(')).filter(|&i| i > 0)
+}
+
+/// Split a call target like `obj.method` at its last top-level `.`, ignoring
+/// dots inside string literals and brackets.
+fn split_method(fname: &str) -> Option<(&str, &str)> {
+    let mut depth = 0usize;
+    let mut quotes = QuoteScan::default();
+    let mut dot = None;
+    for (i, c) in fname.char_indices() {
+        if quotes.step(c) { continue; }
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '.' if depth == 0 => dot = Some(i),
+            _ => {}
+        }
+    }
+    dot.map(|i| (&fname[..i], &fname[i + 1..]))
+}
+
+/// True for plain names such as `x` or `my_var`
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// True for expressions that `assign` can store into: a name, a single-level
+/// attribute like `self.items`, or a subscript of either
+fn is_assignable(target: &str) -> bool {
+    if let Some((base, _)) = split_subscript(target) {
+        return is_assignable(base.trim());
+    }
+    match target.split_once('.') {
+        Some((obj, attr)) => is_identifier(obj) && is_identifier(attr),
+        None => is_identifier(target),
+    }
+}
+
+/// Split `base[index]` into its base and bracketed parts when the expression
+/// ends with a top-level subscript. Returns `None` for a bare `[...]`.
+fn split_subscript(s: &str) -> Option<(&str, &str)> {
+    if !s.ends_with(']') {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut quotes = QuoteScan::default();
+    let mut open = None;
+    for (i, c) in s.char_indices() {
+        if quotes.step(c) { continue; }
+        match c {
+            '[' => {
+                if depth == 0 { open = Some(i); }
+                depth += 1;
+            }
+            '(' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    let open = open?;
+    let base = s[..open].trim();
+    if base.is_empty() {
+        return None;
+    }
+    Some((base, &s[open + 1..s.len() - 1]))
+}
+
+/// Evaluate `base[index]`, where `index` is either an expression or a
+/// `start:end` slice with optional bounds.
+/// 
+/// Lists, strings (by character), bytes and ranges take integer indices,
+/// with negative indices counting from the end; dicts take string keys.
+/// Ranges are indexed arithmetically without materializing their elements,
+/// and slicing a range returns a narrower range. Slice bounds are clamped
+/// like Python's, while an out-of-bounds index is an error.
+fn subscript(base: crate::runtime::Value, index: &str, env: &mut Env) -> Result<crate::runtime::Value> {
+    use crate::runtime::Value;
+
+    if has_method(&base, "__getitem__") {
+        let key = eval_expr(index, env)?;
+        return Ok(invoke_method(env, &base, "__getitem__", vec![key])?.0);
+    }
+
+    let colon = top_level_indices(index).into_iter().find(|&i| index[i..].starts_with(':'));
+    let Some(colon) = colon else {
+        let key = eval_expr(index, env)?;
+        return get_item(base, key, env);
+    };
+    let len = seq_len(&base)?;
+    let mut bound = |expr: &str| -> Result<Option<i64>> {
+        if expr.trim().is_empty() {
+            return Ok(None);
+        }
+        match eval_expr(expr, env)? {
+            Value::Int(i) => Ok(Some(i)),
+            other => Err(anyhow!("Slice bounds must be integers, got {}", other.to_string())),
+        }
+    };
+    let clamp = |i: i64| if i < 0 { (len + i).max(0) } else { i.min(len) };
+    let lo = bound(&index[..colon])?.map(clamp).unwrap_or(0);
+    let hi = bound(&index[colon + 1..])?.map(clamp).unwrap_or(len).max(lo);
+    let (lo_u, hi_u) = (lo as usize, hi as usize);
+    match base {
+        Value::Range(start, _) => Ok(Value::Range(start + lo, start + hi)),
+        Value::List(items) => Ok(Value::List(items[lo_u..hi_u].to_vec())),
+        Value::Str(text) => Ok(Value::Str(text.chars().skip(lo_u).take(hi_u - lo_u).collect())),
+        Value::Bytes(bytes) => Ok(Value::Bytes(bytes[lo_u..hi_u].to_vec())),
+        other => Err(anyhow!("Value {} cannot be sliced", other.to_string())),
+    }
+}
+
+/// Number of elements a subscript counts from: characters for strings
+fn seq_len(base: &crate::runtime::Value) -> Result<i64> {
+    use crate::runtime::Value;
+
+    Ok(match base {
+        Value::Range(start, end) if end <= start => 0,
+        Value::Range(start, end) => end
+            .checked_sub(*start)
+            .ok_or_else(|| anyhow!("range({}, {}) has more than {} elements", start, end, i64::MAX))?,
+        Value::List(items) => items.len() as i64,
+        Value::Str(text) => text.chars().count() as i64,
+        Value::Bytes(bytes) => bytes.len() as i64,
+        _ => 0,
+    })
+}
+
+/// `base[key]` for an already evaluated key
+fn get_item(base: crate::runtime::Value, key: crate::runtime::Value, env: &mut Env) -> Result<crate::runtime::Value> {
+    use crate::runtime::Value;
+
+    if has_method(&base, "__getitem__") {
+        return Ok(invoke_method(env, &base, "__getitem__", vec![key])?.0);
+    }
+    if let Value::Dict(map) = &base {
+        return match &key {
+            Value::Str(k) => map.get(k).cloned().ok_or_else(|| anyhow!("Key not found: {}", k)),
+            other => Err(anyhow!("Dict keys must be strings, got {}", other.to_string())),
+        };
+    }
+    let len = seq_len(&base)?;
+    let i = match key {
+        Value::Int(i) => i,
+        other => return Err(anyhow!("Indices must be integers, got {}", other.to_string())),
+    };
+    let pos = if i < 0 { len + i } else { i };
+    let in_bounds = pos >= 0 && pos < len;
+    let pos_u = pos as usize;
+    match base {
+        Value::Range(start, _) if in_bounds => Ok(Value::Int(start + pos)),
+        Value::List(items) if in_bounds => Ok(items[pos_u].clone()),
+        Value::Str(text) if in_bounds => Ok(Value::Str(text.chars().nth(pos_u).map(String::from).unwrap_or_default())),
+        Value::Bytes(bytes) if in_bounds => Ok(Value::Int(bytes[pos_u] as i64)),
+        Value::Range(..) | Value::List(_) | Value::Str(_) | Value::Bytes(_) => {
+            Err(anyhow!("Index {} out of range for length {}", i, len))
+        }
+        other => Err(anyhow!("Value {} is not subscriptable", other.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Value;
+
+    fn eval(expr: &str) -> Result<Value> {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        eval_expr_str(expr, &mut env)
+    }
+
+    #[test]
+    fn test_string_method_on_literal() {
+        match eval("\"a\nb\n\".splitlines()").unwrap() {
+            Value::List(lines) => assert!(lines == vec![Value::Str("a".into()), Value::Str("b".into())]),
+            other => panic!("unexpected value: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_string_methods_on_variables() {
+        let mut env = Env::new();
+        let src = "line = \"  Name=Ferrum  \"\nparts = line.strip().split(\"=\")\nkey = parts[0].lower()\npos = line.find(\"=\")\nshout = parts[1].upper().replace(\"RR\", \"R\")";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert!(matches!(env.get("key"), Some(Value::Str(s)) if s == "name"));
+        assert!(matches!(env.get("pos"), Some(Value::Int(6))));
+        assert!(matches!(env.get("shout"), Some(Value::Str(s)) if s == "FERUM"));
+    }
+
+    #[test]
+    fn test_list_methods_update_receiver() {
+        let mut env = Env::new();
+        let src = "xs = [3, 1]\nxs.append(2)\nlast = xs.pop()\nxs.append(5)\nxs.sort()\nd = {\"k\": []}\nd[\"k\"].append(1)\nclass Bag:\n    def __init__(self):\n        self.items = []\n    def add(self, x):\n        self.items.append(x)\n        return self.items\nb = Bag()\nin_bag = b.add(7)\ntemp = [2, 1].pop()";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert_eq!(env.get("xs"), Some(&Value::List(vec![Value::Int(1), Value::Int(3), Value::Int(5)])));
+        assert!(matches!(env.get("last"), Some(Value::Int(2))));
+        assert!(matches!(env.get("d"), Some(Value::Dict(m)) if m.get("k") == Some(&Value::List(vec![Value::Int(1)]))));
+        assert_eq!(env.get("in_bag"), Some(&Value::List(vec![Value::Int(7)])));
+        assert!(matches!(env.get("temp"), Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn test_list_method_in_function_changes_only_local_copy() {
+        let mut env = Env::new();
+        let src = "xs = [1]\ndef f():\n    xs.append(2)\n    return xs\ndef g():\n    ys = []\n    for i in [0, 1, 2]:\n        ys.append(i)\n    return ys\nr = f()\nbuilt = g()";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        // the global is read through the parent scope, and assignment stays local
+        assert_eq!(env.get("xs"), Some(&Value::List(vec![Value::Int(1)])));
+        assert_eq!(env.get("r"), Some(&Value::List(vec![Value::Int(1), Value::Int(2)])));
+        assert_eq!(env.get("built"), Some(&Value::List(vec![Value::Int(0), Value::Int(1), Value::Int(2)])));
+    }
+
+    #[test]
+    fn test_dict_methods_and_iteration() {
+        let mut env = Env::new();
+        let src = "d = {\"b\": 2, \"a\": 1}\nd.update({\"c\": 3})\norder = \"\"\nfor k in d:\n    order += k\nks = d.keys()\nvs = d.values()\nfirst = d.items()[0]\nmissing = d.get(\"z\", 0)\nfound = d.get(\"a\")";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert!(matches!(env.get("order"), Some(Value::Str(s)) if s == "abc"));
+        assert_eq!(env.get("vs"), Some(&Value::List(vec![Value::Int(1), Value::Int(2), Value::Int(3)])));
+        assert_eq!(env.get("first"), Some(&Value::List(vec![Value::Str("a".into()), Value::Int(1)])));
+        assert!(matches!(env.get("ks"), Some(Value::List(ks)) if ks.len() == 3));
+        assert!(matches!(env.get("missing"), Some(Value::Int(0))));
+        assert!(matches!(env.get("found"), Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn test_modulo_and_floor_division() {
+        assert!(matches!(eval("7 % 3").unwrap(), Value::Int(1)));
+        assert!(matches!(eval("-7 % 3").unwrap(), Value::Int(2)));
+        assert!(matches!(eval("7.5 % 2.0").unwrap(), Value::Float(f) if f == 1.5));
+        assert!(matches!(eval("7 // 2").unwrap(), Value::Int(3)));
+        assert!(matches!(eval("-7 // 2").unwrap(), Value::Int(-4)));
+        assert!(matches!(eval("7.0 // 2").unwrap(), Value::Float(f) if f == 3.0));
+        assert!(eval("7 % 0").is_err());
+        assert!(eval("7 // 0").is_err());
+    }
+
+    #[test]
+    fn test_for_loop_uses_iterator_protocol() {
+        let mut env = Env::new();
+        let src = "class Countdown:\n    def __init__(self, start):\n        self.n = start\n    def __iter__(self):\n        return self\n    def __next__(self):\n        if self.n <= 0:\n            raise StopIteration\n        self.n = self.n - 1\n        return self.n + 1\ntotal = 0\nfor i in Countdown(3):\n    total = total * 10 + i\nclass Once:\n    def __init__(self):\n        self.done = false\n    def __next__(self):\n        if self.done:\n            raise StopIteration(\"exhausted\")\n        self.done = true\n        return \"x\"\nseen = \"\"\nfor s in Once():\n    seen += s";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert!(matches!(env.get("total"), Some(Value::Int(321))));
+        assert!(matches!(env.get("seen"), Some(Value::Str(s)) if s == "x"));
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert!(matches!(eval("1 + 1 == 2").unwrap(), Value::Bool(true)));
+        assert!(matches!(eval("\"a\" != \"b\"").unwrap(), Value::Bool(true)));
+        assert!(matches!(eval("3 <= 2.5").unwrap(), Value::Bool(false)));
+        assert!(matches!(eval("2 > 1").unwrap(), Value::Bool(true)));
+        assert!(eval("1 < \"a\"").is_err());
+    }
+
+    #[test]
+    fn test_logical_operators_short_circuit() {
+        let mut env = Env::new();
+        env.set("x".to_string(), Value::Int(5));
+        assert!(matches!(eval_expr_str("x > 0 and x < 10", &mut env).unwrap(), Value::Bool(true)));
+        assert!(matches!(eval_expr_str("x < 0 or x == 5", &mut env).unwrap(), Value::Bool(true)));
+        assert!(matches!(eval_expr_str("not x == 5", &mut env).unwrap(), Value::Bool(false)));
+        assert!(matches!(eval_expr_str("false and undefined_func()", &mut env).unwrap(), Value::Bool(false)));
+        assert!(matches!(eval_expr_str("true or undefined_func()", &mut env).unwrap(), Value::Bool(true)));
+        assert!(eval_expr_str("true and undefined_func()", &mut env).is_err());
+        // names containing the keywords are left alone
+        env.set("order".to_string(), Value::Int(1));
+        env.set("android".to_string(), Value::Int(0));
+        assert!(matches!(eval_expr_str("order", &mut env).unwrap(), Value::Int(1)));
+        assert!(matches!(eval_expr_str("android or order", &mut env).unwrap(), Value::Int(1)));
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        assert!(matches!(eval("2+3*4").unwrap(), Value::Int(14)));
+        assert!(matches!(eval("10-2-3").unwrap(), Value::Int(5)));
+        assert!(matches!(eval("2*3+4*5").unwrap(), Value::Int(26)));
+        assert!(matches!(eval("100 / 10 / 5").unwrap(), Value::Int(2)));
+        assert!(matches!(eval("(2 + 3) * 4").unwrap(), Value::Int(20)));
+        assert!(matches!(eval("2 * -3").unwrap(), Value::Int(-6)));
+        assert!(matches!(eval("1e-2 + 1").unwrap(), Value::Float(f) if f == 1.01));
+        assert!(matches!(eval("1 + 2 == 3").unwrap(), Value::Bool(true)));
+    }
+
+    #[test]
+    fn test_unary_minus() {
+        assert!(matches!(eval("-5").unwrap(), Value::Int(-5)));
+        assert!(matches!(eval("3 * -2").unwrap(), Value::Int(-6)));
+        assert!(matches!(eval("-(2+3)").unwrap(), Value::Int(-5)));
+        assert!(matches!(eval("--4").unwrap(), Value::Int(4)));
+        assert!(matches!(eval("(-2)").unwrap(), Value::Int(-2)));
+        assert!(matches!(eval("-2.5 * 2").unwrap(), Value::Float(f) if f == -5.0));
+        assert!(matches!(eval("10 - -3").unwrap(), Value::Int(13)));
+        assert!(matches!(eval("+7").unwrap(), Value::Int(7)));
+        let mut env = Env::new();
+        env.set("x".to_string(), Value::Float(1.5));
+        assert!(matches!(eval_expr_str("-x", &mut env).unwrap(), Value::Float(f) if f == -1.5));
+        assert!(eval("-\"text\"").is_err());
+    }
+
+    #[test]
+    fn test_power_operator() {
+        assert!(matches!(eval("2 ** 10").unwrap(), Value::Int(1024)));
+        assert!(matches!(eval("2 ** 0").unwrap(), Value::Int(1)));
+        assert!(matches!(eval("2.0 ** 0.5").unwrap(), Value::Float(f) if f == 2f64.sqrt()));
+        assert!(matches!(eval("2 ** -1").unwrap(), Value::Float(f) if f == 0.5));
+        assert!(matches!(eval("2 ** 3 ** 2").unwrap(), Value::Int(512)));
+        assert!(matches!(eval("-2 ** 2").unwrap(), Value::Int(-4)));
+        assert!(matches!(eval("3 * 2 ** 2").unwrap(), Value::Int(12)));
+        assert!(eval("2 ** 100").is_err());
+    }
+
+    #[test]
+    fn test_membership_operators() {
+        assert!(matches!(eval("2 in [1, 2, 3]").unwrap(), Value::Bool(true)));
+        assert!(matches!(eval("\"x\" not in [\"a\", \"b\"]").unwrap(), Value::Bool(true)));
+        assert!(matches!(eval("\"ell\" in \"hello\"").unwrap(), Value::Bool(true)));
+        assert!(matches!(eval("\"z\" not in \"hello\"").unwrap(), Value::Bool(true)));
+        assert!(matches!(eval("\"k\" in {\"k\": 1}").unwrap(), Value::Bool(true)));
+        assert!(matches!(eval("\"j\" not in {\"k\": 1}").unwrap(), Value::Bool(true)));
+        assert!(matches!(eval("[1, 2] in [[1, 2], [3]]").unwrap(), Value::Bool(true)));
+        assert!(matches!(eval("not 4 in range(4)").unwrap(), Value::Bool(true)));
+        assert!(matches!(eval("1 in [1] and \"in\" in \"inside\"").unwrap(), Value::Bool(true)));
+        assert!(eval("1 in 5").is_err());
+    }
+
+    #[test]
+    fn test_ternary_expression() {
+        assert!(matches!(eval("1 if True else 2").unwrap(), Value::Int(1)));
+        assert!(matches!(eval("1 if 3 < 2 else 2").unwrap(), Value::Int(2)));
+        assert!(matches!(eval("\"a\" if False else \"b\" if True else \"c\"").unwrap(), Value::Str(s) if s == "b"));
+        assert!(matches!(eval("(1 if False else 5) * 2").unwrap(), Value::Int(10)));
+        // the branch not taken is never evaluated
+        assert!(matches!(eval("0 if True else 1 // 0").unwrap(), Value::Int(0)));
+        assert!(eval("1 if True").is_err());
+    }
+
+    #[test]
+    fn test_tuple_expressions_and_unpacking() {
+        assert!(eval("1, 2 + 3").unwrap() == Value::List(vec![Value::Int(1), Value::Int(5)]));
+        assert!(eval("(1, \"a, b\")").unwrap() == Value::List(vec![Value::Int(1), Value::Str("a, b".into())]));
+
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        crate::parser::parse_and_run("point = [3, 4]\nx, y = point\na, b = 1, 2\na, b = b, a\nq, r = range(0, 2)", &mut env).unwrap();
+        assert!(matches!(env.get("x"), Some(Value::Int(3))));
+        assert!(matches!(env.get("y"), Some(Value::Int(4))));
+        assert!(matches!(env.get("a"), Some(Value::Int(2))));
+        assert!(matches!(env.get("b"), Some(Value::Int(1))));
+        assert!(matches!(env.get("r"), Some(Value::Int(1))));
+
+        let err = crate::parser::parse_and_run("a, b = [1, 2, 3]", &mut env).unwrap_err();
+        assert!(err.to_string().contains("Expected 2 values"), "{}", err);
+        assert!(crate::parser::parse_and_run("a, b += 1", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_generator_yields_values() {
+        let mut env = Env::new();
+        let src = "def gen(items):\n    yield 1\n    for i in items:\n        yield i * 10\n    yield\nr = range(0, 3)\ntotal = 0\ncount = 0\nfor x in gen(r):\n    count = count + 1\n    if x:\n        total = total + x";
+        crate::stdlib::register_stdlib(&mut env);
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert!(matches!(env.get("count"), Some(Value::Int(5))));
+        assert!(matches!(env.get("total"), Some(Value::Int(31))));
+        assert!(crate::parser::parse_and_run("yield 1", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        assert!(matches!(eval("\"héllo\".encode(\"utf-8\").decode(\"utf-8\")").unwrap(), Value::Str(s) if s == "héllo"));
+        assert!(matches!(eval("\"ab\".encode()").unwrap(), Value::Bytes(b) if b == b"ab"));
+    }
+
+    #[test]
+    fn test_list_string_and_dict_subscripts() {
+        let mut env = Env::new();
+        env.set("xs".to_string(), Value::List(vec![Value::Int(10), Value::Int(20), Value::Int(30)]));
+        let mut d = std::collections::HashMap::new();
+        d.insert("a:b".to_string(), Value::Int(1));
+        env.set("d".to_string(), Value::Dict(d));
+        assert!(matches!(eval_expr_str("xs[0]", &mut env).unwrap(), Value::Int(10)));
+        assert!(matches!(eval_expr_str("xs[-1]", &mut env).unwrap(), Value::Int(30)));
+        assert!(matches!(eval_expr_str("xs[1 + 1]", &mut env).unwrap(), Value::Int(30)));
+        assert!(eval_expr_str("xs[3]", &mut env).is_err());
+        assert!(eval_expr_str("xs[-4]", &mut env).is_err());
+        assert!(matches!(eval_expr_str("xs[1:]", &mut env).unwrap(), Value::List(v) if v.len() == 2));
+        assert!(matches!(eval_expr_str("\"héllo\"[1]", &mut env).unwrap(), Value::Str(c) if c == "é"));
+        assert!(matches!(eval_expr_str("\"hello\"[-3:]", &mut env).unwrap(), Value::Str(c) if c == "llo"));
+        assert!(matches!(eval_expr_str("d[\"a:b\"]", &mut env).unwrap(), Value::Int(1)));
+        assert!(eval_expr_str("d[\"missing\"]", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_list_and_dict_literals() {
+        let list = eval("[1, 2+3, \"x\"]").unwrap();
+        assert!(list == Value::List(vec![Value::Int(1), Value::Int(5), Value::Str("x".into())]));
+        assert!(eval("[]").unwrap() == Value::List(vec![]));
+        let nested = eval("[[1, 2], [\"a, b\"],]").unwrap();
+        assert!(matches!(nested, Value::List(v) if v.len() == 2));
+        match eval("{\"a\": 1, \"b\": 2}").unwrap() {
+            Value::Dict(m) => {
+                assert_eq!(m.len(), 2);
+                assert!(m.get("a") == Some(&Value::Int(1)));
+                assert!(m.get("b") == Some(&Value::Int(2)));
+            }
+            other => panic!("unexpected value: {:?}", other),
+        }
+        assert!(eval("{1: 2}").is_err());
+    }
+
+    #[test]
+    fn test_call_arguments_split_at_top_level() {
+        assert!(matches!(eval("len(\"a, b\")").unwrap(), Value::Int(4)));
+        assert!(matches!(eval("len([1, 2, 3])").unwrap(), Value::Int(3)));
+    }
+
+    #[test]
+    fn test_break_and_continue() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let src = "total = 0\nfor i in range(1, 10):\n    if i == 5:\n        break\n    total += i\nodd = 0\nfor i in range(1, 10):\n    if i % 2 == 0:\n        continue\n    odd += i\nn = 0\nwhile True:\n    n += 1\n    if n >= 3:\n        break";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert!(matches!(env.get("total"), Some(Value::Int(10))));
+        assert!(matches!(env.get("odd"), Some(Value::Int(25))));
+        assert!(matches!(env.get("n"), Some(Value::Int(3))));
+        assert!(crate::parser::parse_and_run("break", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_range_indexing() {
+        assert!(matches!(eval("range(0, 10)[3]").unwrap(), Value::Int(3)));
+        assert!(matches!(eval("range(5, 10)[-1]").unwrap(), Value::Int(9)));
+        assert!(eval("range(0, 10)[10]").is_err());
+        assert!(matches!(eval("range(10, 0)[:]").unwrap(), Value::Range(10, 10)));
+        assert!(matches!(eval("range(1, 9223372036854775807)[-1]").unwrap(), Value::Int(9223372036854775806)));
+        // a length past i64::MAX is an error rather than an overflow
+        assert!(eval("range(0 - 9223372036854775807, 9223372036854775807)[5]").is_err());
+    }
+
+    #[test]
+    fn test_range_slicing() {
+        assert!(matches!(eval("range(0, 10)[2:5]").unwrap(), Value::Range(2, 5)));
+        assert!(matches!(eval("range(10, 20)[:3]").unwrap(), Value::Range(10, 13)));
+        assert!(matches!(eval("range(0, 10)[8:50]").unwrap(), Value::Range(8, 10)));
+    }
+
+    #[test]
+    fn test_integer_overflow_is_an_error() {
+        for expr in [
+            "9223372036854775807 + 1",
+            "(-9223372036854775807 - 1) - 1",
+            "4611686018427387904 * 2",
+            "(-9223372036854775807 - 1) / -1",
+            "(-9223372036854775807 - 1) // -1",
+            "(-9223372036854775807 - 1) % -1",
+        ] {
+            let err = eval(expr).unwrap_err();
+            assert!(err.to_string().starts_with("Integer overflow"), "{}: {}", expr, err);
+        }
+        assert!(matches!(eval("9223372036854775806 + 1").unwrap(), Value::Int(i64::MAX)));
+        assert!(matches!(eval("-7 // 2").unwrap(), Value::Int(-4)));
+        assert!(matches!(eval("-7 % 2").unwrap(), Value::Int(1)));
+    }
+}
@@ -0,0 +1,225 @@
+//! Source formatter behind `ferrum fmt`
+//!
+//! Formatting only touches whitespace: blocks are re-indented with four
+//! spaces per level, spacing around assignment, comparison and arithmetic
+//! operators and commas is normalised, and trailing whitespace is stripped. Comments and
+//! single blank lines are kept. Before returning, the result is parsed and
+//! compared with the original program (via [`crate::parser::unparse`]) so a
+//! formatting bug can never change what a script does.
+
+use anyhow::{anyhow, Result};
+
+use crate::eval::QuoteScan;
+use crate::parser::{parse_to_ast, unparse};
+
+/// Operators that get one space on each side, longest first so `**=` wins
+/// over `*=` and `==` over `=`
+const SPACED_OPS: [&str; 21] = [
+    "**=", "//=", "==", "!=", "<=", ">=", "+=", "-=", "*=", "/=", "%=", "**", "//", "=", "<", ">", "+", "-", "*", "/", "%",
+];
+
+/// Operators that are also prefixes: unary `-`/`+` and `*args`/`**kwargs`
+const PREFIX_OPS: [&str; 4] = ["**", "+", "-", "*"];
+
+/// Keywords after which an operator starts an operand, as in `return -1`
+const OPERAND_KEYWORDS: [&str; 12] = ["return", "and", "or", "not", "in", "is", "if", "elif", "else", "while", "yield", "assert"];
+
+/// Format a whole script
+pub fn format_source(src: &str) -> Result<String> {
+    let mut out: Vec<String> = Vec::new();
+    // source indentation widths of the enclosing blocks, outermost first
+    let mut indents: Vec<usize> = vec![0];
+    for raw in src.lines() {
+        let line = raw.trim_end();
+        if line.trim().is_empty() {
+            // keep single blank lines between statements, never leading or repeated ones
+            if out.last().is_some_and(|l| !l.is_empty()) {
+                out.push(String::new());
+            }
+            continue;
+        }
+        let width = line.len() - line.trim_start_matches(' ').len();
+        let text = line.trim_start();
+        let depth = if text.starts_with('#') {
+            // comments follow the block they sit in without opening a new one
+            indents.iter().rposition(|w| *w <= width).unwrap_or(0)
+        } else {
+            while indents.len() > 1 && indents.last().is_some_and(|w| *w > width) {
+                indents.pop();
+            }
+            if indents.last().is_some_and(|w| *w < width) {
+                indents.push(width);
+            }
+            indents.len() - 1
+        };
+        out.push(format!("{}{}", "    ".repeat(depth), format_line(text)));
+    }
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    let mut formatted = out.join("\n");
+    formatted.push('\n');
+
+    let before = program_shape(src)?;
+    let after = program_shape(&formatted).map_err(|e| anyhow!("formatting produced invalid source: {}", e))?;
+    if before != after {
+        return Err(anyhow!("formatting would change the program; file left unchanged"));
+    }
+    Ok(formatted)
+}
+
+/// Normalise spacing within one line of code, keeping strings and any
+/// trailing comment as written
+pub fn format_line(line: &str) -> String {
+    // module paths may contain `/` or `-`
+    if line.starts_with("import ") {
+        return line.trim_end().to_string();
+    }
+    let mut out = String::new();
+    let mut quotes = QuoteScan::default();
+    let mut depth = 0usize;
+    let mut skip_space = false;
+    let mut i = 0;
+    while i < line.len() {
+        let rest = &line[i..];
+        let c = rest.chars().next().unwrap_or(' ');
+        if quotes.step(c) {
+            out.push(c);
+            skip_space = false;
+            i += c.len_utf8();
+            continue;
+        }
+        if c == '#' {
+            let code = out.trim_end();
+            return if code.is_empty() { rest.to_string() } else { format!("{}  {}", code, rest) };
+        }
+        if c.is_whitespace() {
+            if !skip_space && !out.is_empty() && !out.ends_with(' ') {
+                out.push(' ');
+            }
+            i += c.len_utf8();
+            continue;
+        }
+        skip_space = false;
+        let op = SPACED_OPS.iter().find(|op| rest.starts_with(**op));
+        match (c, op) {
+            // keyword arguments (`f(a=1)`) stay tight; everything else is spaced
+            (_, Some(&"=")) if depth > 0 => {
+                trim_trailing_space(&mut out);
+                out.push('=');
+                skip_space = true;
+                i += 1;
+            }
+            // unary minus, `*args` and the exponent in `1e-5` stay attached
+            (_, Some(op)) if PREFIX_OPS.contains(op) && !follows_operand(&out) => {
+                out.push_str(op);
+                skip_space = true;
+                i += op.len();
+            }
+            (_, Some(op)) => {
+                trim_trailing_space(&mut out);
+                out.push(' ');
+                out.push_str(op);
+                out.push(' ');
+                skip_space = true;
+                i += op.len();
+            }
+            (',', None) => {
+                trim_trailing_space(&mut out);
+                out.push_str(", ");
+                skip_space = true;
+                i += 1;
+            }
+            ('(' | '[' | '{', None) => {
+                depth += 1;
+                out.push(c);
+                skip_space = true;
+                i += 1;
+            }
+            (')' | ']' | '}', None) => {
+                depth = depth.saturating_sub(1);
+                trim_trailing_space(&mut out);
+                out.push(c);
+                i += 1;
+            }
+            (':', None) if rest.len() == 1 => {
+                trim_trailing_space(&mut out);
+                out.push(':');
+                i += 1;
+            }
+            _ => {
+                out.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    out.trim_end().to_string()
+}
+
+/// Whether the code so far ends with a value an operator can apply to, so
+/// `-` is binary after `x` or `)` but unary after `(`, `=` or `return`
+fn follows_operand(out: &str) -> bool {
+    let code = out.trim_end();
+    let Some(last) = code.chars().last() else {
+        return false;
+    };
+    if !(last.is_alphanumeric() || last == '_') {
+        return matches!(last, ')' | ']' | '}' | '"' | '\'');
+    }
+    let start = code.rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.')).map_or(0, |i| i + 1);
+    let word = &code[start..];
+    // `1e` is the start of a number like `1e-5`, not an operand
+    let exponent = word.starts_with(|c: char| c.is_ascii_digit()) && word.ends_with(['e', 'E']);
+    !exponent && !OPERAND_KEYWORDS.contains(&word)
+}
+
+fn trim_trailing_space(out: &mut String) {
+    while out.ends_with(' ') {
+        out.pop();
+    }
+}
+
+/// The program's statements with whitespace outside strings removed, so
+/// two sources compare equal when they differ only in layout
+fn program_shape(src: &str) -> Result<String> {
+    let text = unparse(&parse_to_ast(src)?);
+    let mut shape = String::new();
+    for line in text.lines() {
+        let body = line.trim_start();
+        shape.push_str(&line[..line.len() - body.len()]);
+        let mut quotes = QuoteScan::default();
+        shape.extend(body.chars().filter(|c| quotes.step(*c) || !c.is_whitespace()));
+        shape.push('\n');
+    }
+    Ok(shape)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_line_spacing() {
+        assert_eq!(format_line("x=1+2"), "x = 1 + 2");
+        assert_eq!(format_line("y = a*b-c//2%3"), "y = a * b - c // 2 % 3");
+        assert_eq!(format_line("p=2**-n"), "p = 2 ** -n");
+        assert_eq!(format_line("return -x+f(-1, *args)"), "return -x + f(-1, *args)");
+        assert_eq!(format_line("z = xs[1:-1] - 1e-5"), "z = xs[1:-1] - 1e-5");
+        assert_eq!(format_line("total+=n   # running sum"), "total += n  # running sum");
+        assert_eq!(format_line("if a==b and c!=d :"), "if a == b and c != d:");
+        assert_eq!(format_line("f( a ,b=2 )"), "f(a, b=2)");
+        assert_eq!(format_line("s = \"a=b,  c\"  "), "s = \"a=b,  c\"");
+        assert_eq!(format_line("y=-1"), "y = -1");
+    }
+
+    #[test]
+    fn test_format_reindents_and_is_idempotent() {
+        let src = "# header\ndef  f(x):\n  if x>1 :\n      return x*2   \n  # small\n  return x\n\n\n\nr=f( 3 )\n";
+        let formatted = format_source(src).unwrap();
+        assert_eq!(
+            formatted,
+            "# header\ndef f(x):\n    if x > 1:\n        return x * 2\n    # small\n    return x\n\nr = f(3)\n"
+        );
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+}
@@ -14,7 +14,7 @@
 //! use ferrum::interpreter;
 //!
 //! // Run a Ferrum script
-//! interpreter::run_file("script.fm")?;
+//! interpreter::run_file("script.fm", &[])?;
 //!
 //! // Start interactive REPL
 //! interpreter::repl()?;
@@ -41,11 +41,14 @@
 //! ```
 
 pub mod parser;
+pub mod formatter;
+pub mod coverage;
 pub mod eval;
 pub mod runtime;
 pub mod repl;
 pub mod stdlib;
 pub mod ui;
+pub mod watch;
 
 /// High-level interpreter interface for running Ferrum scripts
 pub mod interpreter {
@@ -56,23 +59,82 @@ pub mod interpreter {
     /// 
     /// # Arguments
     /// * `path` - Path to the `.fm` script file
+    /// * `args` - Arguments for the script, seen as `argv[1:]` and by `parse_args()`
     /// 
     /// # Returns
     /// Ok(()) on successful execution, or an error message
     /// 
     /// # Example
     /// ```ignore
-    /// ferrum::interpreter::run_file("example.fm")?;
+    /// ferrum::interpreter::run_file("example.fm", &["--verbose".to_string()])?;
     /// ```
-    pub fn run_file(path: &str) -> Result<()> {
-        let path = Path::new(path);
-        let src = std::fs::read_to_string(path)?;
-        let mut env = crate::runtime::Env::new();
-        crate::stdlib::register_stdlib(&mut env);
+    pub fn run_file(path: &str, args: &[String]) -> Result<()> {
+        let src = std::fs::read_to_string(Path::new(path))?;
+        let mut env = script_env(path, args);
         crate::parser::parse_and_run(&src, &mut env)?;
         Ok(())
     }
 
+    /// Env with the standard library and the script's command line: `argv`
+    /// holds the script path followed by `args`
+    fn script_env(path: &str, args: &[String]) -> crate::runtime::Env {
+        crate::stdlib::sys::set_script_args(args.to_vec());
+        let mut env = crate::runtime::Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let argv = std::iter::once(path.to_string()).chain(args.iter().cloned());
+        env.set("argv".to_string(), crate::runtime::Value::List(argv.map(crate::runtime::Value::Str).collect()));
+        env
+    }
+
+    /// Run a Ferrum script with profiling on, printing the time spent in each
+    /// user function to stderr when it finishes (or fails)
+    ///
+    /// # Example
+    /// ```ignore
+    /// ferrum::interpreter::profile_file("example.fm", &[])?;
+    /// ```
+    pub fn profile_file(path: &str, args: &[String]) -> Result<()> {
+        let src = std::fs::read_to_string(path)?;
+        let mut env = script_env(path, args);
+        let profiler = env.enable_profiling();
+        let result = crate::parser::parse_and_run(&src, &mut env);
+        eprint!("\n{}", profiler.report());
+        result
+    }
+
+    /// Run a Ferrum script, printing each statement with its line number and
+    /// the values of the variables it uses to stderr just before it runs
+    ///
+    /// # Example
+    /// ```ignore
+    /// ferrum::interpreter::trace_file("example.fm", &[])?;
+    /// ```
+    pub fn trace_file(path: &str, args: &[String]) -> Result<()> {
+        let src = std::fs::read_to_string(path)?;
+        let ast = crate::parser::parse_to_ast_with_lines(&src)?;
+        let mut env = script_env(path, args);
+        env.set_trace_hook(|line| eprintln!("{}", line));
+        crate::parser::run_module(&ast, &mut env)
+    }
+
+    /// Run a Ferrum script while counting executed lines, then print a
+    /// per-line coverage report to stderr, even when the script fails
+    ///
+    /// # Example
+    /// ```ignore
+    /// ferrum::interpreter::coverage_file("example.fm", &[])?;
+    /// ```
+    pub fn coverage_file(path: &str, args: &[String]) -> Result<()> {
+        let src = std::fs::read_to_string(path)?;
+        let ast = crate::parser::parse_to_ast_with_lines(&src)?;
+        let mut env = script_env(path, args);
+        let coverage = env.enable_coverage();
+        let result = crate::parser::run_module(&ast, &mut env);
+        let executable = crate::coverage::executable_lines(&ast.body);
+        eprint!("\n{}", coverage.report(path, &src, &executable));
+        result
+    }
+
     /// Start the interactive REPL (Read-Eval-Print Loop)
     /// 
     /// # Returns
@@ -86,6 +148,25 @@ pub mod interpreter {
         crate::repl::start()
     }
 
+    /// Format a Ferrum script, rewriting it in place or, with `to_stdout`,
+    /// printing the result instead
+    ///
+    /// # Example
+    /// ```ignore
+    /// ferrum::interpreter::format_file("example.fm", false)?;
+    /// ```
+    pub fn format_file(path: &str, to_stdout: bool) -> Result<()> {
+        let src = std::fs::read_to_string(path)?;
+        let formatted = crate::formatter::format_source(&src)?;
+        if to_stdout {
+            print!("{}", formatted);
+        } else if formatted != src {
+            std::fs::write(path, formatted)?;
+            println!("Formatted {}", path);
+        }
+        Ok(())
+    }
+
     /// Build a Ferrum script to bytecode (AST serialization)
     /// 
     /// This is a prototype implementation that serializes the parsed AST
@@ -112,5 +193,21 @@ pub mod interpreter {
         println!("Built {} -> {} (AST debug)", path, out);
         Ok(())
     }
-}
 
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_script_sees_forwarded_argv() {
+            let dir = tempfile::tempdir().unwrap();
+            let script = dir.path().join("args.fm");
+            let script_path = script.to_string_lossy().into_owned();
+            std::fs::write(&script, "assert len(argv) == 3\nassert argv[1] == \"--name\"\nassert argv[2] == \"fm\"\nassert version() != \"\"").unwrap();
+            run_file(&script_path, &["--name".to_string(), "fm".to_string()]).unwrap();
+            assert_eq!(crate::stdlib::sys::script_args(), vec!["--name".to_string(), "fm".to_string()]);
+            // the same script fails without the arguments
+            assert!(run_file(&script_path, &[]).is_err());
+        }
+    }
+}
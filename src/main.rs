@@ -13,11 +13,35 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Run a .fm script
-    Run { file: String },
+    Run {
+        file: String,
+        /// Rerun the script whenever it or a module it imports changes
+        #[arg(long, conflicts_with_all = ["profile", "trace", "coverage"])]
+        watch: bool,
+        /// Print the time spent in each function when the script exits
+        #[arg(long, conflicts_with_all = ["trace", "coverage"])]
+        profile: bool,
+        /// Print each statement with its line number before running it
+        #[arg(long, conflicts_with = "coverage")]
+        trace: bool,
+        /// Report which lines ran, with hit counts, when the script exits
+        #[arg(long)]
+        coverage: bool,
+        /// Arguments passed to the script, available as argv and through parse_args()
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
     /// Start the Ferrum REPL
     Repl,
     /// Build (serialize) a .fm file to bytecode (prototype)
     Build { file: String },
+    /// Reformat a .fm file in place
+    Fmt {
+        file: String,
+        /// Print the formatted source instead of rewriting the file
+        #[arg(long)]
+        stdout: bool,
+    },
 }
 
 #[tokio::main(flavor = "multi_thread")]
@@ -25,9 +49,19 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Run { file } => {
+        Commands::Run { file, watch, profile, trace, coverage, args } => {
             // Blocking run is fine for now; runs on tokio runtime.
-            interpreter::run_file(&file)?;
+            if watch {
+                ferrum::watch::run_watch(&file, &args)?;
+            } else if profile {
+                interpreter::profile_file(&file, &args)?;
+            } else if trace {
+                interpreter::trace_file(&file, &args)?;
+            } else if coverage {
+                interpreter::coverage_file(&file, &args)?;
+            } else {
+                interpreter::run_file(&file, &args)?;
+            }
         }
         Commands::Repl => {
             interpreter::repl()?;
@@ -35,7 +69,23 @@ async fn main() -> Result<()> {
         Commands::Build { file } => {
             interpreter::build(&file)?;
         }
+        Commands::Fmt { file, stdout } => {
+            interpreter::format_file(&file, stdout)?;
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_modes_are_mutually_exclusive() {
+        assert!(Cli::try_parse_from(["ferrum", "run", "--watch", "--coverage", "a.fm"]).is_err());
+        assert!(Cli::try_parse_from(["ferrum", "run", "--profile", "--trace", "a.fm"]).is_err());
+        assert!(Cli::try_parse_from(["ferrum", "run", "--coverage", "a.fm", "x"]).is_ok());
+        assert!(Cli::try_parse_from(["ferrum", "run", "--trace", "a.fm"]).is_ok());
+    }
+}
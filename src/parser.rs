@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
@@ -17,9 +17,10 @@ pub struct ModuleAST {
 /// 
 /// This is a compact prototype parser and supports a subset of Ferrum syntax:
 /// - Class and function definitions
-/// - Control flow (if/elif/else, while, for)
-/// - Variable assignments and expressions
+/// - Control flow (if/elif/else, while, for, break, continue)
+/// - Variable assignments (including `+=`, `-=`, `*=`, `/=`) and expressions
 /// - Print statements
+/// - Generator `yield` statements
 /// - Comments (lines starting with #)
 /// 
 /// # Example
@@ -28,41 +29,101 @@ pub struct ModuleAST {
 /// let ast = parse_to_ast(source)?;
 /// ```
 pub fn parse_to_ast(src: &str) -> Result<ModuleAST> {
-    let mut lines: Vec<(usize, String)> = Vec::new();
-    for raw in src.lines() {
+    parse(src, false)
+}
+
+/// Like [`parse_to_ast`], but each statement is preceded by a
+/// [`Stmt::Line`] marker holding its 1-based source line, for tracing and
+/// coverage. Plain runs skip the markers so they cost nothing.
+pub fn parse_to_ast_with_lines(src: &str) -> Result<ModuleAST> {
+    parse(src, true)
+}
+
+fn parse(src: &str, with_lines: bool) -> Result<ModuleAST> {
+    let mut lines: Vec<SourceLine> = Vec::new();
+    for (n, raw) in src.lines().enumerate() {
         let s = raw.to_string();
         if s.trim().is_empty() {
             continue;
         }
         let indent = s.chars().take_while(|c| *c == ' ').count();
         let trimmed = s[indent..].to_string();
-        lines.push((indent, trimmed));
+        lines.push((indent, trimmed, with_lines.then_some(n + 1)));
     }
 
-    let mut q: VecDeque<(usize, String)> = VecDeque::from(lines);
+    let mut q: VecDeque<SourceLine> = VecDeque::from(lines);
     let body = parse_block(0, &mut q)?;
     Ok(ModuleAST { body })
 }
 
-fn parse_block(min_indent: usize, q: &mut VecDeque<(usize, String)>) -> Result<Vec<Stmt>> {
+/// Indentation, text without the indent, and the line number when the parse
+/// records them
+type SourceLine = (usize, String, Option<usize>);
+
+fn parse_block(min_indent: usize, q: &mut VecDeque<SourceLine>) -> Result<Vec<Stmt>> {
     let mut stmts = Vec::new();
-    while let Some((indent, _line)) = q.front() {
+    while let Some((indent, _line, _)) = q.front() {
         if *indent < min_indent {
             break;
         }
-        let (indent, line) = q.pop_front().unwrap();
+        let (indent, line, lineno) = q.pop_front().unwrap();
         if line.trim_start().starts_with("#") {
             continue;
         }
+        if let Some(n) = lineno {
+            stmts.push(Stmt::Line(n, line.clone()));
+        }
         if line.ends_with(":") {
             let header = line.trim_end_matches(':').to_string();
             let body = parse_block(indent + 1, q)?;
-            let stmt = if header.starts_with("if ") || header.starts_with("elif ") {
-                // We'll create an If with a single branch for simplicity
+            let stmt = if header.starts_with("if ") {
                 let cond = header.splitn(2, ' ').nth(1).unwrap_or("true").to_string();
-                Stmt::If(vec![(cond, body)], vec![])
-            } else if header.starts_with("else") {
-                Stmt::Else(body)
+                let mut branches = vec![(cond, body)];
+                let mut else_block = vec![];
+                // fold following `elif`/`else` clauses at the same indentation into this node
+                while let Some(clause) = next_clause(indent, q) {
+                    if let Some(cond) = clause.strip_prefix("elif ") {
+                        q.pop_front();
+                        branches.push((cond.trim().to_string(), parse_block(indent + 1, q)?));
+                    } else if clause.trim_end() == "else" {
+                        q.pop_front();
+                        else_block = parse_block(indent + 1, q)?;
+                        break;
+                    } else {
+                        break;
+                    }
+                }
+                Stmt::If(branches, else_block)
+            } else if header.starts_with("elif ") || header.trim_end() == "else" {
+                return Err(anyhow!("'{}' without a matching 'if'", header.split_whitespace().next().unwrap_or("")));
+            } else if header.trim_end() == "try" {
+                let mut except_clause = None;
+                let mut finally_body = vec![];
+                while let Some(clause) = next_clause(indent, q) {
+                    if except_clause.is_none() && (clause == "except" || clause.starts_with("except ")) {
+                        q.pop_front();
+                        // `except`, `except Error` or `except Error as e`: only the bound name matters
+                        let name = clause.split_once(" as ").map(|(_, n)| n.trim().to_string());
+                        let except_body = parse_block(indent + 1, q)?;
+                        if except_body.is_empty() {
+                            return Err(anyhow!("expected an indented block after 'except'"));
+                        }
+                        except_clause = Some((name, except_body));
+                    } else if clause.trim_end() == "finally" {
+                        q.pop_front();
+                        finally_body = parse_block(indent + 1, q)?;
+                        break;
+                    } else {
+                        break;
+                    }
+                }
+                if except_clause.is_none() && finally_body.is_empty() {
+                    return Err(anyhow!("'try' without 'except' or 'finally'"));
+                }
+                let (except_name, except_body) = except_clause.unwrap_or_default();
+                Stmt::Try { body, except_name, except_body, finally_body }
+            } else if header == "except" || header.starts_with("except ") || header.trim_end() == "finally" {
+                return Err(anyhow!("'{}' without a matching 'try'", header.split_whitespace().next().unwrap_or("")));
             } else if header.starts_with("while ") {
                 let cond = header.splitn(2, ' ').nth(1).unwrap_or("true").to_string();
                 Stmt::While(cond, body)
@@ -78,41 +139,504 @@ fn parse_block(min_indent: usize, q: &mut VecDeque<(usize, String)>) -> Result<V
                 let rest = header.trim_start_matches("class ").to_string();
                 Stmt::Class(rest, body)
             } else {
-                // Generic block (e.g., try:, except:)
+                // Generic block
                 Stmt::Block(header, body)
             };
             stmts.push(stmt);
         } else {
             // Simple statement line
-            let s = parse_simple_statement(line);
+            let s = parse_simple_statement(line)?;
             stmts.push(s);
         }
     }
     Ok(stmts)
 }
 
-fn parse_simple_statement(line: String) -> Stmt {
+/// Header of the next block line at exactly `indent` (e.g. `elif x`, `else`,
+/// `except`), skipping comment lines, without consuming it.
+fn next_clause(indent: usize, q: &mut VecDeque<SourceLine>) -> Option<String> {
+    while q.front().is_some_and(|(_, l, _)| l.trim_start().starts_with('#')) {
+        q.pop_front();
+    }
+    match q.front() {
+        Some((i, l, _)) if *i == indent && l.ends_with(':') => Some(l.trim_end_matches(':').to_string()),
+        _ => None,
+    }
+}
+
+fn parse_simple_statement(line: String) -> Result<Stmt> {
     let s = line.trim().to_string();
-    if s.starts_with("print(") && s.ends_with(")") {
+    let stmt = if s.starts_with("print(") && s.ends_with(")") {
         let inner = s[6..s.len() - 1].to_string();
         Stmt::Print(inner)
     } else if s.starts_with("return ") {
         let expr = s[7..].to_string();
         Stmt::Return(expr)
-    } else if s.contains('=') {
-        let parts: Vec<&str> = s.splitn(2, '=').collect();
-        let lhs = parts[0].trim().to_string();
-        let rhs = parts[1].trim().to_string();
-        Stmt::Assign(lhs, rhs)
+    } else if let Some(expr) = s.strip_prefix("raise ") {
+        Stmt::Raise(expr.trim().to_string())
+    } else if s == "raise" {
+        return Err(anyhow!("'raise' needs an error or message"));
+    } else if let Some(rest) = s.strip_prefix("assert ") {
+        // `assert cond` or `assert cond, message`
+        match crate::eval::split_top_level_commas(rest).as_slice() {
+            [cond] => Stmt::Assert(cond.trim().to_string(), None),
+            [cond, msg] => Stmt::Assert(cond.trim().to_string(), Some(msg.trim().to_string())),
+            _ => return Err(anyhow!("Invalid assert: {}", s)),
+        }
+    } else if s == "assert" {
+        return Err(anyhow!("'assert' needs a condition"));
+    } else if s == "pass" {
+        Stmt::Pass
+    } else if s == "break" {
+        Stmt::Break
+    } else if s == "continue" {
+        Stmt::Continue
+    } else if s == "yield" || s.starts_with("yield ") {
+        Stmt::Yield(s[5..].trim().to_string())
+    } else if let Some((lhs, op, rhs)) = split_assignment(&s) {
+        let lhs = lhs.trim().to_string();
+        let rhs = rhs.trim();
+        if lhs.is_empty() || rhs.is_empty() {
+            return Err(anyhow!("Incomplete assignment: {}", s));
+        }
+        if op.is_some() && lhs.contains(',') {
+            return Err(anyhow!("Augmented assignment to multiple targets: {}", s));
+        }
+        match op {
+            Some(op) => Stmt::AugAssign(lhs, op.to_string(), rhs.to_string()),
+            None => Stmt::Assign(lhs, rhs.to_string()),
+        }
     } else if s.starts_with("import ") {
         let name = s.trim_start_matches("import ").trim().to_string();
         Stmt::Import(name)
     } else {
         Stmt::Expr(s)
+    };
+    Ok(stmt)
+}
+
+/// Find a top-level assignment `=` (not part of `==`, `!=`, `<=`, `>=` and not
+/// inside brackets or strings). Returns the target, the operator of an
+/// augmented assignment such as `+=` (if any), and the right-hand side.
+fn split_assignment(s: &str) -> Option<(&str, Option<&'static str>, &str)> {
+    const AUGMENTED: [&str; 7] = ["**", "//", "+", "-", "*", "/", "%"];
+    let bytes = s.as_bytes();
+    let mut depth = 0usize;
+    let mut quotes = crate::eval::QuoteScan::default();
+    for (i, c) in s.char_indices() {
+        if quotes.step(c) {
+            continue;
+        }
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '=' if depth == 0 => {
+                let next = bytes.get(i + 1).copied();
+                let prev = if i > 0 { bytes[i - 1] } else { b' ' };
+                if next == Some(b'=') || matches!(prev, b'=' | b'!' | b'<' | b'>') {
+                    continue;
+                }
+                let before = &s[..i];
+                return match AUGMENTED.iter().find(|op| before.ends_with(**op)) {
+                    Some(op) => Some((&before[..before.len() - op.len()], Some(op), &s[i + 1..])),
+                    None => Some((before, None, &s[i + 1..])),
+                };
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Regenerate Ferrum source from a parsed module, indenting nested blocks by
+/// four spaces. Comments and blank lines are not kept, but the output parses
+/// to the same statements.
+pub fn unparse(ast: &ModuleAST) -> String {
+    let mut out = String::new();
+    unparse_block(&ast.body, 0, &mut out);
+    out
+}
+
+fn unparse_block(stmts: &[Stmt], depth: usize, out: &mut String) {
+    if stmts.is_empty() {
+        push_line(out, depth, "pass");
+    }
+    for stmt in stmts {
+        unparse_stmt(stmt, depth, out);
+    }
+}
+
+fn push_line(out: &mut String, depth: usize, line: &str) {
+    out.push_str(&"    ".repeat(depth));
+    out.push_str(line);
+    out.push('\n');
+}
+
+fn unparse_stmt(stmt: &Stmt, depth: usize, out: &mut String) {
+    let block = |header: String, body: &[Stmt], out: &mut String| {
+        push_line(out, depth, &format!("{}:", header));
+        unparse_block(body, depth + 1, out);
+    };
+    match stmt {
+        Stmt::Print(expr) => push_line(out, depth, &format!("print({})", expr)),
+        Stmt::Assign(lhs, rhs) => push_line(out, depth, &format!("{} = {}", lhs, rhs)),
+        Stmt::AugAssign(lhs, op, rhs) => push_line(out, depth, &format!("{} {}= {}", lhs, op, rhs)),
+        Stmt::Expr(expr) => push_line(out, depth, expr),
+        Stmt::If(branches, else_block) => {
+            for (i, (cond, body)) in branches.iter().enumerate() {
+                let keyword = if i == 0 { "if" } else { "elif" };
+                block(format!("{} {}", keyword, cond), body, out);
+            }
+            if !else_block.is_empty() {
+                block("else".to_string(), else_block, out);
+            }
+        }
+        Stmt::Else(body) => block("else".to_string(), body, out),
+        Stmt::While(cond, body) => block(format!("while {}", cond), body, out),
+        Stmt::For(header, body) => block(format!("for {}", header), body, out),
+        Stmt::Def(sig, body) => block(format!("def {}", sig), body, out),
+        Stmt::Class(name, body) => block(format!("class {}", name), body, out),
+        Stmt::Block(header, body) => block(header.clone(), body, out),
+        Stmt::Try { body, except_name, except_body, finally_body } => {
+            block("try".to_string(), body, out);
+            // an empty except body means the source had only `finally`
+            if !except_body.is_empty() {
+                let header = match except_name {
+                    Some(name) => format!("except Exception as {}", name),
+                    None => "except".to_string(),
+                };
+                block(header, except_body, out);
+            }
+            if !finally_body.is_empty() {
+                block("finally".to_string(), finally_body, out);
+            }
+        }
+        Stmt::Raise(expr) => push_line(out, depth, &format!("raise {}", expr)),
+        Stmt::Assert(cond, msg) => match msg {
+            Some(msg) => push_line(out, depth, &format!("assert {}, {}", cond, msg)),
+            None => push_line(out, depth, &format!("assert {}", cond)),
+        },
+        Stmt::Return(expr) => push_line(out, depth, format!("return {}", expr).trim_end()),
+        Stmt::Break => push_line(out, depth, "break"),
+        Stmt::Continue => push_line(out, depth, "continue"),
+        Stmt::Pass => push_line(out, depth, "pass"),
+        Stmt::Yield(expr) => push_line(out, depth, format!("yield {}", expr).trim_end()),
+        Stmt::Import(name) => push_line(out, depth, &format!("import {}", name)),
+        Stmt::Line(..) => {}
+    }
+}
+
+/// One-line description of a statement for traces: simple statements as
+/// written, compound ones by their header (`while i < 3`, `def f(x)`)
+pub fn describe(stmt: &Stmt) -> String {
+    match stmt {
+        Stmt::If(branches, _) => format!("if {}", branches.first().map(|b| b.0.as_str()).unwrap_or("")),
+        Stmt::Else(_) => "else".to_string(),
+        Stmt::While(cond, _) => format!("while {}", cond),
+        Stmt::For(header, _) => format!("for {}", header),
+        Stmt::Def(sig, _) => format!("def {}", sig),
+        Stmt::Class(name, _) => format!("class {}", name),
+        Stmt::Block(header, _) => header.clone(),
+        Stmt::Try { .. } => "try".to_string(),
+        simple => {
+            let mut out = String::new();
+            unparse_stmt(simple, 0, &mut out);
+            out.trim_end().to_string()
+        }
     }
 }
 
 pub fn parse_and_run(src: &str, env: &mut crate::runtime::Env) -> Result<()> {
-    let ast = parse_to_ast(src)?;
-    crate::eval::run_stmts(&ast.body, env)
+    run_module(&parse_to_ast(src)?, env)
+}
+
+/// Run a parsed script's top level
+pub fn run_module(ast: &ModuleAST, env: &mut crate::runtime::Env) -> Result<()> {
+    match crate::eval::run_stmts(&ast.body, env)? {
+        crate::eval::Flow::Break => Err(anyhow!("'break' outside loop")),
+        crate::eval::Flow::Continue => Err(anyhow!("'continue' outside loop")),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{Env, Value};
+
+    fn run(src: &str) -> Env {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        parse_and_run(src, &mut env).unwrap();
+        env
+    }
+
+    #[test]
+    fn test_augmented_assignment() {
+        let env = run("x = 5\nx += 2\nx *= 3\nx -= 1\nx //= 4\ny = 1.5\ny /= 2\ns = \"ab\"\ns += \"c\" + \"d\"\nz = 3\nz **= 2");
+        assert!(matches!(env.get("x"), Some(Value::Int(5))));
+        assert!(matches!(env.get("z"), Some(Value::Int(9))));
+        assert!(matches!(env.get("y"), Some(Value::Float(f)) if *f == 0.75));
+        assert!(matches!(env.get("s"), Some(Value::Str(s)) if s == "abcd"));
+    }
+
+    #[test]
+    fn test_augmented_assignment_on_attribute() {
+        let src = "class Counter:\n    def __init__(self):\n        self.count = 1\n        self.count += 2\nc = Counter()\nn = c.count";
+        let env = run(src);
+        assert!(matches!(env.get("n"), Some(Value::Int(3))));
+    }
+
+    #[test]
+    fn test_augmented_assignment_evaluates_index_once() {
+        let src = "def f():\n    print(\"called\")\n    return 1\nxs = [10, 20]\nxs[f()] += 5\nd = {\"k\": 1}\nd[\"k\"] -= 4";
+        let printed = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let sink = printed.clone();
+        let mut env = Env::new();
+        env.set_output_hook(move |line| sink.borrow_mut().push(line.to_string()));
+        parse_and_run(src, &mut env).unwrap();
+        assert_eq!(*printed.borrow(), vec!["called"]);
+        assert_eq!(env.get("xs"), Some(&Value::List(vec![Value::Int(10), Value::Int(25)])));
+        assert!(matches!(env.get("d"), Some(Value::Dict(m)) if m.get("k") == Some(&Value::Int(-3))));
+        // the compound form survives unparsing
+        assert_eq!(unparse(&parse_to_ast("xs[0] **= 2").unwrap()), "xs[0] **= 2\n");
+    }
+
+    #[test]
+    fn test_else_runs_only_when_no_branch_matches() {
+        let env = run("x = 0\nif false:\n    x = 1\nelif false:\n    x = 2\nelse:\n    x = 3");
+        assert!(matches!(env.get("x"), Some(Value::Int(3))));
+        let env = run("x = 0\nif false:\n    x = 1\n# comment\nelif true:\n    x = 2\nelse:\n    x = 3\ny = x");
+        assert!(matches!(env.get("y"), Some(Value::Int(2))));
+    }
+
+    #[test]
+    fn test_nested_if_else_attaches_to_inner_if() {
+        let ast = parse_to_ast("if a:\n    if b:\n        x = 1\n    else:\n        x = 2\nelse:\n    x = 3").unwrap();
+        assert_eq!(ast.body.len(), 1);
+        match &ast.body[0] {
+            Stmt::If(branches, else_block) => {
+                assert_eq!(branches.len(), 1);
+                assert_eq!(else_block.len(), 1);
+                assert!(matches!(&branches[0].1[0], Stmt::If(_, inner_else) if inner_else.len() == 1));
+            }
+            other => panic!("unexpected statement: {:?}", other),
+        }
+        assert!(parse_to_ast("else:\n    x = 1").is_err());
+    }
+
+    #[test]
+    fn test_try_catches_division_by_zero() {
+        let env = run("log = 0\ntry:\n    x = 1 / 0\n    log = 1\nexcept ZeroDivisionError as e:\n    msg = e\n    log = log + 10\nfinally:\n    log = log + 100");
+        assert!(matches!(env.get("log"), Some(Value::Int(110))));
+        assert!(matches!(env.get("msg"), Some(Value::Error(m)) if m.contains("Division by zero")));
+    }
+
+    #[test]
+    fn test_finally_runs_without_error_and_reraises_when_uncaught() {
+        let env = run("log = 0\ntry:\n    log = 1\nexcept:\n    log = 2\nfinally:\n    done = true");
+        assert!(matches!(env.get("log"), Some(Value::Int(1))));
+        assert!(matches!(env.get("done"), Some(Value::Bool(true))));
+
+        let mut env = Env::new();
+        let result = parse_and_run("try:\n    x = 1 / 0\nfinally:\n    done = true", &mut env);
+        assert!(result.is_err());
+        assert!(matches!(env.get("done"), Some(Value::Bool(true))));
+        assert!(parse_to_ast("try:\n    x = 1").is_err());
+    }
+
+    #[test]
+    fn test_raise_and_catch() {
+        let env = run("try:\n    raise ValueError(\"bad input\")\n    x = 1\nexcept ValueError as e:\n    msg = e.message\n    err = e");
+        assert!(env.get("x").is_none());
+        assert!(matches!(env.get("msg"), Some(Value::Str(m)) if m == "bad input"));
+        assert!(matches!(env.get("err"), Some(Value::Error(m)) if m == "bad input"));
+
+        let mut env = Env::new();
+        let err = parse_and_run("raise \"boom\"", &mut env).unwrap_err();
+        assert_eq!(err.to_string(), "boom");
+    }
+
+    #[test]
+    fn test_function_returns_value() {
+        let env = run("def add(a, b):\n    return a + b\nx = add(2, 3)\ndef nothing():\n    y = 1\nz = nothing()");
+        assert!(matches!(env.get("x"), Some(Value::Int(5))));
+        assert!(matches!(env.get("z"), Some(Value::None)));
+    }
+
+    #[test]
+    fn test_recursive_factorial() {
+        let env = run("def fact(n):\n    if n <= 1:\n        return 1\n    return n * fact(n - 1)\nx = fact(5)");
+        assert!(matches!(env.get("x"), Some(Value::Int(120))));
+    }
+
+    #[test]
+    fn test_method_returns_value() {
+        let env = run("class Adder:\n    def __init__(self, base):\n        self.base = base\n    def add(self, n):\n        return self.base + n\na = Adder(10)\nx = a.add(5)");
+        assert!(matches!(env.get("x"), Some(Value::Int(15))));
+    }
+
+    #[test]
+    fn test_str_dunder_formats_instance() {
+        let mut env = run("class Point:\n    def __init__(self, x, y):\n        self.x = x\n        self.y = y\n    def __str__(self):\n        return \"(\" + str(self.x) + \", \" + str(self.y) + \")\"\np = Point(1, 2)\ns = str(p)\nprint(p)");
+        assert!(matches!(env.get("s"), Some(Value::Str(s)) if s == "(1, 2)"));
+        let pair = Value::List(vec![env.get("p").cloned().unwrap(), Value::Int(3)]);
+        assert_eq!(crate::eval::format_value(&pair, &mut env).unwrap(), "[(1, 2), 3]");
+    }
+
+    #[test]
+    fn test_function_reads_outer_variables() {
+        let env = run("scale = 3\ndef outer(x):\n    def inner(y):\n        return y * scale + x\n    return inner(x)\nr = outer(2)");
+        assert!(matches!(env.get("r"), Some(Value::Int(8))));
+    }
+
+    #[test]
+    fn test_callee_does_not_see_caller_locals() {
+        let env = run("x = 1\ndef f():\n    return x\ndef g():\n    x = 5\n    return f()\nr = g()\ndef h():\n    secret = 7\n    return peek()\ndef peek():\n    return secret\ntry:\n    h()\n    leaked = true\nexcept:\n    leaked = false");
+        // names resolve where f is defined, not where it is called from
+        assert!(matches!(env.get("r"), Some(Value::Int(1))));
+        assert!(matches!(env.get("leaked"), Some(Value::Bool(false))));
+    }
+
+    #[test]
+    fn test_assignment_in_function_stays_local() {
+        let env = run("count = 1\ndef bump():\n    count = count + 1\n    return count\nr = bump()");
+        assert!(matches!(env.get("r"), Some(Value::Int(2))));
+        assert!(matches!(env.get("count"), Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn test_default_parameters() {
+        let env = run("def greet(name, greeting=\"Hello\", punct=\"!\"):\n    return greeting + \" \" + name + punct\na = greet(\"Ann\")\nb = greet(\"Bo\", \"Hi\", \"?\")");
+        assert!(matches!(env.get("a"), Some(Value::Str(s)) if s == "Hello Ann!"));
+        assert!(matches!(env.get("b"), Some(Value::Str(s)) if s == "Hi Bo?"));
+    }
+
+    #[test]
+    fn test_method_default_parameter() {
+        let env = run("class Counter:\n    def __init__(self, start=10):\n        self.n = start\n    def add(self, k=1):\n        return self.n + k\nc = Counter()\nx = c.add()\ny = c.add(5)");
+        assert!(matches!(env.get("x"), Some(Value::Int(11))));
+        assert!(matches!(env.get("y"), Some(Value::Int(15))));
+        let mut env = Env::new();
+        assert!(parse_and_run("def bad(a=1, b):\n    return a", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_keyword_arguments() {
+        let env = run("def person(name, age=0, city=\"?\"):\n    return name + \"/\" + str(age) + \"/\" + city\na = person(city=\"Oslo\", name=\"Ann\")\nb = person(\"Bo\", city=\"Rome\")\nok = person(\"Cy\", age=3) == \"Cy/3/?\"");
+        assert!(matches!(env.get("a"), Some(Value::Str(s)) if s == "Ann/0/Oslo"));
+        assert!(matches!(env.get("b"), Some(Value::Str(s)) if s == "Bo/0/Rome"));
+        assert!(matches!(env.get("ok"), Some(Value::Bool(true))));
+    }
+
+    #[test]
+    fn test_keyword_argument_errors() {
+        let src = "class Person:\n    def __init__(self, name):\n        self.name = name\np = Person(name=\"Alice\")\nn = p.name";
+        let env = run(src);
+        assert!(matches!(env.get("n"), Some(Value::Str(s)) if s == "Alice"));
+        for bad in ["f(a=1, 2)", "f(1, a=2)", "f(b=1)"] {
+            let mut env = Env::new();
+            let src = format!("def f(a):\n    return a\nx = {}", bad);
+            assert!(parse_and_run(&src, &mut env).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let env = run("a = \"line1\\nline2\"\nb = \"tab\\there\\r\\0\"\nc = \"say \\\"hi\\\"\"\nd = \"back\\\\\"\ne = \"keep\\q\"");
+        assert!(matches!(env.get("a"), Some(Value::Str(s)) if s == "line1\nline2"));
+        assert!(matches!(env.get("b"), Some(Value::Str(s)) if s == "tab\there\r\0"));
+        assert!(matches!(env.get("c"), Some(Value::Str(s)) if s == "say \"hi\""));
+        assert!(matches!(env.get("d"), Some(Value::Str(s)) if s == "back\\"));
+        assert!(matches!(env.get("e"), Some(Value::Str(s)) if s == "keep\\q"));
+    }
+
+    #[test]
+    fn test_escaped_backslash_ends_string() {
+        let env = run("parts = [\"a\\\\\", \"b\"]\nn = len(parts)\ns = \"x\\\\\" + \"=y\"");
+        assert!(matches!(env.get("n"), Some(Value::Int(2))));
+        assert!(matches!(env.get("s"), Some(Value::Str(s)) if s == "x\\=y"));
+    }
+
+    #[test]
+    fn test_comparison_is_not_assignment() {
+        assert!(matches!(parse_simple_statement("x == 1".to_string()).unwrap(), Stmt::Expr(_)));
+        assert!(matches!(parse_simple_statement("f(a, b=1)".to_string()).unwrap(), Stmt::Expr(_)));
+        assert!(parse_simple_statement("x = ".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_indexed_assignment() {
+        let env = run("xs = [1, 2, 3]\nxs[0] = 10\nxs[-1] += 5\nd = {\"a\": [1, 2]}\nd[\"b\"] = 7\nd[\"a\"][1] = 9");
+        assert_eq!(env.get("xs"), Some(&Value::List(vec![Value::Int(10), Value::Int(2), Value::Int(8)])));
+        match env.get("d") {
+            Some(Value::Dict(map)) => {
+                assert_eq!(map.get("b"), Some(&Value::Int(7)));
+                assert_eq!(map.get("a"), Some(&Value::List(vec![Value::Int(1), Value::Int(9)])));
+            }
+            other => panic!("expected dict, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_assert() {
+        let env = run("x = 3\nassert x == 3\nassert x > 0, \"positive\"\nok = True");
+        assert!(matches!(env.get("ok"), Some(Value::Bool(true))));
+        let mut env = Env::new();
+        let err = parse_and_run("x = 1\nassert x == 2, \"x was \" + x", &mut env).unwrap_err();
+        assert_eq!(err.to_string(), "AssertionError: x was 1");
+        let err = parse_and_run("assert False", &mut env).unwrap_err();
+        assert_eq!(err.to_string(), "AssertionError");
+        let env = run("try:\n    assert 1 == 2, \"nope\"\nexcept AssertionError as e:\n    caught = e");
+        assert!(matches!(env.get("caught"), Some(Value::Error(m)) if m == "AssertionError: nope"));
+    }
+
+    #[test]
+    fn test_pass_and_failing_expression_statements() {
+        let env = run("n = 0\nfor i in range(3):\n    pass\nwhile n < 2:\n    n += 1\n    pass\ndef noop():\n    pass\nnoop()");
+        assert!(matches!(env.get("n"), Some(Value::Int(2))));
+        assert!(matches!(parse_simple_statement("pass".to_string()).unwrap(), Stmt::Pass));
+        let mut env = Env::new();
+        assert!(parse_and_run("undefined_name", &mut env).is_err());
+        assert!(parse_and_run("prnit(1)", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_unparse_round_trips() {
+        let src = "# totals\nclass Acc:\n  def __init__(self):\n    self.n = 0\ndef total(xs, start=0):\n  t = start\n  for x in xs:\n    if x < 0:\n      continue\n    elif x > 100:\n      break\n    else:\n      t += x\n  return t\ntry:\n  assert total([1, 2]) == 3, \"sum\"\n  r = total([1, -5, 2, 500, 7], 10)\nexcept ValueError as e:\n  r = -1\nfinally:\n  done = True\nwhile False:\n  pass";
+        let ast = parse_to_ast(src).unwrap();
+        let text = unparse(&ast);
+        assert!(text.starts_with("class Acc:\n    def __init__(self):\n        self.n = 0\n"), "{}", text);
+        // unparsing is a fixpoint once comments are gone
+        assert_eq!(unparse(&parse_to_ast(&text).unwrap()), text);
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        parse_and_run(&text, &mut env).unwrap();
+        assert!(matches!(env.get("r"), Some(Value::Int(13))));
+        assert!(matches!(env.get("done"), Some(Value::Bool(true))));
+    }
+
+    #[test]
+    fn test_trace_emits_one_line_per_statement() {
+        let src = "x = 1\n# comment\nwhile x < 3:\n    x += 1\nif x == 3:\n    print(\"done\")\nelse:\n    pass";
+        let lines = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let sink = lines.clone();
+        let mut env = Env::new();
+        env.set_output_hook(|_| {});
+        env.set_trace_hook(move |line| sink.borrow_mut().push(line.to_string()));
+        run_module(&parse_to_ast_with_lines(src).unwrap(), &mut env).unwrap();
+        assert_eq!(
+            *lines.borrow(),
+            vec![
+                "[trace] line 1: x = 1",
+                "[trace] line 3: while x < 3:  (x=1)",
+                "[trace] line 4: x += 1  (x=1)",
+                "[trace] line 4: x += 1  (x=2)",
+                "[trace] line 5: if x == 3:  (x=3)",
+                "[trace] line 6: print(\"done\")",
+            ]
+        );
+        // the markers only exist in the traced parse
+        assert!(!parse_to_ast(src).unwrap().body.iter().any(|s| matches!(s, Stmt::Line(..))));
+    }
 }
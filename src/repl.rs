@@ -1,5 +1,5 @@
-use anyhow::Result;
-use std::io::{self, Write};
+use anyhow::{anyhow, Result};
+use std::io::{self, BufRead, Write};
 
 /// Start the Ferrum interactive REPL (Read-Eval-Print Loop)
 /// 
@@ -68,3 +68,77 @@ pub fn start() -> Result<()> {
     Ok(())
 }
 
+/// Pause at a `breakpoint()` and inspect `env`, the scope that called it.
+///
+/// Each line read from `input` is evaluated as an expression and its value
+/// printed, or failing that run as a statement, so variables can be changed
+/// before resuming. `vars` lists the variables in scope, `continue` (or end
+/// of input) resumes the script and `exit` aborts it with an error.
+pub fn inspect(env: &mut crate::runtime::Env, input: &mut impl BufRead) -> Result<()> {
+    env.write_line("Paused at breakpoint. Type 'continue' to resume, 'exit' to abort, 'vars' to list variables.");
+    loop {
+        env.write_prompt("(break) ");
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        match line.trim() {
+            "" => {}
+            "continue" | "c" => return Ok(()),
+            "exit" | "quit" => return Err(anyhow!("exited at breakpoint")),
+            "vars" => {
+                let (vars, _) = env.bindings();
+                let mut names: Vec<_> = vars.keys().collect();
+                names.sort();
+                for name in names {
+                    env.write_line(&format!("{} = {}", name, vars[name].to_string()));
+                }
+            }
+            code => match crate::eval::eval_expr_str(code, env) {
+                Ok(value) => {
+                    let shown = crate::eval::format_value(&value, env)?;
+                    env.write_line(&shown);
+                }
+                Err(expr_err) => {
+                    let result = crate::parser::parse_to_ast(code).and_then(|ast| crate::eval::run_stmts(&ast.body, env));
+                    if let Err(e) = result {
+                        // report whichever error is about the line as typed
+                        let e = if code.contains('=') { e } else { expr_err };
+                        env.write_line(&format!("Error: {}", e));
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{Env, Function, Value};
+
+    /// `breakpoint()` as registered by the stdlib, but reading scripted input
+    fn scripted_breakpoint(env: &mut Env, _args: Vec<Value>) -> Result<Value> {
+        inspect(env, &mut io::Cursor::new("n * 10\nmissing\nn = n + 1\ncontinue\nn = 99\n")).map(|_| Value::None)
+    }
+
+    #[test]
+    fn test_breakpoint_shows_local_and_resumes() {
+        let lines = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let sink = lines.clone();
+        let mut env = Env::new();
+        env.set_output_hook(move |line| sink.borrow_mut().push(line.to_string()));
+        env.set_func("breakpoint".to_string(), Function::NativeEnv(scripted_breakpoint));
+        crate::parser::parse_and_run("def f(n):\n    breakpoint()\n    print(\"after\", n)\nf(4)", &mut env).unwrap();
+        let lines = lines.borrow();
+        // prompts and answers both go through the output hook
+        assert!(lines.windows(2).any(|w| w[0] == "(break)" && w[1] == "40"), "{:?}", lines);
+        assert!(lines.iter().any(|l| l.starts_with("Error: ")), "{:?}", lines);
+        // the assignment made while paused is kept and nothing after `continue` runs
+        assert_eq!(lines.last().map(String::as_str), Some("after 5"));
+
+        let mut paused = Env::new();
+        paused.set_output_hook(|_| {});
+        assert!(inspect(&mut paused, &mut io::Cursor::new("exit\n")).is_err());
+    }
+}
@@ -1,5 +1,10 @@
 use anyhow::{anyhow, Result};
 use std::collections::HashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::path::Path;
 
 /// Runtime value types in Ferrum
@@ -9,10 +14,12 @@ use std::path::Path;
 /// - Floats (f64)
 /// - Booleans (bool)
 /// - Strings (String)
+/// - Bytes (Vec<u8>) for binary data
 /// - Lists (Vec<Value>)
 /// - Dictionaries (HashMap<String, Value>)
 /// - Classes and Instances
 /// - Ranges for iteration
+/// - Functions (first-class references to named functions)
 /// - None (nil/null)
 #[derive(Clone, Debug)]
 pub enum Value {
@@ -20,12 +27,17 @@ pub enum Value {
     Float(f64),
     Bool(bool),
     Str(String),
+    Bytes(Vec<u8>),
     List(Vec<Value>),
     Dict(HashMap<String, Value>),
         Class(String, HashMap<String, Function>),
         Instance(String, HashMap<String, Value>, HashMap<String, Function>),
     None,
     Range(i64, i64),
+    /// A function used as a value, e.g. passed to `map_values`
+    Func(String, Function),
+    /// A raised or caught exception; `.message` reads the text
+    Error(String),
 }
 
 impl Value {
@@ -35,12 +47,50 @@ impl Value {
             Value::Int(i) => *i != 0,
             Value::Float(f) => *f != 0.0,
             Value::Str(s) => !s.is_empty(),
+            Value::Bytes(b) => !b.is_empty(),
             Value::List(vs) => !vs.is_empty(),
             Value::Dict(m) => !m.is_empty(),
             Value::None => false,
             Value::Range(_, _) => true,
             Value::Class(_, _) => true,
             Value::Instance(_, _, _) => true,
+            Value::Func(_, _) => true,
+            Value::Error(_) => true,
+        }
+    }
+
+    /// Recursively copy lists, dicts and instance fields so the result
+    /// shares no containers with `self`.
+    pub fn deep_copy(&self) -> Value {
+        match self {
+            Value::List(vs) => Value::List(vs.iter().map(Value::deep_copy).collect()),
+            Value::Dict(m) => Value::Dict(m.iter().map(|(k, v)| (k.clone(), v.deep_copy())).collect()),
+            Value::Instance(name, fields, methods) => Value::Instance(
+                name.clone(),
+                fields.iter().map(|(k, v)| (k.clone(), v.deep_copy())).collect(),
+                methods.clone(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// Name of the value's type as scripts see it; an instance reports its
+    /// class name.
+    pub fn type_name(&self) -> String {
+        match self {
+            Value::Int(_) => "int".to_string(),
+            Value::Float(_) => "float".to_string(),
+            Value::Bool(_) => "bool".to_string(),
+            Value::Str(_) => "str".to_string(),
+            Value::Bytes(_) => "bytes".to_string(),
+            Value::List(_) => "list".to_string(),
+            Value::Dict(_) => "dict".to_string(),
+            Value::Class(..) => "class".to_string(),
+            Value::Instance(name, _, _) => name.clone(),
+            Value::None => "None".to_string(),
+            Value::Range(..) => "range".to_string(),
+            Value::Func(..) => "function".to_string(),
+            Value::Error(_) => "error".to_string(),
         }
     }
 
@@ -50,20 +100,171 @@ impl Value {
             Value::Float(f) => f.to_string(),
             Value::Bool(b) => b.to_string(),
             Value::Str(s) => s.clone(),
+            Value::Bytes(b) => format!("b\"{}\"", b.escape_ascii()),
             Value::List(vs) => format!("[{}]", vs.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")),
             Value::Dict(_) => "{...}".to_string(),
             Value::Class(name, _) => format!("<class {}>", name),
             Value::Instance(name, _, _) => format!("<instance {}>", name),
             Value::None => "None".to_string(),
             Value::Range(s,e) => format!("range({}, {})", s, e),
+            Value::Func(name, _) => format!("<function {}>", name),
+            Value::Error(msg) => msg.clone(),
+        }
+    }
+}
+
+/// Structural equality: numbers compare across Int/Float, containers compare
+/// element-wise, and classes/instances are never equal.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Int(a), Value::Float(b)) | (Value::Float(b), Value::Int(a)) => (*a as f64) == *b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Dict(a), Value::Dict(b)) => a == b,
+            (Value::Range(s1, e1), Value::Range(s2, e2)) => s1 == s2 && e1 == e2,
+            (Value::Error(a), Value::Error(b)) => a == b,
+            (Value::None, Value::None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Ordering for numbers, strings, booleans and lists. Lists compare
+/// lexicographically, element by element, with a shorter list ordering first
+/// when it is a prefix of the other. Other combinations are unordered.
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a.partial_cmp(b),
+            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+            (Value::Int(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+            (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f64)),
+            (Value::Bool(a), Value::Bool(b)) => a.partial_cmp(b),
+            (Value::Str(a), Value::Str(b)) => a.partial_cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => a.partial_cmp(b),
+            (Value::List(a), Value::List(b)) => {
+                for (x, y) in a.iter().zip(b.iter()) {
+                    match x.partial_cmp(y)? {
+                        std::cmp::Ordering::Equal => continue,
+                        ord => return Some(ord),
+                    }
+                }
+                a.len().partial_cmp(&b.len())
+            }
+            (Value::None, Value::None) => Some(std::cmp::Ordering::Equal),
+            _ => None,
         }
     }
 }
 
+/// A user function parameter: its name and, for `b=1`, the default expression
+/// evaluated when a call leaves it out
+pub type Param = (String, Option<String>);
+
 #[derive(Debug, Clone)]
 pub enum Function {
     Native(fn(Vec<Value>) -> Result<Value>),
-    User { params: Vec<String>, body: Vec<crate::eval::Stmt> },
+    /// Native function that needs the calling environment, e.g. to invoke Ferrum callbacks
+    NativeEnv(fn(&mut Env, Vec<Value>) -> Result<Value>),
+    User { params: Vec<Param>, body: Vec<crate::eval::Stmt> },
+    /// A callable with leading arguments pre-bound, created by `partial`
+    Partial { func: Box<Value>, args: Vec<Value> },
+}
+
+/// Limits on how much work a script may do, shared by an env and every call
+/// made from it. See [`Env::set_budget`].
+#[derive(Debug, Clone)]
+pub struct Budget {
+    max_steps: Option<u64>,
+    deadline: Option<Instant>,
+    steps: Arc<AtomicU64>,
+}
+
+impl Budget {
+    /// Count one executed statement, failing once a limit is passed. The clock
+    /// is only read every few steps to keep the check cheap.
+    pub fn charge(&self) -> Result<()> {
+        let steps = self.steps.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(max) = self.max_steps {
+            if steps > max {
+                return Err(anyhow!("budget exceeded: more than {} steps", max));
+            }
+        }
+        if let Some(deadline) = self.deadline {
+            if steps % 64 == 1 && Instant::now() >= deadline {
+                return Err(anyhow!("budget exceeded: time limit reached"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Per-function call counts and cumulative time, shared by an env and every
+/// call made from it. See [`Env::enable_profiling`].
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    stats: Rc<RefCell<HashMap<String, FnStats>>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct FnStats {
+    calls: u64,
+    total: Duration,
+    /// Calls of this function currently running; time is only added when the
+    /// outermost one returns so recursion isn't counted twice
+    active: u32,
+}
+
+impl Profiler {
+    fn enter(&self, name: &str) {
+        let mut stats = self.stats.borrow_mut();
+        let entry = stats.entry(name.to_string()).or_default();
+        entry.calls += 1;
+        entry.active += 1;
+    }
+
+    fn exit(&self, name: &str, elapsed: Duration) {
+        if let Some(entry) = self.stats.borrow_mut().get_mut(name) {
+            entry.active -= 1;
+            if entry.active == 0 {
+                entry.total += elapsed;
+            }
+        }
+    }
+
+    /// `(function, calls, cumulative time)` for every function called so far,
+    /// slowest first
+    pub fn entries(&self) -> Vec<(String, u64, Duration)> {
+        let mut entries: Vec<_> = self.stats.borrow().iter().map(|(name, s)| (name.clone(), s.calls, s.total)).collect();
+        entries.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        entries
+    }
+
+    /// The entries as a table for printing at exit
+    pub fn report(&self) -> String {
+        let entries = self.entries();
+        let width = entries.iter().map(|e| e.0.len()).max().unwrap_or(0).max("function".len());
+        let mut out = format!("{:<width$}  {:>8}  {:>12}  {:>12}\n", "function", "calls", "total ms", "per call ms");
+        for (name, calls, total) in entries {
+            let ms = total.as_secs_f64() * 1000.0;
+            out.push_str(&format!("{:<width$}  {:>8}  {:>12.3}  {:>12.3}\n", name, calls, ms, ms / calls as f64));
+        }
+        out
+    }
+}
+
+/// Saved bindings of an [`Env`], taken with [`Env::snapshot`] and rolled back
+/// to with [`Env::restore`]
+#[derive(Debug, Clone)]
+pub struct EnvSnapshot {
+    vars: HashMap<String, Value>,
+    funcs: HashMap<String, Function>,
+    modules: HashMap<String, HashMap<String, Value>>,
 }
 
 /// Runtime environment containing variables, functions, and modules
@@ -73,16 +274,220 @@ pub enum Function {
 /// - Function definitions (funcs)
 /// - Loaded modules with their exports (modules)
 /// 
-/// Each function call creates a new child environment to handle local scope.
+/// Each function call runs in a new local environment (see
+/// [`Env::with_call_env`]). Names that aren't local are looked up through the
+/// `parent` chain, which follows where functions are defined.
 pub struct Env {
     vars: HashMap<String, Value>,
     funcs: HashMap<String, Function>,
     pub modules: HashMap<String, HashMap<String, Value>>,
+    /// Values yielded so far when this env runs a generator body
+    pub yields: Option<Vec<Value>>,
+    /// Enclosing scope, consulted when a name isn't bound locally
+    parent: Option<Rc<Env>>,
+    /// Set for untrusted code: `import` only sees builtin modules, never files
+    pub sandboxed: bool,
+    /// Step/time limits; None (the default) means unlimited
+    pub budget: Option<Budget>,
+    /// Receives printed lines instead of stdout; see [`Env::set_output_hook`]
+    output: Option<OutputHook>,
+    /// Times user function calls when set; None (the default) costs nothing
+    pub profiler: Option<Profiler>,
+    /// Receives a line per executed statement; see [`Env::set_trace_hook`]
+    trace: Option<OutputHook>,
+    /// Counts statement hits per source line when set; see [`Env::enable_coverage`]
+    pub coverage: Option<crate::coverage::Coverage>,
+    /// Number of user function calls this env is nested in
+    depth: usize,
 }
 
+/// Deepest nesting of user function calls before a call fails with
+/// "maximum recursion depth exceeded"
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+/// Callback that receives each line a script prints, shared by an env and
+/// its children
+pub type OutputHook = Rc<RefCell<dyn FnMut(&str)>>;
+
 impl Env {
     pub fn new() -> Self {
-        Env { vars: HashMap::new(), funcs: HashMap::new(), modules: HashMap::new() }
+        Env { vars: HashMap::new(), funcs: HashMap::new(), modules: HashMap::new(), yields: None, parent: None, sandboxed: false, budget: None, output: None, profiler: None, trace: None, coverage: None, depth: 0 }
+    }
+
+    /// Env with the standard library registered in sandbox mode: filesystem,
+    /// process and device builtins raise "disabled in sandbox" errors.
+    pub fn new_sandboxed() -> Self {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib_sandboxed(&mut env);
+        env
+    }
+
+    /// Run `f` in a new local env for a function call.
+    ///
+    /// Scoping is lexical: names the call doesn't bind are looked up in the
+    /// scope that defines the function `defined_as`, or in the global scope
+    /// for methods and function values, never in the caller's locals. That
+    /// scope is shared rather than copied; when it is this env, it is lent to
+    /// the call and taken back once `f` returns.
+    pub fn with_call_env<T>(&mut self, defined_as: Option<&str>, f: impl FnOnce(&mut Env) -> Result<T>) -> Result<T> {
+        let mut call = Env {
+            sandboxed: self.sandboxed,
+            budget: self.budget.clone(),
+            output: self.output.clone(),
+            profiler: self.profiler.clone(),
+            trace: self.trace.clone(),
+            coverage: self.coverage.clone(),
+            depth: self.depth + 1,
+            ..Env::new()
+        };
+        if let Some(scope) = self.call_scope(defined_as) {
+            call.parent = Some(scope);
+            return f(&mut call);
+        }
+        let lent = Rc::new(std::mem::replace(self, Env::new()));
+        call.parent = Some(lent.clone());
+        let result = f(&mut call);
+        drop(call);
+        *self = match Rc::try_unwrap(lent) {
+            Ok(env) => env,
+            Err(_) => unreachable!("a call env never outlives its call"),
+        };
+        result
+    }
+
+    /// Enclosing scope for a call made from this env, or None when it is this env
+    fn call_scope(&self, defined_as: Option<&str>) -> Option<Rc<Env>> {
+        if let Some(name) = defined_as {
+            if self.funcs.contains_key(name) {
+                return None;
+            }
+            let mut scope = self.parent.as_ref();
+            while let Some(env) = scope {
+                if env.funcs.contains_key(name) {
+                    return Some(env.clone());
+                }
+                scope = env.parent.as_ref();
+            }
+        }
+        // the global scope is the root of the parent chain
+        let mut global = self.parent.as_ref()?;
+        while let Some(parent) = &global.parent {
+            global = parent;
+        }
+        Some(global.clone())
+    }
+
+    /// Send every line printed from now on, including from function calls, to
+    /// `hook` instead of stdout. Worker threads (`with_timeout`) still print
+    /// to stdout.
+    pub fn set_output_hook(&mut self, hook: impl FnMut(&str) + 'static) {
+        self.output = Some(Rc::new(RefCell::new(hook)));
+    }
+
+    /// Print one line through the output hook, or to stdout without one
+    pub fn write_line(&self, line: &str) {
+        match &self.output {
+            Some(hook) => (hook.borrow_mut())(line),
+            None => println!("{}", line),
+        }
+    }
+
+    /// Show `prompt` without ending the line, flushing stdout so it appears
+    /// before input is read. An output hook takes whole lines, so it gets the
+    /// prompt as a line of its own.
+    pub fn write_prompt(&self, prompt: &str) {
+        match &self.output {
+            Some(hook) => (hook.borrow_mut())(prompt.trim_end()),
+            None => {
+                use std::io::Write;
+                print!("{}", prompt);
+                let _ = std::io::stdout().flush();
+            }
+        }
+    }
+
+    /// Report every statement to `hook` just before it runs, with its line
+    /// number when the AST came from [`crate::parser::parse_to_ast_with_lines`]
+    pub fn set_trace_hook(&mut self, hook: impl FnMut(&str) + 'static) {
+        self.trace = Some(Rc::new(RefCell::new(hook)));
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    /// Send one line to the trace hook, if there is one
+    pub fn trace_line(&self, line: &str) {
+        if let Some(hook) = &self.trace {
+            (hook.borrow_mut())(line);
+        }
+    }
+
+    /// Fail once this env is nested more than [`MAX_CALL_DEPTH`] calls deep,
+    /// so runaway recursion is an error a script can catch
+    pub fn check_call_depth(&self) -> Result<()> {
+        if self.depth > MAX_CALL_DEPTH {
+            return Err(anyhow!("maximum recursion depth exceeded"));
+        }
+        Ok(())
+    }
+
+    /// Count how often each source line runs from now on, returning the
+    /// counter. Lines are only known for ASTs from
+    /// [`crate::parser::parse_to_ast_with_lines`].
+    pub fn enable_coverage(&mut self) -> crate::coverage::Coverage {
+        let coverage = crate::coverage::Coverage::default();
+        self.coverage = Some(coverage.clone());
+        coverage
+    }
+
+    /// Record the calls and cumulative time of every user function called by
+    /// name from now on, returning the profiler that collects them
+    pub fn enable_profiling(&mut self) -> Profiler {
+        let profiler = Profiler::default();
+        self.profiler = Some(profiler.clone());
+        profiler
+    }
+
+    /// Limit the statements executed from now on to `max_steps` and the run
+    /// time to `max_time`. Exceeding either aborts with a "budget exceeded" error.
+    pub fn set_budget(&mut self, max_steps: Option<u64>, max_time: Option<Duration>) {
+        self.budget = Some(Budget {
+            max_steps,
+            deadline: max_time.map(|t| Instant::now() + t),
+            steps: Arc::new(AtomicU64::new(0)),
+        });
+    }
+
+    /// Copy of this env's variables, functions and modules, so the effects of
+    /// running a snippet can be undone with [`Env::restore`]. Only the
+    /// environment is covered, not files or other outside state.
+    pub fn snapshot(&self) -> EnvSnapshot {
+        EnvSnapshot { vars: self.vars.clone(), funcs: self.funcs.clone(), modules: self.modules.clone() }
+    }
+
+    /// Roll back to the bindings saved in `snapshot`, dropping anything defined since
+    pub fn restore(&mut self, snapshot: EnvSnapshot) {
+        self.vars = snapshot.vars;
+        self.funcs = snapshot.funcs;
+        self.modules = snapshot.modules;
+    }
+
+    /// Every variable and function visible from this env, with inner scopes
+    /// shadowing outer ones. Unlike an `Env` these can be sent to another thread.
+    pub fn bindings(&self) -> (HashMap<String, Value>, HashMap<String, Function>) {
+        let (mut vars, mut funcs) = match &self.parent {
+            Some(parent) => parent.bindings(),
+            None => (HashMap::new(), HashMap::new()),
+        };
+        vars.extend(self.vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+        funcs.extend(self.funcs.iter().map(|(k, f)| (k.clone(), f.clone())));
+        (vars, funcs)
+    }
+
+    /// Top-level env holding the given bindings, the inverse of [`Env::bindings`]
+    pub fn from_bindings(vars: HashMap<String, Value>, funcs: HashMap<String, Function>) -> Env {
+        Env { vars, funcs, ..Env::new() }
     }
 
     pub fn set(&mut self, name: String, val: Value) {
@@ -90,76 +495,68 @@ impl Env {
     }
 
     pub fn get(&self, name: &str) -> Option<&Value> {
-        self.vars.get(name)
+        match self.vars.get(name) {
+            Some(v) => Some(v),
+            None => self.parent.as_ref()?.get(name),
+        }
+    }
+
+    /// Variable bound in this scope itself (not a parent), for changing it in place
+    pub fn get_local_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.vars.get_mut(name)
     }
 
     pub fn set_func(&mut self, name: String, f: Function) {
         self.funcs.insert(name, f);
     }
 
+    pub fn get_func(&self, name: &str) -> Option<&Function> {
+        match self.funcs.get(name) {
+            Some(f) => Some(f),
+            None => self.parent.as_ref()?.get_func(name),
+        }
+    }
+
     pub fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value> {
+        self.call_kw(name, args, Vec::new())
+    }
+
+    /// Call `name` with positional `args` and `name=value` keyword arguments
+    pub fn call_kw(&mut self, name: &str, args: Vec<Value>, kwargs: Vec<(String, Value)>) -> Result<Value> {
         // direct function call
-        if let Some(f) = self.funcs.get(name) {
-            match f {
-                Function::Native(fun) => fun(args),
-                Function::User { params, body } => {
-                    // create new local env
-                    let mut child = Env::new();
-                    // pass args positionally
-                    for (i, p) in params.iter().enumerate() {
-                        if let Some(v) = args.get(i) {
-                            child.set(p.clone(), v.clone());
-                        } else {
-                            child.set(p.clone(), Value::None);
-                        }
-                    }
-                    // inherit std modules/functions
-                    // run body
-                    crate::eval::run_stmts(body, &mut child)?;
-                    Ok(Value::None)
-                }
-            }
-        } else if let Some(v) = self.vars.get(name) {
+        if let Some(f) = self.get_func(name).cloned() {
+            self.call_named(name, Some(name), &f, args, kwargs)
+        } else if let Some(v) = self.get(name) {
             // If it's a class, calling it constructs an instance
             match v {
+                Value::Func(_, f) => {
+                    let f = f.clone();
+                    self.call_named(name, None, &f, args, kwargs)
+                }
                 Value::Class(class_name, methods) => {
-                    let fields = HashMap::new();
-                    let inst_methods = methods.clone();
-
-                    // create instance and child env to run __init__ if present
-                    let mut child = Env::new();
-                    let instance = Value::Instance(class_name.clone(), fields, inst_methods.clone());
-                    child.set("self".to_string(), instance.clone());
-
-                    if let Some(init_fn) = inst_methods.get("__init__") {
-                        match init_fn {
-                            Function::User { params, body } => {
-                                // bind positional args to params (skipping self)
-                                let mut ai = 0usize;
-                                for p in params.iter() {
-                                    if p == "self" {
-                                        // self already set
-                                        continue;
-                                    }
-                                    if let Some(a) = args.get(ai) {
-                                        child.set(p.clone(), a.clone());
-                                    } else {
-                                        child.set(p.clone(), Value::None);
-                                    }
-                                    ai += 1;
+                    let (class_name, inst_methods) = (class_name.clone(), methods.clone());
+
+                    // run __init__, if present, in a call env with `self` bound
+                    let instance = Value::Instance(class_name.clone(), HashMap::new(), inst_methods.clone());
+                    self.with_call_env(None, |child| {
+                        child.set("self".to_string(), instance);
+                        if let Some(init_fn) = inst_methods.get("__init__") {
+                            match init_fn {
+                                Function::User { params, body } => {
+                                    // bind positional args to params (self is already set)
+                                    let params = params.iter().filter(|(p, _)| p != "self");
+                                    crate::eval::bind_params(child, params, args, kwargs)?;
+                                    crate::eval::run_function_body(body, child)?;
                                 }
-                                crate::eval::run_stmts(body, &mut child)?;
+                                _ if !kwargs.is_empty() => {
+                                    return Err(anyhow!("{}() takes no keyword arguments", class_name));
+                                }
+                                _ => {}
                             }
-                            _ => {}
                         }
-                    }
-
-                    // retrieve possibly-updated instance from child
-                    if let Some(v) = child.get("self") {
-                        return Ok(v.clone());
-                    }
-
-                    Ok(Value::Instance(class_name.clone(), HashMap::new(), inst_methods))
+                        // retrieve possibly-updated instance from child
+                        Ok(child.get("self").cloned().unwrap_or_else(|| Value::Instance(class_name.clone(), HashMap::new(), inst_methods.clone())))
+                    })
                 }
                 _ => Err(anyhow!("Undefined function: {}", name)),
             }
@@ -168,10 +565,79 @@ impl Env {
         }
     }
 
+    /// Call `f`, timing it under `name` when profiling a user function.
+    /// `defined_as` names the function binding whose scope the call runs in.
+    fn call_named(&mut self, name: &str, defined_as: Option<&str>, f: &Function, args: Vec<Value>, kwargs: Vec<(String, Value)>) -> Result<Value> {
+        let profiler = match (&self.profiler, f) {
+            (Some(profiler), Function::User { .. }) => profiler.clone(),
+            _ => return self.call_function_in(defined_as, f, args, kwargs),
+        };
+        profiler.enter(name);
+        let start = Instant::now();
+        let result = self.call_function_in(defined_as, f, args, kwargs);
+        profiler.exit(name, start.elapsed());
+        result
+    }
+
+    /// Call a function given as a value: a `Func`, or a `Str` naming a function
+    /// (the form UI callbacks and module dicts use).
+    pub fn call_value(&mut self, callee: &Value, args: Vec<Value>) -> Result<Value> {
+        self.call_value_kw(callee, args, Vec::new())
+    }
+
+    pub fn call_value_kw(&mut self, callee: &Value, args: Vec<Value>, kwargs: Vec<(String, Value)>) -> Result<Value> {
+        match callee {
+            Value::Func(_, f) => self.call_function_kw(f, args, kwargs),
+            Value::Str(name) => self.call_kw(name, args, kwargs),
+            other => Err(anyhow!("{} is not callable", other.to_string())),
+        }
+    }
+
+    pub fn call_function(&mut self, f: &Function, args: Vec<Value>) -> Result<Value> {
+        self.call_function_kw(f, args, Vec::new())
+    }
+
+    /// Call `f`; keyword arguments are only accepted by user-defined functions
+    pub fn call_function_kw(&mut self, f: &Function, args: Vec<Value>, kwargs: Vec<(String, Value)>) -> Result<Value> {
+        self.call_function_in(None, f, args, kwargs)
+    }
+
+    fn call_function_in(&mut self, defined_as: Option<&str>, f: &Function, args: Vec<Value>, kwargs: Vec<(String, Value)>) -> Result<Value> {
+        if !kwargs.is_empty() && matches!(f, Function::Native(_) | Function::NativeEnv(_)) {
+            return Err(anyhow!("Builtin functions take no keyword arguments (got '{}')", kwargs[0].0));
+        }
+        match f {
+            Function::Native(fun) => fun(args),
+            Function::NativeEnv(fun) => fun(self, args),
+            Function::User { params, body } => self.with_call_env(defined_as, |child| {
+                // bind positional then keyword args, falling back to defaults
+                crate::eval::bind_params(child, params.iter(), args, kwargs)?;
+                // Generators run eagerly: the whole body executes on the call
+                // and the yielded values come back as a list, which `for`
+                // loops and list builtins consume like any other list.
+                let generator = crate::eval::contains_yield(body);
+                if generator {
+                    child.yields = Some(Vec::new());
+                }
+                // run body; a `return` in a generator just ends it
+                let result = crate::eval::run_function_body(body, child)?;
+                if generator {
+                    return Ok(Value::List(child.yields.take().unwrap_or_default()));
+                }
+                Ok(result)
+            }),
+            Function::Partial { func, args: bound } => {
+                let mut all = bound.clone();
+                all.extend(args);
+                self.call_value_kw(func, all, kwargs)
+            }
+        }
+    }
+
     pub fn import_module(&mut self, name: &str) -> Result<()> {
         let path = if name.ends_with(".fm") { name.to_string() } else { format!("{}.fm", name) };
         let p = Path::new(&path);
-        if !p.exists() {
+        if self.sandboxed || !p.exists() {
             // If a module file isn't present, try to expose a builtin stdlib module
             // by creating a Dict mapping attribute names to function names so scripts
             // can do `import iced` and call `iced.Window()` etc.
@@ -220,3 +686,95 @@ impl Env {
         Err(anyhow!("Cannot set attribute {} on {}", attr, obj))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(xs: &[i64]) -> Value {
+        Value::List(xs.iter().map(|i| Value::Int(*i)).collect())
+    }
+
+    #[test]
+    fn test_restore_rolls_back_bindings() {
+        let mut env = Env::new();
+        env.set("keep".to_string(), Value::Int(1));
+        let snap = env.snapshot();
+
+        env.set("keep".to_string(), Value::Int(2));
+        env.set("added".to_string(), Value::Int(3));
+        env.set_func("f".to_string(), Function::Native(|_| Ok(Value::None)));
+        env.restore(snap.clone());
+
+        assert!(env.get("added").is_none());
+        assert!(env.get_func("f").is_none());
+        assert_eq!(env.get("keep"), Some(&Value::Int(1)));
+
+        // a snapshot can be restored more than once
+        env.set("added".to_string(), Value::Int(4));
+        env.restore(snap);
+        assert!(env.get("added").is_none());
+    }
+
+    #[test]
+    fn test_deep_copy_is_independent() {
+        let original = Value::List(vec![ints(&[1, 2]), ints(&[3])]);
+        let mut copy = original.deep_copy();
+        if let Value::List(outer) = &mut copy {
+            if let Value::List(inner) = &mut outer[0] {
+                inner.push(Value::Int(99));
+            }
+        }
+        assert!(original == Value::List(vec![ints(&[1, 2]), ints(&[3])]));
+        assert!(copy == Value::List(vec![ints(&[1, 2, 99]), ints(&[3])]));
+    }
+
+    #[test]
+    fn test_list_ordering_is_lexicographic() {
+        assert!(ints(&[1, 2]) < ints(&[1, 3]));
+        assert!(ints(&[1]) < ints(&[1, 0]));
+        assert!(ints(&[2]) > ints(&[1, 9]));
+        assert!(ints(&[1, 2]) == ints(&[1, 2]));
+        assert_eq!(Value::List(vec![Value::Int(1)]).partial_cmp(&Value::List(vec![Value::Str("a".into())])), None);
+    }
+
+    #[test]
+    fn test_output_hook_receives_printed_lines() {
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = lines.clone();
+        let mut env = Env::new();
+        env.set_output_hook(move |line| sink.borrow_mut().push(line.to_string()));
+        crate::parser::parse_and_run("print(\"hello\", 1)\ndef greet(n):\n    print(\"hi \" + n)\ngreet(\"fm\")", &mut env).unwrap();
+        assert_eq!(*lines.borrow(), vec!["hello 1".to_string(), "hi fm".to_string()]);
+    }
+
+    #[test]
+    fn test_profiler_puts_slow_function_first() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let profiler = env.enable_profiling();
+        let src = "def slow():\n    sleep(0.05)\ndef fast(n):\n    return n + 1\ndef fact(n):\n    if n <= 1:\n        return 1\n    return n * fact(n - 1)\nfor i in range(0, 20):\n    fast(i)\nslow()\nfact(5)";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        let entries = profiler.entries();
+        assert_eq!(entries[0].0, "slow");
+        assert!(entries[0].2 >= Duration::from_millis(50));
+        let calls = |name: &str| entries.iter().find(|e| e.0 == name).map(|e| e.1);
+        assert_eq!(calls("fast"), Some(20));
+        assert_eq!(calls("fact"), Some(5));
+        // builtins aren't user functions and stay out of the report
+        assert_eq!(calls("sleep"), None);
+        assert!(profiler.report().lines().nth(1).unwrap().starts_with("slow "));
+    }
+
+    #[test]
+    fn test_runaway_recursion_is_an_error() {
+        let mut env = Env::new();
+        let src = "def f(n):\n    return f(n + 1)\ntry:\n    f(0)\nexcept Exception as e:\n    caught = str(e)";
+        crate::stdlib::register_stdlib(&mut env);
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert_eq!(env.get("caught"), Some(&Value::Str("maximum recursion depth exceeded".to_string())));
+        // recursion below the limit still works
+        crate::parser::parse_and_run("def g(n):\n    if n == 0:\n        return 0\n    return g(n - 1) + 1\nr = g(900)", &mut env).unwrap();
+        assert_eq!(env.get("r"), Some(&Value::Int(900)));
+    }
+}
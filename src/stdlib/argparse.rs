@@ -0,0 +1,163 @@
+//! Argparse module - command-line argument parsing for scripts
+//!
+//! `parse_args(spec)` reads the script's arguments (those after the script
+//! path in `ferrum run script.fm ...`) according to a dict `spec`:
+//!
+//! - Keys starting with `-` are options. The value is the default, and its
+//!   type decides how the option is parsed: `False` makes a switch that is
+//!   set to `True` when present, an Int or Float default parses the value as
+//!   a number, a string default takes any value, and `None` marks a required
+//!   string option. Values are given as `--name value` or `--name=value`.
+//! - The optional `"positional"` key lists required positional argument
+//!   names in order.
+//!
+//! The result dict is keyed by option name without leading dashes (with
+//! inner dashes turned into underscores) and by positional name. Unknown
+//! options, missing values and missing required arguments are errors that
+//! include the usage text.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::runtime::Value;
+
+/// Result key for an option: `--dry-run` becomes `dry_run`
+fn key_for(flag: &str) -> String {
+    flag.trim_start_matches('-').replace('-', "_")
+}
+
+fn positional_names(spec: &HashMap<String, Value>) -> Result<Vec<String>> {
+    match spec.get("positional") {
+        None => Ok(Vec::new()),
+        Some(Value::List(names)) => names
+            .iter()
+            .map(|n| match n {
+                Value::Str(s) => Ok(s.clone()),
+                other => Err(anyhow!("positional names must be strings, got {}", other.to_string())),
+            })
+            .collect(),
+        Some(other) => Err(anyhow!("\"positional\" must be a list of names, got {}", other.to_string())),
+    }
+}
+
+/// One-line usage summary built from `spec`, e.g.
+/// `usage: [--name NAME] [--verbose] --out OUT input`
+pub fn usage(spec: &HashMap<String, Value>) -> String {
+    let mut flags: Vec<&String> = spec.keys().filter(|k| k.starts_with('-')).collect();
+    flags.sort();
+    let mut parts = vec!["usage:".to_string()];
+    for flag in flags {
+        let metavar = key_for(flag).to_uppercase();
+        parts.push(match &spec[flag] {
+            Value::Bool(_) => format!("[{}]", flag),
+            Value::None => format!("{} {}", flag, metavar),
+            _ => format!("[{} {}]", flag, metavar),
+        });
+    }
+    parts.extend(positional_names(spec).unwrap_or_default());
+    parts.join(" ")
+}
+
+fn convert(flag: &str, default: &Value, raw: &str) -> Result<Value> {
+    match default {
+        Value::Int(_) => raw.parse().map(Value::Int).map_err(|_| anyhow!("argument {}: expected an integer, got '{}'", flag, raw)),
+        Value::Float(_) => raw.parse().map(Value::Float).map_err(|_| anyhow!("argument {}: expected a number, got '{}'", flag, raw)),
+        _ => Ok(Value::Str(raw.to_string())),
+    }
+}
+
+/// Parse `argv` against `spec`, returning the dict of values
+pub fn parse(spec: &HashMap<String, Value>, argv: &[String]) -> Result<HashMap<String, Value>> {
+    let with_usage = |e: anyhow::Error| anyhow!("{}\n{}", e, usage(spec));
+    let positionals = positional_names(spec)?;
+
+    let mut out = HashMap::new();
+    for (flag, default) in spec.iter().filter(|(k, _)| k.starts_with('-')) {
+        if !matches!(default, Value::None) {
+            out.insert(key_for(flag), default.clone());
+        }
+    }
+
+    let mut given = Vec::new();
+    let mut args = argv.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') || arg == "-" {
+            given.push(arg.clone());
+            continue;
+        }
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) => (f, Some(v.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let default = spec.get(flag).ok_or_else(|| with_usage(anyhow!("unrecognized argument: {}", flag)))?;
+        let value = match default {
+            Value::Bool(_) => Value::Bool(true),
+            _ => {
+                let raw = match inline {
+                    Some(v) => v,
+                    None => args.next().cloned().ok_or_else(|| with_usage(anyhow!("argument {}: expected a value", flag)))?,
+                };
+                convert(flag, default, &raw).map_err(with_usage)?
+            }
+        };
+        out.insert(key_for(flag), value);
+    }
+
+    if given.len() > positionals.len() {
+        return Err(with_usage(anyhow!("unrecognized arguments: {}", given[positionals.len()..].join(" "))));
+    }
+    for (i, name) in positionals.iter().enumerate() {
+        match given.get(i) {
+            Some(v) => { out.insert(name.clone(), Value::Str(v.clone())); }
+            None => return Err(with_usage(anyhow!("missing required argument: {}", name))),
+        }
+    }
+    for (flag, default) in spec.iter().filter(|(k, _)| k.starts_with('-')) {
+        if matches!(default, Value::None) && !out.contains_key(&key_for(flag)) {
+            return Err(with_usage(anyhow!("missing required argument: {}", flag)));
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> HashMap<String, Value> {
+        let mut spec = HashMap::new();
+        spec.insert("--name".to_string(), Value::None);
+        spec.insert("--count".to_string(), Value::Int(1));
+        spec.insert("--verbose".to_string(), Value::Bool(false));
+        spec.insert("positional".to_string(), Value::List(vec![Value::Str("input".into())]));
+        spec
+    }
+
+    fn argv(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_flags_and_positionals() {
+        let parsed = parse(&spec(), &argv("--name ferrum --verbose data.txt --count=3")).unwrap();
+        assert!(parsed["name"] == Value::Str("ferrum".into()));
+        assert!(parsed["verbose"] == Value::Bool(true));
+        assert!(parsed["count"] == Value::Int(3));
+        assert!(parsed["input"] == Value::Str("data.txt".into()));
+    }
+
+    #[test]
+    fn test_parse_defaults_and_errors() {
+        let parsed = parse(&spec(), &argv("--name x in")).unwrap();
+        assert!(parsed["verbose"] == Value::Bool(false));
+        assert!(parsed["count"] == Value::Int(1));
+
+        let err = parse(&spec(), &argv("in")).unwrap_err().to_string();
+        assert!(err.contains("missing required argument: --name"));
+        assert!(err.contains("usage: [--count COUNT] --name NAME [--verbose] input"));
+        assert!(parse(&spec(), &argv("--name x --bogus in")).is_err());
+        assert!(parse(&spec(), &argv("--name x --count many in")).is_err());
+        assert!(parse(&spec(), &argv("--name x")).is_err());
+    }
+}
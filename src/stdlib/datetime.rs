@@ -0,0 +1,91 @@
+//! Datetime module - arithmetic on timestamps and durations
+//!
+//! Timestamps are Unix seconds, as returned by `time()`, and may be integers
+//! or floats. Results stay integers when every input is an integer.
+
+use anyhow::{anyhow, Result};
+
+use crate::runtime::Value;
+
+fn seconds(v: Option<&Value>, what: &str) -> Result<Value> {
+    match v {
+        Some(Value::Int(i)) => Ok(Value::Int(*i)),
+        Some(Value::Float(f)) => Ok(Value::Float(*f)),
+        _ => Err(anyhow!("{} must be a number of seconds", what)),
+    }
+}
+
+fn combine(name: &str, a: Value, b: Value, op: fn(f64, f64) -> f64, int_op: fn(i64, i64) -> Option<i64>) -> Result<Value> {
+    match (a, b) {
+        (Value::Int(x), Value::Int(y)) => int_op(x, y)
+            .map(Value::Int)
+            .ok_or_else(|| anyhow!("Integer overflow in {}({}, {})", name, x, y)),
+        (x, y) => Ok(Value::Float(op(as_f64(&x), as_f64(&y)))),
+    }
+}
+
+fn as_f64(v: &Value) -> f64 {
+    match v {
+        Value::Int(i) => *i as f64,
+        Value::Float(f) => *f,
+        _ => 0.0,
+    }
+}
+
+/// Seconds from timestamp `b` to timestamp `a` (`a - b`)
+pub fn datetime_diff(args: Vec<Value>) -> Result<Value> {
+    let a = seconds(args.first(), "datetime_diff() first timestamp")?;
+    let b = seconds(args.get(1), "datetime_diff() second timestamp")?;
+    combine("datetime_diff", a, b, |x, y| x - y, i64::checked_sub)
+}
+
+/// Timestamp `ts` shifted by `seconds` (negative to go back)
+pub fn datetime_add(args: Vec<Value>) -> Result<Value> {
+    let ts = seconds(args.first(), "datetime_add() timestamp")?;
+    let delta = seconds(args.get(1), "datetime_add() offset")?;
+    combine("datetime_add", ts, delta, |x, y| x + y, i64::checked_add)
+}
+
+/// Human-friendly duration such as "1h 2m 3s" or "2d 5m". Zero units are
+/// omitted, fractions of a second are truncated and negative durations get
+/// a leading "-".
+pub fn duration_str(total_seconds: f64) -> String {
+    let sign = if total_seconds < 0.0 { "-" } else { "" };
+    let mut rest = total_seconds.abs().trunc() as u64;
+    let mut parts = Vec::new();
+    for (unit, size) in [("d", 86_400), ("h", 3_600), ("m", 60), ("s", 1)] {
+        let n = rest / size;
+        rest %= size;
+        if n > 0 {
+            parts.push(format!("{}{}", n, unit));
+        }
+    }
+    if parts.is_empty() {
+        return "0s".to_string();
+    }
+    format!("{}{}", sign, parts.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_str() {
+        assert_eq!(duration_str(3723.0), "1h 2m 3s");
+        assert_eq!(duration_str(0.4), "0s");
+        assert_eq!(duration_str(59.9), "59s");
+        assert_eq!(duration_str(90_000.0), "1d 1h");
+        assert_eq!(duration_str(-61.0), "-1m 1s");
+    }
+
+    #[test]
+    fn test_diff_and_add() {
+        assert!(datetime_diff(vec![Value::Int(100), Value::Int(40)]).unwrap() == Value::Int(60));
+        assert!(datetime_add(vec![Value::Int(100), Value::Float(0.5)]).unwrap() == Value::Float(100.5));
+        assert!(datetime_add(vec![Value::Str("now".into()), Value::Int(1)]).is_err());
+        let err = datetime_add(vec![Value::Int(i64::MAX), Value::Int(1)]).unwrap_err();
+        assert!(err.to_string().contains("Integer overflow"), "{}", err);
+        assert!(datetime_diff(vec![Value::Int(i64::MIN), Value::Int(1)]).is_err());
+    }
+}
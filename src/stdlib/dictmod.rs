@@ -0,0 +1,90 @@
+//! Dict module - methods callable on dict values
+//!
+//! `eval_expr` routes `d.method(args)` here whenever the receiver evaluates
+//! to a dict that isn't acting as a module. Like list methods, a mutating
+//! method changes a copy that the caller stores back into the receiver.
+//! Keys, values and items come back in sorted key order so scripts behave
+//! the same on every run.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::runtime::Value;
+
+/// True when the method `name` changes the dict it is called on
+pub fn is_mutating(name: &str) -> bool {
+    matches!(name, "update")
+}
+
+/// The dict's keys in sorted order
+pub fn sorted_keys(map: &HashMap<String, Value>) -> Vec<String> {
+    let mut keys: Vec<String> = map.keys().cloned().collect();
+    keys.sort();
+    keys
+}
+
+/// Call the dict method `name` on `map` with the evaluated `args`
+pub fn call_method(map: &mut HashMap<String, Value>, name: &str, args: Vec<Value>) -> Result<Value> {
+    match name {
+        "keys" => Ok(Value::List(sorted_keys(map).into_iter().map(Value::Str).collect())),
+        "values" => Ok(Value::List(sorted_keys(map).iter().map(|k| map[k].clone()).collect())),
+        "items" => Ok(Value::List(
+            sorted_keys(map)
+                .into_iter()
+                .map(|k| {
+                    let v = map[&k].clone();
+                    Value::List(vec![Value::Str(k), v])
+                })
+                .collect(),
+        )),
+        "get" => {
+            let default = args.get(1).cloned().unwrap_or(Value::None);
+            match args.first() {
+                Some(Value::Str(key)) => Ok(map.get(key).cloned().unwrap_or(default)),
+                Some(_) => Ok(default),
+                None => Err(anyhow!("get() takes a key and an optional default")),
+            }
+        }
+        "update" => match args.into_iter().next() {
+            Some(Value::Dict(other)) => {
+                map.extend(other);
+                Ok(Value::None)
+            }
+            _ => Err(anyhow!("update() takes a dict")),
+        },
+        _ => Err(anyhow!("Unknown dict method: {}", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dict(pairs: &[(&str, i64)]) -> HashMap<String, Value> {
+        pairs.iter().map(|(k, v)| (k.to_string(), Value::Int(*v))).collect()
+    }
+
+    #[test]
+    fn test_keys_values_items_are_sorted() {
+        let mut map = dict(&[("b", 2), ("a", 1), ("c", 3)]);
+        let strs = |xs: &[&str]| Value::List(xs.iter().map(|x| Value::Str(x.to_string())).collect());
+        assert_eq!(call_method(&mut map, "keys", vec![]).unwrap(), strs(&["a", "b", "c"]));
+        assert_eq!(call_method(&mut map, "values", vec![]).unwrap(), Value::List(vec![Value::Int(1), Value::Int(2), Value::Int(3)]));
+        match call_method(&mut map, "items", vec![]).unwrap() {
+            Value::List(items) => assert_eq!(items[0], Value::List(vec![Value::Str("a".into()), Value::Int(1)])),
+            other => panic!("expected list, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_get_and_update() {
+        let mut map = dict(&[("a", 1)]);
+        assert_eq!(call_method(&mut map, "get", vec![Value::Str("a".into())]).unwrap(), Value::Int(1));
+        assert_eq!(call_method(&mut map, "get", vec![Value::Str("z".into())]).unwrap(), Value::None);
+        assert_eq!(call_method(&mut map, "get", vec![Value::Str("z".into()), Value::Int(0)]).unwrap(), Value::Int(0));
+        call_method(&mut map, "update", vec![Value::Dict(dict(&[("a", 5), ("b", 6)]))]).unwrap();
+        assert_eq!(map, dict(&[("a", 5), ("b", 6)]));
+        assert!(call_method(&mut map, "update", vec![Value::Int(1)]).is_err());
+    }
+}
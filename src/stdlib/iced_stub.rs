@@ -17,43 +17,96 @@
 
 #[cfg(feature = "real-iced")]
 pub mod iced_real {
-    use iced::{Application, Command, Element, Settings, widget, executor};
+    use iced::{Application, Command, Element, Settings, Subscription, widget, executor};
+    use std::sync::{Arc, Mutex};
+    use crate::runtime::Env;
 
     /// App that dynamically builds its view from the ui registry.
+    /// 
+    /// The app holds the script's `Env` while the window is open so widget
+    /// messages can run their Ferrum callbacks (see the event model in `ui`).
     pub struct SimpleApp {
         label: String,
         window_id: String,
+        env: Arc<Mutex<Env>>,
     }
 
     #[derive(Debug, Clone)]
     pub enum Msg {
-        Pressed,
+        /// A button with the given registry id was pressed
+        Pressed(String),
+        /// A slider with the given registry id was moved
+        SliderChanged(String, f64),
+        /// A dropdown with the given registry id had an option picked
+        Selected(String, String),
+        /// A key was pressed while the window had focus
+        Key(String),
     }
 
     impl Application for SimpleApp {
         type Executor = executor::Default;
         type Message = Msg;
         type Theme = iced::Theme;
-        type Flags = (String, String);
+        type Flags = (String, String, Arc<Mutex<Env>>);
 
         fn new(flags: Self::Flags) -> (Self, Command<Self::Message>) {
-            let (title, window_id) = flags;
-            (SimpleApp { label: title, window_id }, Command::none())
+            let (title, window_id, env) = flags;
+            (SimpleApp { label: title, window_id, env }, Command::none())
         }
 
         fn title(&self) -> String {
             self.label.clone()
         }
 
+        fn theme(&self) -> Self::Theme {
+            match crate::ui::get(&self.window_id) {
+                Some(crate::ui::UiObj::Window(w)) if w.theme == "dark" => iced::Theme::Dark,
+                _ => iced::Theme::Light,
+            }
+        }
+
         fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
             match message {
-                Msg::Pressed => {
-                    
+                Msg::Pressed(id) => {
+                    let mut env = self.env.lock().unwrap();
+                    if let Err(e) = crate::ui::dispatch(&mut env, &id, "click", vec![]) {
+                        eprintln!("[iced_real] click handler failed: {}", e);
+                    }
+                }
+                Msg::SliderChanged(id, value) => {
+                    let value = value.round() as i64;
+                    crate::ui::set_slider_value(&id, value);
+                    let mut env = self.env.lock().unwrap();
+                    if let Err(e) = crate::ui::dispatch(&mut env, &id, "change", vec![crate::runtime::Value::Int(value)]) {
+                        eprintln!("[iced_real] change handler failed: {}", e);
+                    }
+                }
+                Msg::Key(key) => {
+                    let mut env = self.env.lock().unwrap();
+                    if let Err(e) = crate::ui::dispatch(&mut env, &self.window_id, "key", vec![crate::runtime::Value::Str(key)]) {
+                        eprintln!("[iced_real] key handler failed: {}", e);
+                    }
+                }
+                Msg::Selected(id, value) => {
+                    crate::ui::set_selected(&id, &value);
+                    let mut env = self.env.lock().unwrap();
+                    if let Err(e) = crate::ui::dispatch(&mut env, &id, "select", vec![crate::runtime::Value::Str(value)]) {
+                        eprintln!("[iced_real] select handler failed: {}", e);
+                    }
                 }
             }
             Command::none()
         }
 
+        fn subscription(&self) -> Subscription<Self::Message> {
+            iced::subscription::events_with(|event, _status| match event {
+                iced::Event::Keyboard(iced::keyboard::Event::KeyPressed { key_code, .. }) => {
+                    Some(Msg::Key(format!("{:?}", key_code)))
+                }
+                _ => None,
+            })
+        }
+
         fn view(&self) -> Element<'_, Self::Message> {
             // Build the view dynamically from the ui registry
             build_view_from_registry(&self.window_id)
@@ -63,46 +116,113 @@ pub mod iced_real {
     /// Build an iced view from the ui registry window.
     fn build_view_from_registry(window_id: &str) -> Element<'static, Msg> {
         use crate::ui;
-        if let Some(obj) = ui::get(window_id) {
-            if let ui::UiObj::Window(w) = obj {
+        if let Some(ui::UiObj::Window(w)) = ui::get(window_id) {
+            let mut col = widget::Column::new();
+            for child_id in &w.children {
+                if let Some(child) = build_widget(child_id) {
+                    col = col.push(child);
+                }
+            }
+            return col.into();
+        }
+        // Fallback: empty column if registry lookup fails
+        widget::Column::new().into()
+    }
+
+    /// Build the iced element for a registry object, recursing into
+    /// Column/Row containers so nested layouts render.
+    fn build_widget(id: &str) -> Option<Element<'static, Msg>> {
+        use crate::ui;
+        let element: Element<'static, Msg> = match ui::get(id)? {
+            ui::UiObj::Text(t) => widget::Text::new(t.content).into(),
+            ui::UiObj::Button(b) => {
+                widget::Button::new(widget::Text::new(b.label.clone()))
+                    .on_press(Msg::Pressed(id.to_string()))
+                    .into()
+            }
+            ui::UiObj::Slider(s) => {
+                // Convert i64 range to f64 for iced slider
+                let id = id.to_string();
+                widget::Slider::new(
+                    (s.min as f64)..=(s.max as f64), 
+                    s.value as f64, 
+                    move |v| Msg::SliderChanged(id.clone(), v)
+                ).into()
+            }
+            ui::UiObj::ProgressBar(p) => {
+                widget::ProgressBar::new((p.min as f32)..=(p.max as f32), p.value as f32).into()
+            }
+            ui::UiObj::Radio(r) => {
+                // For now, just show a text label for radio buttons
+                widget::Text::new(format!("○ {}", r.label)).into()
+            }
+            ui::UiObj::Dropdown(d) => {
+                let id = id.to_string();
+                widget::PickList::new(
+                    d.options,
+                    d.selected,
+                    move |v| Msg::Selected(id.clone(), v)
+                ).into()
+            }
+            ui::UiObj::Image(i) => build_image(&i.path),
+            ui::UiObj::Column(c) => {
                 let mut col = widget::Column::new();
-                for child_id in &w.children {
-                    if let Some(child_obj) = ui::get(child_id) {
-                        match child_obj {
-                            ui::UiObj::Button(b) => {
-                                let btn = widget::Button::new(widget::Text::new(b.label.clone()))
-                                    .on_press(Msg::Pressed);
-                                col = col.push(btn);
-                            }
-                            ui::UiObj::Slider(s) => {
-                                // Convert i64 range to f64 for iced slider
-                                let slider = widget::Slider::new(
-                                    (s.min as f64)..=(s.max as f64), 
-                                    50.0, 
-                                    |_| Msg::Pressed
-                                );
-                                col = col.push(slider);
-                            }
-                            ui::UiObj::Radio(r) => {
-                                // For now, just show a text label for radio buttons
-                                let label = widget::Text::new(format!("○ {}", r.label));
-                                col = col.push(label);
-                            }
-                            _ => {}
+                for child_id in &c.children {
+                    if let Some(child) = build_widget(child_id) {
+                        col = col.push(child);
+                    }
+                }
+                col.into()
+            }
+            ui::UiObj::Row(r) => {
+                let mut row = widget::Row::new();
+                for child_id in &r.children {
+                    if let Some(child) = build_widget(child_id) {
+                        row = row.push(child);
+                    }
+                }
+                row.into()
+            }
+            ui::UiObj::Grid(g) => {
+                let mut col = widget::Column::new();
+                for cells in g.rows() {
+                    let mut row = widget::Row::new();
+                    for child_id in &cells {
+                        if let Some(child) = build_widget(child_id) {
+                            row = row.push(child);
                         }
                     }
+                    col = col.push(row);
                 }
-                return col.into();
+                col.into()
             }
+            ui::UiObj::Window(_) => return None,
+        };
+        Some(element)
+    }
+
+    /// Show the picture at `path`, or a text placeholder when the file is
+    /// missing or image support is not compiled in.
+    fn build_image(path: &str) -> Element<'static, Msg> {
+        if !std::path::Path::new(path).is_file() {
+            return widget::Text::new(format!("[missing image: {}]", path)).into();
+        }
+        #[cfg(feature = "real-iced-image")]
+        {
+            widget::image::Image::new(path.to_string()).into()
+        }
+        #[cfg(not(feature = "real-iced-image"))]
+        {
+            widget::Text::new(format!("[image: {}]", path)).into()
         }
-        // Fallback: empty column if registry lookup fails
-        widget::Column::new().into()
     }
 
     /// Launch a window by window_id, reading from the ui registry.
-    pub fn launch_window(window_id: &str, title: &str) {
+    /// 
+    /// Blocks until the window is closed; callbacks run against `env`.
+    pub fn launch_window(window_id: &str, title: &str, env: Arc<Mutex<Env>>) {
         let t = title.to_string();
-        let settings = Settings { flags: (t, window_id.to_string()), ..Settings::default() };
+        let settings = Settings::with_flags((t, window_id.to_string(), env));
         SimpleApp::run(settings).expect("Iced run failed");
     }
 
@@ -422,7 +542,7 @@ pub mod iced {
         /// Create a new Window with default properties (800x600, no title, no icon)
         /// 
         /// # Example
-        /// ```
+        /// ```ignore
         /// let w = Window()
         /// w.set_title("My App")
         /// ```
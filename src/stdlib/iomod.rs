@@ -2,8 +2,28 @@
 //!
 //! This module provides functions for reading from and writing to files and streams.
 
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, BufRead, BufReader, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Open line readers keyed by handle ID, see [`open_lines`]
+static LINE_READERS: Lazy<Mutex<HashMap<String, BufReader<fs::File>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_READER: AtomicUsize = AtomicUsize::new(1);
+
+/// A file opened with [`open_file`]: buffered for reading, or plain for
+/// writing and appending
+enum FileHandle {
+    Read(BufReader<fs::File>),
+    Write(fs::File),
+}
+
+/// Open file handles keyed by handle ID, see [`open_file`]
+static FILES: Lazy<Mutex<HashMap<String, FileHandle>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
 /// Read an entire file into a string
 pub fn read_file(path: &str) -> Result<String, io::Error> {
@@ -31,6 +51,25 @@ pub fn read_input() -> Result<String, io::Error> {
     Ok(buffer.trim_end().to_string())
 }
 
+/// Prompt for a line from `reader` and parse it as `T`.
+/// 
+/// Invalid input is reported on stdout and the prompt is shown again until a
+/// line parses. Running out of input (EOF) is an error, so scripts fed from a
+/// pipe don't loop forever.
+pub fn read_parsed<T: FromStr>(prompt: &str, reader: &mut impl BufRead) -> Result<T, io::Error> {
+    loop {
+        write_output(prompt)?;
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "end of input"));
+        }
+        match line.trim().parse::<T>() {
+            Ok(v) => return Ok(v),
+            Err(_) => println!("Invalid input '{}', please try again", line.trim()),
+        }
+    }
+}
+
 /// Write to stdout
 pub fn write_output(msg: &str) -> Result<(), io::Error> {
     io::stdout().write_all(msg.as_bytes())?;
@@ -53,7 +92,137 @@ pub fn read_lines(path: &str) -> Result<Vec<String>, io::Error> {
     Ok(content.lines().map(|s| s.to_string()).collect())
 }
 
+/// Open `path` for reading one line at a time and return a handle ID for
+/// [`next_line`]. Only the current line is held in memory.
+pub fn open_lines(path: &str) -> Result<String, io::Error> {
+    let reader = BufReader::new(fs::File::open(path)?);
+    let id = format!("lines-{}", NEXT_READER.fetch_add(1, Ordering::Relaxed));
+    LINE_READERS.lock().unwrap().insert(id.clone(), reader);
+    Ok(id)
+}
+
+/// Next line from an [`open_lines`] handle without its line ending, or `None`
+/// at end of file. The handle is closed once it is exhausted.
+pub fn next_line(id: &str) -> Result<Option<String>, io::Error> {
+    let mut readers = LINE_READERS.lock().unwrap();
+    let Some(reader) = readers.get_mut(id) else {
+        return Ok(None);
+    };
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        readers.remove(id);
+        return Ok(None);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(Some(line))
+}
+
+/// Close an [`open_lines`] handle early, e.g. after breaking out of a loop
+pub fn close_lines(id: &str) {
+    LINE_READERS.lock().unwrap().remove(id);
+}
+
+/// Open `path` with mode `"r"` (read), `"w"` (truncate and write) or `"a"`
+/// (append, creating the file if needed) and return a handle ID for the
+/// `file_*` functions
+pub fn open_file(path: &str, mode: &str) -> Result<String, io::Error> {
+    let handle = match mode {
+        "r" => FileHandle::Read(BufReader::new(fs::File::open(path)?)),
+        "w" => FileHandle::Write(fs::File::create(path)?),
+        "a" => FileHandle::Write(fs::OpenOptions::new().create(true).append(true).open(path)?),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid mode '{}', expected \"r\", \"w\" or \"a\"", mode))),
+    };
+    let id = format!("file-{}", NEXT_READER.fetch_add(1, Ordering::Relaxed));
+    FILES.lock().unwrap().insert(id.clone(), handle);
+    Ok(id)
+}
+
+fn with_reader<T>(id: &str, f: impl FnOnce(&mut BufReader<fs::File>) -> Result<T, io::Error>) -> Result<T, io::Error> {
+    match FILES.lock().unwrap().get_mut(id) {
+        Some(FileHandle::Read(reader)) => f(reader),
+        Some(FileHandle::Write(_)) => Err(io::Error::other("file not open for reading")),
+        None => Err(io::Error::other("I/O operation on closed file")),
+    }
+}
+
+/// The rest of an [`open_file`] handle's contents
+pub fn file_read(id: &str) -> Result<String, io::Error> {
+    with_reader(id, |reader| {
+        let mut content = String::new();
+        io::Read::read_to_string(reader, &mut content)?;
+        Ok(content)
+    })
+}
+
+/// The next line including its line ending, or "" at end of file
+pub fn file_readline(id: &str) -> Result<String, io::Error> {
+    with_reader(id, |reader| {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        Ok(line)
+    })
+}
+
+/// Write `content` to a handle opened with `"w"` or `"a"`
+pub fn file_write(id: &str, content: &str) -> Result<(), io::Error> {
+    match FILES.lock().unwrap().get_mut(id) {
+        Some(FileHandle::Write(file)) => file.write_all(content.as_bytes()),
+        Some(FileHandle::Read(_)) => Err(io::Error::other("file not open for writing")),
+        None => Err(io::Error::other("I/O operation on closed file")),
+    }
+}
+
+/// Close an [`open_file`] handle, flushing any written data. Closing twice
+/// is harmless.
+pub fn file_close(id: &str) -> Result<(), io::Error> {
+    match FILES.lock().unwrap().remove(id) {
+        Some(FileHandle::Write(mut file)) => file.flush(),
+        _ => Ok(()),
+    }
+}
+
 /// Write lines to a file (one per line)
 pub fn write_lines(path: &str, lines: Vec<&str>) -> Result<(), io::Error> {
     fs::write(path, lines.join("\n"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_are_read_one_at_a_time() {
+        let path = std::env::temp_dir().join(format!("ferrum_lines_{}.txt", std::process::id()));
+        fs::write(&path, "first\nsecond\r\n\nlast").unwrap();
+        let id = open_lines(path.to_str().unwrap()).unwrap();
+        assert_eq!(next_line(&id).unwrap().as_deref(), Some("first"));
+        assert_eq!(next_line(&id).unwrap().as_deref(), Some("second"));
+        assert_eq!(next_line(&id).unwrap().as_deref(), Some(""));
+        assert_eq!(next_line(&id).unwrap().as_deref(), Some("last"));
+        assert_eq!(next_line(&id).unwrap(), None);
+        // exhausted handles are released
+        assert!(!LINE_READERS.lock().unwrap().contains_key(&id));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_parsed_valid_input() {
+        let mut input = io::Cursor::new("42\n");
+        assert_eq!(read_parsed::<i64>("> ", &mut input).unwrap(), 42);
+        let mut input = io::Cursor::new(" 2.5 \n");
+        assert_eq!(read_parsed::<f64>("> ", &mut input).unwrap(), 2.5);
+    }
+
+    #[test]
+    fn test_read_parsed_reprompts_until_valid() {
+        let mut input = io::Cursor::new("abc\n\n7\n");
+        assert_eq!(read_parsed::<i64>("> ", &mut input).unwrap(), 7);
+        let mut input = io::Cursor::new("abc\n");
+        assert!(read_parsed::<i64>("> ", &mut input).is_err());
+    }
+}
@@ -4,27 +4,25 @@
 
 use serde_json::Value as JsonValue;
 
-/// Serialize a Ferrum value to JSON string
+/// Serialize a Ferrum value to JSON string. Dict keys come out sorted;
+/// values with no JSON form (functions, instances, NaN) become `null`.
 pub fn dumps(value: &crate::runtime::Value) -> String {
+    ferrum_to_json_value(value).to_string()
+}
+
+/// Convert a Ferrum Value to a serde_json Value
+fn ferrum_to_json_value(value: &crate::runtime::Value) -> JsonValue {
     match value {
-        crate::runtime::Value::None => "null".to_string(),
-        crate::runtime::Value::Bool(b) => b.to_string(),
-        crate::runtime::Value::Int(i) => i.to_string(),
-        crate::runtime::Value::Float(f) => f.to_string(),
-        crate::runtime::Value::Str(s) => format!(r#""{}""#, s.replace('\\', "\\\\").replace('"', "\\\"")),
-        crate::runtime::Value::List(items) => {
-            let json_items: Vec<String> = items.iter().map(|v| dumps(v)).collect();
-            format!("[{}]", json_items.join(","))
-        }
+        crate::runtime::Value::Bool(b) => JsonValue::Bool(*b),
+        crate::runtime::Value::Int(i) => JsonValue::from(*i),
+        crate::runtime::Value::Float(f) => serde_json::Number::from_f64(*f).map(JsonValue::Number).unwrap_or(JsonValue::Null),
+        crate::runtime::Value::Str(s) => JsonValue::String(s.clone()),
+        crate::runtime::Value::List(items) => JsonValue::Array(items.iter().map(ferrum_to_json_value).collect()),
+        crate::runtime::Value::Range(start, end) => JsonValue::Array((*start..*end).map(JsonValue::from).collect()),
         crate::runtime::Value::Dict(map) => {
-            let mut json_items: Vec<String> = map
-                .iter()
-                .map(|(k, v)| format!(r#""{}":{}  "#, k, dumps(v)))
-                .collect();
-            json_items.sort();
-            format!("{{{}}}", json_items.join(","))
+            JsonValue::Object(map.iter().map(|(k, v)| (k.clone(), ferrum_to_json_value(v))).collect())
         }
-        _ => "null".to_string(),
+        _ => JsonValue::Null,
     }
 }
 
@@ -64,3 +62,48 @@ fn json_to_ferrum_value(json_val: &JsonValue) -> crate::runtime::Value {
         }
     }
 }
+
+/// Walk `value` along a dotted path such as `"a.b.0.c"`. Numeric segments
+/// index into lists; on dicts every segment is a key. Returns `None` when any
+/// step is missing.
+pub fn get_path<'a>(value: &'a crate::runtime::Value, path: &str) -> Option<&'a crate::runtime::Value> {
+    let mut current = value;
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        current = match current {
+            crate::runtime::Value::Dict(map) => map.get(segment)?,
+            crate::runtime::Value::List(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Value;
+
+    #[test]
+    fn test_dumps_is_valid_json() {
+        let doc = loads(r#"{"b": [1, 2.5, "x\"y\n"], "a": {"n": null, "t": true}}"#).unwrap();
+        assert_eq!(dumps(&doc), r#"{"a":{"n":null,"t":true},"b":[1,2.5,"x\"y\n"]}"#);
+        assert_eq!(loads(&dumps(&doc)).unwrap(), doc);
+    }
+
+    #[test]
+    fn test_get_path_nested() {
+        let doc = loads(r#"{"a": {"b": [{"c": 1}, {"c": {"d": "deep"}}]}}"#).unwrap();
+        assert_eq!(get_path(&doc, "a.b.1.c.d"), Some(&Value::Str("deep".to_string())));
+        assert_eq!(get_path(&doc, "a.b.0.c"), Some(&Value::Int(1)));
+        assert_eq!(get_path(&doc, ""), Some(&doc));
+    }
+
+    #[test]
+    fn test_get_path_missing() {
+        let doc = loads(r#"{"a": {"b": [1, 2]}}"#).unwrap();
+        assert_eq!(get_path(&doc, "a.b.5"), None);
+        assert_eq!(get_path(&doc, "a.x"), None);
+        assert_eq!(get_path(&doc, "a.b.first"), None);
+        assert_eq!(get_path(&doc, "a.b.0.c"), None);
+    }
+}
@@ -0,0 +1,168 @@
+//! List module - methods callable on list values
+//!
+//! `eval_expr` routes `xs.method(args)` here whenever the receiver evaluates
+//! to a list. Lists are values, so a mutating method changes a copy that the
+//! caller stores back into the receiver when it names a variable, attribute
+//! or subscript (`xs`, `self.items`, `groups[k]`). Mutating an anonymous list
+//! such as `[3, 1].sort()` has no lasting effect.
+
+use anyhow::{anyhow, Result};
+
+use crate::runtime::Value;
+
+/// True when the method `name` changes the list it is called on
+pub fn is_mutating(name: &str) -> bool {
+    matches!(name, "append" | "pop" | "insert" | "remove" | "sort" | "reverse")
+}
+
+/// `items` in ascending order, or an error naming the first pair of values
+/// that can't be compared (such as an int and a str).
+///
+/// This is a stable merge sort rather than `sort_by`, since `Value` has no
+/// total order and std's sort may panic when the comparison isn't one.
+pub fn sort_values(items: Vec<Value>) -> Result<Vec<Value>> {
+    if items.len() < 2 {
+        return Ok(items);
+    }
+    let mut left = items;
+    let right = left.split_off(left.len() / 2);
+    let mut left = sort_values(left)?.into_iter().peekable();
+    let mut right = sort_values(right)?.into_iter().peekable();
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        let ord = a
+            .partial_cmp(b)
+            .ok_or_else(|| anyhow!("cannot compare {} and {}", a.type_name(), b.type_name()))?;
+        // ties take from the left half, keeping equal values in order
+        let next = if ord == std::cmp::Ordering::Greater { right.next() } else { left.next() };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+/// Call the list method `name` on `items` with the evaluated `args`
+pub fn call_method(items: &mut Vec<Value>, name: &str, args: Vec<Value>) -> Result<Value> {
+    match name {
+        "append" => {
+            let value = args.into_iter().next().ok_or_else(|| anyhow!("append() takes one argument"))?;
+            items.push(value);
+            Ok(Value::None)
+        }
+        "pop" => {
+            if items.is_empty() {
+                return Err(anyhow!("pop from empty list"));
+            }
+            let pos = match args.first() {
+                None => items.len() - 1,
+                Some(_) => position(items.len(), index_arg(name, &args, 0)?, false)?,
+            };
+            Ok(items.remove(pos))
+        }
+        "insert" => {
+            let index = index_arg(name, &args, 0)?;
+            let value = args.get(1).cloned().ok_or_else(|| anyhow!("insert() takes an index and a value"))?;
+            // like Python, out-of-range positions insert at the nearest end
+            let pos = position(items.len(), index, true)?;
+            items.insert(pos, value);
+            Ok(Value::None)
+        }
+        "remove" => {
+            let value = args.first().ok_or_else(|| anyhow!("remove() takes one argument"))?;
+            let pos = items.iter().position(|v| v == value).ok_or_else(|| anyhow!("list.remove(x): x not in list"))?;
+            items.remove(pos);
+            Ok(Value::None)
+        }
+        "sort" => {
+            // sort a copy so a failed sort leaves the list as it was
+            *items = sort_values(items.clone()).map_err(|e| anyhow!("sort(): {}", e))?;
+            Ok(Value::None)
+        }
+        "reverse" => {
+            items.reverse();
+            Ok(Value::None)
+        }
+        "index" => {
+            let value = args.first().ok_or_else(|| anyhow!("index() takes one argument"))?;
+            items.iter()
+                .position(|v| v == value)
+                .map(|i| Value::Int(i as i64))
+                .ok_or_else(|| anyhow!("{} is not in list", value.to_string()))
+        }
+        "count" => {
+            let value = args.first().ok_or_else(|| anyhow!("count() takes one argument"))?;
+            Ok(Value::Int(items.iter().filter(|v| *v == value).count() as i64))
+        }
+        _ => Err(anyhow!("Unknown list method: {}", name)),
+    }
+}
+
+fn index_arg(method: &str, args: &[Value], i: usize) -> Result<i64> {
+    match args.get(i) {
+        Some(Value::Int(n)) => Ok(*n),
+        _ => Err(anyhow!("{}() expects an integer index", method)),
+    }
+}
+
+/// Resolve a possibly negative index against `len`. With `clamp`, indices
+/// past either end snap to it instead of failing.
+fn position(len: usize, index: i64, clamp: bool) -> Result<usize> {
+    let len = len as i64;
+    let pos = if index < 0 { len + index } else { index };
+    if clamp {
+        return Ok(pos.clamp(0, len) as usize);
+    }
+    if pos < 0 || pos >= len {
+        return Err(anyhow!("Index {} out of range for length {}", index, len));
+    }
+    Ok(pos as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(xs: &[i64]) -> Vec<Value> {
+        xs.iter().map(|x| Value::Int(*x)).collect()
+    }
+
+    #[test]
+    fn test_append_pop_insert_remove() {
+        let mut items = ints(&[1, 2]);
+        call_method(&mut items, "append", ints(&[3])).unwrap();
+        assert_eq!(items, ints(&[1, 2, 3]));
+        assert_eq!(call_method(&mut items, "pop", vec![]).unwrap(), Value::Int(3));
+        assert_eq!(call_method(&mut items, "pop", ints(&[0])).unwrap(), Value::Int(1));
+        call_method(&mut items, "insert", ints(&[0, 9])).unwrap();
+        call_method(&mut items, "insert", ints(&[99, 7])).unwrap();
+        assert_eq!(items, ints(&[9, 2, 7]));
+        call_method(&mut items, "remove", ints(&[2])).unwrap();
+        assert_eq!(items, ints(&[9, 7]));
+        assert!(call_method(&mut items, "remove", ints(&[5])).is_err());
+        assert!(call_method(&mut Vec::new(), "pop", vec![]).is_err());
+    }
+
+    #[test]
+    fn test_sort_reverse_index_count() {
+        let mut items = ints(&[3, 1, 2, 1]);
+        call_method(&mut items, "sort", vec![]).unwrap();
+        assert_eq!(items, ints(&[1, 1, 2, 3]));
+        call_method(&mut items, "reverse", vec![]).unwrap();
+        assert_eq!(items, ints(&[3, 2, 1, 1]));
+        assert_eq!(call_method(&mut items, "index", ints(&[1])).unwrap(), Value::Int(2));
+        assert_eq!(call_method(&mut items, "count", ints(&[1])).unwrap(), Value::Int(2));
+        assert!(call_method(&mut items, "index", ints(&[8])).is_err());
+        let mut mixed = vec![Value::Int(1), Value::Str("a".into())];
+        assert!(call_method(&mut mixed, "sort", vec![]).is_err());
+    }
+
+    #[test]
+    fn test_sort_mixed_list_is_an_error() {
+        let original: Vec<Value> = (0..60).map(|i| if i % 2 == 0 { Value::Int(i) } else { Value::Str(format!("s{}", i)) }).collect();
+        let mut items = original.clone();
+        let err = call_method(&mut items, "sort", vec![]).unwrap_err();
+        assert!(err.to_string().contains("cannot compare"), "{}", err);
+        assert_eq!(items, original);
+    }
+}
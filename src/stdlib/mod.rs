@@ -9,10 +9,90 @@ pub mod iced_stub;
 pub mod opencv;
 pub mod check;
 pub mod subprocess;
+pub mod strmod;
+pub mod listmod;
+pub mod dictmod;
+pub mod datetime;
+pub mod argparse;
+pub mod signals;
+pub mod net;
+pub mod statistics;
+pub mod term;
 
 use crate::runtime::{Env, Function, Value};
 use crate::ui;
 
+/// Builtins that reach outside the interpreter (files, processes, devices).
+/// Sandboxed environments replace them with stubs that raise an error.
+pub const SANDBOX_DISABLED: &[&str] = &[
+    "read_file", "read_file_lines", "write_file", "open", "listdir", "path_exists", "getcwd", "which", "walk", "tempfile", "tempdir",
+    "exit", "chdir", "isdir", "isfile", "mkdir", "makedirs", "remove_file", "remove_dir", "remove_dir_recursive", "getenv", "setenv",
+    "subprocess_run", "subprocess_popen", "subprocess_pipe", "kill_pid", "on_signal", "breakpoint",
+    "http_get", "http_post", "tcp_connect", "tcp_listen", "tcp_accept", "tcp_send", "tcp_recv", "tcp_close",
+    "cv_load_image", "cv_save_image", "cv_start_camera", "cv_open_writer",
+];
+
+fn sandbox_disabled(args: Vec<Value>) -> anyhow::Result<Value> {
+    let name = args.first().map(|v| v.to_string()).unwrap_or_default();
+    Err(anyhow::anyhow!("{}() is disabled in sandbox", name))
+}
+
+/// Register the standard library with every [`SANDBOX_DISABLED`] builtin
+/// stubbed out, for running untrusted code
+pub fn register_stdlib_sandboxed(env: &mut Env) {
+    register_stdlib(env);
+    for name in SANDBOX_DISABLED {
+        // the stub is a partial so the error can name the builtin that was called
+        let stub = Function::Partial {
+            func: Box::new(Value::Func("sandbox_disabled".to_string(), Function::Native(sandbox_disabled))),
+            args: vec![Value::Str(name.to_string())],
+        };
+        env.set_func(name.to_string(), stub);
+    }
+    // untrusted code may neither read nor change the process environment
+    let stub = Function::Partial {
+        func: Box::new(Value::Func("sandbox_disabled".to_string(), Function::Native(sandbox_disabled))),
+        args: vec![Value::Str("env".to_string())],
+    };
+    let methods = ["__getitem__", "__setitem__", "keys"].iter().map(|m| (m.to_string(), stub.clone())).collect();
+    env.set("env".to_string(), Value::Instance("Environ".to_string(), std::collections::HashMap::new(), methods));
+    env.sandboxed = true;
+}
+
+fn environ_get(args: Vec<Value>) -> anyhow::Result<Value> {
+    match args.get(1) {
+        Some(Value::Str(key)) => osmod::getenv(key)
+            .map(Value::Str)
+            .ok_or_else(|| anyhow::anyhow!("Environment variable not set: {}", key)),
+        _ => Err(anyhow::anyhow!("env keys must be strings")),
+    }
+}
+
+fn environ_set(args: Vec<Value>) -> anyhow::Result<Value> {
+    match (args.get(1), args.get(2)) {
+        (Some(Value::Str(key)), Some(value)) => {
+            osmod::setenv(key, &value.to_string());
+            Ok(Value::None)
+        }
+        _ => Err(anyhow::anyhow!("env keys must be strings")),
+    }
+}
+
+fn environ_keys(_args: Vec<Value>) -> anyhow::Result<Value> {
+    let mut keys: Vec<String> = std::env::vars().map(|(k, _)| k).collect();
+    keys.sort();
+    Ok(Value::List(keys.into_iter().map(Value::Str).collect()))
+}
+
+/// The `env` object: a live view of the process environment
+fn environ() -> Value {
+    let mut methods = std::collections::HashMap::new();
+    methods.insert("__getitem__".to_string(), Function::Native(environ_get));
+    methods.insert("__setitem__".to_string(), Function::Native(environ_set));
+    methods.insert("keys".to_string(), Function::Native(environ_keys));
+    Value::Instance("Environ".to_string(), std::collections::HashMap::new(), methods)
+}
+
 pub fn register_stdlib(env: &mut Env) {
     // math
     env.set_func("sin".to_string(), Function::Native(|args| {
@@ -106,19 +186,98 @@ pub fn register_stdlib(env: &mut Env) {
         Ok(Value::Float(a.log10()))
     }));
 
-    // random.randint
-    env.set_func("randint".to_string(), Function::Native(|args| {
-        let a = match args.get(0) {
-            Some(Value::Int(i)) => *i as i64,
-            _ => 0,
+    env.set("pi".to_string(), Value::Float(math::PI));
+    env.set("e".to_string(), Value::Float(math::E));
+
+    fn number_arg(name: &str, args: &[Value]) -> anyhow::Result<f64> {
+        match args.first() {
+            Some(Value::Float(f)) => Ok(*f),
+            Some(Value::Int(i)) => Ok(*i as f64),
+            _ => Err(anyhow::anyhow!("{}() requires a number", name)),
+        }
+    }
+    env.set_func("asin".to_string(), Function::Native(|args| Ok(Value::Float(math::asin(number_arg("asin", &args)?)))));
+    env.set_func("acos".to_string(), Function::Native(|args| Ok(Value::Float(math::acos(number_arg("acos", &args)?)))));
+    env.set_func("atan".to_string(), Function::Native(|args| Ok(Value::Float(math::atan(number_arg("atan", &args)?)))));
+    env.set_func("ceil".to_string(), Function::Native(|args| Ok(Value::Int(math::ceil(number_arg("ceil", &args)?)))));
+    env.set_func("floor".to_string(), Function::Native(|args| Ok(Value::Int(math::floor(number_arg("floor", &args)?)))));
+    // round(x) -> int; round(x, digits) -> float rounded to that many decimal places
+    env.set_func("round".to_string(), Function::Native(|args| {
+        let x = number_arg("round", &args)?;
+        match args.get(1) {
+            None => Ok(Value::Int(math::round(x))),
+            Some(Value::Int(digits)) => {
+                let scale = 10f64.powi(*digits as i32);
+                Ok(Value::Float((x * scale).round() / scale))
+            }
+            Some(_) => Err(anyhow::anyhow!("round() digits must be an integer")),
+        }
+    }));
+
+    // min(a, b) / max(a, b) or min(list) / max(list); ints stay ints
+    fn extreme(name: &str, args: &[Value], wanted: std::cmp::Ordering) -> anyhow::Result<Value> {
+        let items = match args {
+            [Value::List(items)] => items.as_slice(),
+            [Value::Range(start, end)] if start < end => {
+                return Ok(Value::Int(if wanted.is_lt() { *start } else { end - 1 }));
+            }
+            _ => args,
         };
-        let b = match args.get(1) {
-            Some(Value::Int(i)) => *i as i64,
-            _ => 0,
+        let mut best = items.first().ok_or_else(|| anyhow::anyhow!("{}() of an empty sequence", name))?;
+        for item in &items[1..] {
+            match item.partial_cmp(best) {
+                Some(ord) if ord == wanted => best = item,
+                Some(_) => {}
+                None => return Err(anyhow::anyhow!("{}() found values that cannot be compared", name)),
+            }
+        }
+        Ok(best.clone())
+    }
+    env.set_func("min".to_string(), Function::Native(|args| extreme("min", &args, std::cmp::Ordering::Less)));
+    env.set_func("max".to_string(), Function::Native(|args| extreme("max", &args, std::cmp::Ordering::Greater)));
+
+    // random.randint
+    // randint(a, b) -> integer in a..=b; all random builtins follow seed(n)
+    env.set_func("randint".to_string(), Function::Native(|args| match (args.first(), args.get(1)) {
+        (Some(Value::Int(a)), Some(Value::Int(b))) if a <= b => Ok(Value::Int(random::randint(*a, *b))),
+        (Some(Value::Int(a)), Some(Value::Int(b))) => Err(anyhow::anyhow!("randint() empty range: {} > {}", a, b)),
+        _ => Err(anyhow::anyhow!("randint() requires two integers")),
+    }));
+    env.set_func("random".to_string(), Function::Native(|_args| Ok(Value::Float(random::random()))));
+    env.set_func("random_bool".to_string(), Function::Native(|_args| Ok(Value::Bool(random::random_bool()))));
+    env.set_func("uniform".to_string(), Function::Native(|args| {
+        let num = |v: Option<&Value>| match v {
+            Some(Value::Int(i)) => Ok(*i as f64),
+            Some(Value::Float(f)) => Ok(*f),
+            _ => Err(anyhow::anyhow!("uniform() requires two numbers")),
         };
-        let v = rand::random::<u32>() % ((b - a + 1) as u32);
-        Ok(Value::Int((a + v as i64) as i64))
+        let (a, b) = (num(args.first())?, num(args.get(1))?);
+        // the width must be finite too, or rand can't scale a sample to it
+        if !a.is_finite() || !b.is_finite() || !(b - a).is_finite() {
+            return Err(anyhow::anyhow!("uniform() bounds and the distance between them must be finite, got {} and {}", a, b));
+        }
+        if a == b {
+            return Ok(Value::Float(a));
+        }
+        Ok(Value::Float(random::uniform(a.min(b), a.max(b))))
+    }));
+    env.set_func("choice".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::List(items)) => random::choice(items).ok_or_else(|| anyhow::anyhow!("choice() from an empty list")),
+        _ => Err(anyhow::anyhow!("choice() requires a list")),
     }));
+    // shuffle(list) -> new list in random order; the argument is left as is
+    env.set_func("shuffle".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::List(items)) => Ok(Value::List(random::shuffle(items))),
+        _ => Err(anyhow::anyhow!("shuffle() requires a list")),
+    }));
+    env.set_func("seed".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Int(n)) => {
+            random::seed(*n as u64);
+            Ok(Value::None)
+        }
+        _ => Err(anyhow::anyhow!("seed() requires an integer")),
+    }));
+
 
     // range(start, end) or range(end) -> Range(start, end)
     env.set_func("range".to_string(), Function::Native(|args| {
@@ -137,13 +296,64 @@ pub fn register_stdlib(env: &mut Env) {
         }
     }));
 
+    // http_get(url) / http_post(url, body) -> {"status", "body", "headers"} or {"error"}
+    env.set_func("http_get".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Str(url)) => Ok(net::http_get(url)),
+        _ => Err(anyhow::anyhow!("http_get() requires a URL string")),
+    }));
+    env.set_func("http_post".to_string(), Function::Native(|args| match (args.first(), args.get(1)) {
+        (Some(Value::Str(url)), Some(Value::Str(body))) => Ok(net::http_post(url, body)),
+        (Some(Value::Str(url)), Some(body)) => Ok(net::http_post(url, &jsonmod::dumps(body))),
+        _ => Err(anyhow::anyhow!("http_post() requires a URL string and a body")),
+    }));
+
+    fn port_arg(port: i64) -> anyhow::Result<u16> {
+        u16::try_from(port).map_err(|_| anyhow::anyhow!("port must be between 0 and 65535, got {}", port))
+    }
+    // tcp_connect(host, port) / tcp_listen(port) / tcp_accept(listener) -> handle string
+    env.set_func("tcp_connect".to_string(), Function::Native(|args| match (args.first(), args.get(1)) {
+        (Some(Value::Str(host)), Some(Value::Int(port))) => net::tcp_connect(host, port_arg(*port)?),
+        _ => Err(anyhow::anyhow!("tcp_connect() requires a host string and a port")),
+    }));
+    env.set_func("tcp_listen".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Int(port)) => net::tcp_listen(port_arg(*port)?),
+        _ => Err(anyhow::anyhow!("tcp_listen() requires a port")),
+    }));
+    env.set_func("tcp_accept".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Str(handle)) => net::tcp_accept(handle),
+        _ => Err(anyhow::anyhow!("tcp_accept() requires a listener handle")),
+    }));
+    // tcp_send(handle, data) -> bytes sent; tcp_recv(handle, n) -> str ("" once the peer closes)
+    env.set_func("tcp_send".to_string(), Function::Native(|args| match (args.first(), args.get(1)) {
+        (Some(Value::Str(handle)), Some(Value::Str(data))) => net::tcp_send(handle, data),
+        _ => Err(anyhow::anyhow!("tcp_send() requires a handle and a string")),
+    }));
+    env.set_func("tcp_recv".to_string(), Function::Native(|args| match (args.first(), args.get(1)) {
+        (Some(Value::Str(handle)), Some(Value::Int(n))) if *n > 0 => net::tcp_recv(handle, *n as usize),
+        _ => Err(anyhow::anyhow!("tcp_recv() requires a handle and a positive byte count")),
+    }));
+    env.set_func("tcp_close".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Str(handle)) => net::tcp_close(handle),
+        _ => Err(anyhow::anyhow!("tcp_close() requires a handle")),
+    }));
+
     // json: dumps/loads via serde_json
-    env.set_func("json_dumps".to_string(), Function::Native(|args| {
-        if let Some(Value::Str(s)) = args.get(0) {
-            // assume s is JSON serializable string
-            Ok(Value::Str(s.clone()))
-        } else {
-            Ok(Value::Str(String::new()))
+    env.set_func("json_dumps".to_string(), Function::Native(|args| match args.first() {
+        Some(value) => Ok(Value::Str(jsonmod::dumps(value))),
+        None => Err(anyhow::anyhow!("json_dumps() takes a value")),
+    }));
+    env.set_func("json_loads".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Str(text)) => jsonmod::loads(text).map_err(|e| anyhow::anyhow!(e)),
+        _ => Err(anyhow::anyhow!("json_loads() takes a JSON string")),
+    }));
+
+    // json_get(value, "a.b.0.c"[, default]) -> nested value, or default/None when missing
+    env.set_func("json_get".to_string(), Function::Native(|args| {
+        match (args.first(), args.get(1)) {
+            (Some(value), Some(Value::Str(path))) => Ok(jsonmod::get_path(value, path)
+                .cloned()
+                .unwrap_or_else(|| args.get(2).cloned().unwrap_or(Value::None))),
+            _ => Err(anyhow::anyhow!("json_get() requires a value and a path string")),
         }
     }));
 
@@ -167,18 +377,65 @@ pub fn register_stdlib(env: &mut Env) {
     }));
 
     // time.sleep
+    // parse_args(spec) or parse_args(spec, argv_list) -> dict of parsed script arguments
+    env.set_func("parse_args".to_string(), Function::Native(|args| {
+        let spec = match args.first() {
+            Some(Value::Dict(spec)) => spec,
+            _ => return Err(anyhow::anyhow!("parse_args() requires a spec dict")),
+        };
+        let argv = match args.get(1) {
+            Some(Value::List(items)) => items.iter().map(|v| v.to_string()).collect(),
+            _ => sys::script_args(),
+        };
+        Ok(Value::Dict(argparse::parse(spec, &argv)?))
+    }));
+
+    env.set_func("datetime_diff".to_string(), Function::Native(datetime::datetime_diff));
+    env.set_func("datetime_add".to_string(), Function::Native(datetime::datetime_add));
+    env.set_func("duration_str".to_string(), Function::Native(|args| {
+        match args.first() {
+            Some(Value::Int(i)) => Ok(Value::Str(datetime::duration_str(*i as f64))),
+            Some(Value::Float(f)) => Ok(Value::Str(datetime::duration_str(*f))),
+            _ => Err(anyhow::anyhow!("duration_str() requires a number of seconds")),
+        }
+    }));
+
+    env.set_func("monotonic".to_string(), Function::Native(|_args| {
+        Ok(Value::Float(timemod::monotonic()))
+    }));
+
+    env.set_func("sleep_until".to_string(), Function::Native(|args| {
+        match args.first() {
+            Some(Value::Float(f)) => timemod::sleep_until(*f).map_err(|e| anyhow::anyhow!("sleep_until(): {}", e))?,
+            Some(Value::Int(i)) => timemod::sleep_until(*i as f64).map_err(|e| anyhow::anyhow!("sleep_until(): {}", e))?,
+            _ => return Err(anyhow::anyhow!("sleep_until() requires a monotonic() deadline")),
+        }
+        Ok(Value::None)
+    }));
+
     env.set_func("sleep".to_string(), Function::Native(|args| {
-        if let Some(Value::Float(f)) = args.get(0) {
-            std::thread::sleep(std::time::Duration::from_secs_f64(*f));
-        } else if let Some(Value::Int(i)) = args.get(0) {
-            std::thread::sleep(std::time::Duration::from_secs(*i as u64));
+        match args.first() {
+            Some(Value::Float(f)) => timemod::sleep(*f).map_err(|e| anyhow::anyhow!("sleep(): {}", e))?,
+            Some(Value::Int(i)) => timemod::sleep(*i as f64).map_err(|e| anyhow::anyhow!("sleep(): {}", e))?,
+            _ => {}
         }
         Ok(Value::None)
     }));
 
-    // sys.argv
+    // sys.argv: the script path and its arguments, filled in by interpreter::run_file
     env.set("argv".to_string(), Value::List(vec![]));
 
+    // exit([code]) ends the process immediately; version() -> interpreter version
+    env.set_func("exit".to_string(), Function::Native(|args| match args.first() {
+        None => sys::exit(0),
+        Some(Value::Int(code)) => match i32::try_from(*code) {
+            Ok(code) => sys::exit(code),
+            Err(_) => Err(anyhow::anyhow!("exit() code out of range: {}", code)),
+        },
+        Some(other) => Err(anyhow::anyhow!("exit() requires an integer code, got {}", other.type_name())),
+    }));
+    env.set_func("version".to_string(), Function::Native(|_args| Ok(Value::Str(sys::version().to_string()))));
+
     // os.listdir stub
     env.set_func("listdir".to_string(), Function::Native(|args| {
         if let Some(Value::Str(p)) = args.get(0) {
@@ -209,11 +466,116 @@ pub fn register_stdlib(env: &mut Env) {
         }
     }));
 
+    // chdir/mkdir/makedirs/remove_file/remove_dir/remove_dir_recursive(path) -> None
+    fn path_op(name: &str, args: &[Value], op: fn(&str) -> std::io::Result<()>) -> anyhow::Result<Value> {
+        match args.first() {
+            Some(Value::Str(path)) => {
+                op(path).map_err(|e| anyhow::anyhow!("{}({}): {}", name, path, e))?;
+                Ok(Value::None)
+            }
+            _ => Err(anyhow::anyhow!("{}() requires a path", name)),
+        }
+    }
+    env.set_func("chdir".to_string(), Function::Native(|args| path_op("chdir", &args, osmod::chdir)));
+    env.set_func("mkdir".to_string(), Function::Native(|args| path_op("mkdir", &args, osmod::mkdir)));
+    env.set_func("makedirs".to_string(), Function::Native(|args| path_op("makedirs", &args, osmod::makedirs)));
+    env.set_func("remove_file".to_string(), Function::Native(|args| path_op("remove_file", &args, osmod::remove_file)));
+    env.set_func("remove_dir".to_string(), Function::Native(|args| path_op("remove_dir", &args, osmod::remove_dir)));
+    env.set_func("remove_dir_recursive".to_string(), Function::Native(|args| {
+        path_op("remove_dir_recursive", &args, osmod::remove_dir_recursive)
+    }));
+
+    // isdir(path) / isfile(path) -> bool
+    env.set_func("isdir".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Str(path)) => Ok(Value::Bool(osmod::isdir(path))),
+        _ => Err(anyhow::anyhow!("isdir() requires a path")),
+    }));
+    env.set_func("isfile".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Str(path)) => Ok(Value::Bool(osmod::isfile(path))),
+        _ => Err(anyhow::anyhow!("isfile() requires a path")),
+    }));
+
+    // getenv(name[, default]) -> str, or default (None) when unset; setenv(name, value)
+    env.set_func("getenv".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Str(name)) => Ok(osmod::getenv(name).map(Value::Str).unwrap_or_else(|| args.get(1).cloned().unwrap_or(Value::None))),
+        _ => Err(anyhow::anyhow!("getenv() requires a variable name")),
+    }));
+    env.set_func("setenv".to_string(), Function::Native(|args| match (args.first(), args.get(1)) {
+        (Some(Value::Str(name)), Some(value)) if !name.is_empty() && !name.contains(['=', '\0']) => {
+            osmod::setenv(name, &value.to_string());
+            Ok(Value::None)
+        }
+        _ => Err(anyhow::anyhow!("setenv() requires a variable name and a value")),
+    }));
+
     // os.platform
+    // which(name) -> full path of an executable on PATH, or None
+    env.set_func("which".to_string(), Function::Native(|args| {
+        match args.first() {
+            Some(Value::Str(name)) => Ok(osmod::which(name).map(Value::Str).unwrap_or(Value::None)),
+            _ => Err(anyhow::anyhow!("which() requires a program name")),
+        }
+    }));
+
+    // walk(root) -> every file path under root, recursively
+    env.set_func("walk".to_string(), Function::Native(|args| {
+        match args.first() {
+            Some(Value::Str(root)) => {
+                let files = osmod::walk(root).map_err(|e| anyhow::anyhow!("walk({}): {}", root, e))?;
+                Ok(Value::List(files.into_iter().map(Value::Str).collect()))
+            }
+            _ => Err(anyhow::anyhow!("walk() requires a directory path")),
+        }
+    }));
+
+    // path_join(a, b, ...) / path_basename(p) / path_dirname(p) / path_splitext(p) -> [root, ext] / path_exists(p)
+    env.set_func("path_join".to_string(), Function::Native(|args| {
+        let parts = args
+            .iter()
+            .map(|a| match a {
+                Value::Str(s) => Ok(s.clone()),
+                other => Err(anyhow::anyhow!("path_join() takes strings, got {}", other.type_name())),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Value::Str(osmod::path_join(&parts)))
+    }));
+    env.set_func("path_basename".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Str(p)) => Ok(Value::Str(osmod::path_basename(p))),
+        _ => Err(anyhow::anyhow!("path_basename() requires a path")),
+    }));
+    env.set_func("path_dirname".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Str(p)) => Ok(Value::Str(osmod::path_dirname(p))),
+        _ => Err(anyhow::anyhow!("path_dirname() requires a path")),
+    }));
+    env.set_func("path_splitext".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Str(p)) => {
+            let (root, ext) = osmod::path_splitext(p);
+            Ok(Value::List(vec![Value::Str(root), Value::Str(ext)]))
+        }
+        _ => Err(anyhow::anyhow!("path_splitext() requires a path")),
+    }));
+    env.set_func("path_exists".to_string(), Function::Native(|args| match args.first() {
+        Some(Value::Str(p)) => Ok(Value::Bool(osmod::path_exists(p))),
+        _ => Err(anyhow::anyhow!("path_exists() requires a path")),
+    }));
+
+    // tempfile([create]) / tempdir(): scratch paths that are not cleaned up automatically
+    env.set_func("tempfile".to_string(), Function::Native(|args| {
+        let create = args.first().map(|v| v.is_truthy()).unwrap_or(true);
+        Ok(Value::Str(osmod::tempfile(create)?))
+    }));
+
+    env.set_func("tempdir".to_string(), Function::Native(|_args| {
+        Ok(Value::Str(osmod::tempdir()?))
+    }));
+
     env.set_func("platform".to_string(), Function::Native(|_args| {
         Ok(Value::Str(std::env::consts::OS.to_string()))
     }));
 
+    // env["KEY"] reads and env["KEY"] = "val" writes the process environment
+    env.set("env".to_string(), environ());
+
     // io.read / io.write
     env.set_func("read_file".to_string(), Function::Native(|args| {
         if let Some(Value::Str(p)) = args.get(0) {
@@ -224,6 +586,85 @@ pub fn register_stdlib(env: &mut Env) {
         Ok(Value::Str(String::new()))
     }));
 
+    // read_file_lines(path) -> lazy line iterator for `for line in ...:` loops
+    // (`lines_id` also serves the File objects from `open`)
+    fn lines_id(selfv: Option<&Value>) -> anyhow::Result<String> {
+        match selfv {
+            Some(Value::Instance(_, fields, _)) => match fields.get("__id") {
+                Some(Value::Str(id)) => Ok(id.clone()),
+                _ => Err(anyhow::anyhow!("file object has no handle")),
+            },
+            _ => Err(anyhow::anyhow!("file method called without self")),
+        }
+    }
+    fn file_lines_next(args: Vec<Value>) -> anyhow::Result<Value> {
+        match iomod::next_line(&lines_id(args.first())?)? {
+            Some(line) => Ok(Value::Str(line)),
+            None => Err(anyhow::anyhow!(crate::eval::STOP_ITERATION)),
+        }
+    }
+    fn file_lines_close(args: Vec<Value>) -> anyhow::Result<Value> {
+        iomod::close_lines(&lines_id(args.first())?);
+        Ok(Value::None)
+    }
+    env.set_func("read_file_lines".to_string(), Function::Native(|args| {
+        let Some(Value::Str(path)) = args.first() else {
+            return Err(anyhow::anyhow!("read_file_lines() requires a path"));
+        };
+        let id = iomod::open_lines(path).map_err(|e| anyhow::anyhow!("read_file_lines({}): {}", path, e))?;
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("__id".to_string(), Value::Str(id));
+        fields.insert("path".to_string(), Value::Str(path.clone()));
+        let mut methods = std::collections::HashMap::new();
+        methods.insert("__next__".to_string(), Function::Native(file_lines_next));
+        methods.insert("close".to_string(), Function::Native(file_lines_close));
+        Ok(Value::Instance("FileLines".to_string(), fields, methods))
+    }));
+
+    // open(path, mode="r") -> File object with read(), readline(), write(s) and close()
+    fn file_method<T>(args: &[Value], op: impl FnOnce(&str) -> std::io::Result<T>) -> anyhow::Result<T> {
+        let id = lines_id(args.first())?;
+        op(&id).map_err(|e| anyhow::anyhow!("{}", e))
+    }
+    fn file_read(args: Vec<Value>) -> anyhow::Result<Value> {
+        file_method(&args, iomod::file_read).map(Value::Str)
+    }
+    fn file_readline(args: Vec<Value>) -> anyhow::Result<Value> {
+        file_method(&args, iomod::file_readline).map(Value::Str)
+    }
+    fn file_write(args: Vec<Value>) -> anyhow::Result<Value> {
+        let Some(Value::Str(content)) = args.get(1) else {
+            return Err(anyhow::anyhow!("write() requires a string"));
+        };
+        file_method(&args, |id| iomod::file_write(id, content))?;
+        Ok(Value::None)
+    }
+    fn file_close(args: Vec<Value>) -> anyhow::Result<Value> {
+        file_method(&args, iomod::file_close)?;
+        Ok(Value::None)
+    }
+    env.set_func("open".to_string(), Function::Native(|args| {
+        let Some(Value::Str(path)) = args.first() else {
+            return Err(anyhow::anyhow!("open() requires a path"));
+        };
+        let mode = match args.get(1) {
+            Some(Value::Str(mode)) => mode.clone(),
+            None => "r".to_string(),
+            Some(other) => return Err(anyhow::anyhow!("open() mode must be a string, got {}", other.type_name())),
+        };
+        let id = iomod::open_file(path, &mode).map_err(|e| anyhow::anyhow!("open({}): {}", path, e))?;
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("__id".to_string(), Value::Str(id));
+        fields.insert("path".to_string(), Value::Str(path.clone()));
+        fields.insert("mode".to_string(), Value::Str(mode));
+        let mut methods = std::collections::HashMap::new();
+        methods.insert("read".to_string(), Function::Native(file_read));
+        methods.insert("readline".to_string(), Function::Native(file_readline));
+        methods.insert("write".to_string(), Function::Native(file_write));
+        methods.insert("close".to_string(), Function::Native(file_close));
+        Ok(Value::Instance("File".to_string(), fields, methods))
+    }));
+
     env.set_func("write_file".to_string(), Function::Native(|args| {
         if let (Some(Value::Str(p)), Some(Value::Str(content))) = (args.get(0), args.get(1)) {
             let _ = std::fs::write(p, content);
@@ -242,20 +683,56 @@ pub fn register_stdlib(env: &mut Env) {
     }));
 
     env.set_func("float".to_string(), Function::Native(|args| {
-        if let Some(Value::Str(s)) = args.get(0) {
-            if let Ok(f) = s.parse::<f64>() { return Ok(Value::Float(f)); }
+        match args.first() {
+            // accepts "nan", "inf" and "-inf" as well as ordinary numbers
+            Some(Value::Str(s)) => s.trim().parse::<f64>()
+                .map(Value::Float)
+                .map_err(|_| anyhow::anyhow!("Could not convert string to float: {:?}", s)),
+            Some(Value::Int(i)) => Ok(Value::Float(*i as f64)),
+            Some(Value::Float(f)) => Ok(Value::Float(*f)),
+            Some(Value::Bool(b)) => Ok(Value::Float(if *b { 1.0 } else { 0.0 })),
+            Some(other) => Err(anyhow::anyhow!("float() can't convert {}", other.to_string())),
+            None => Ok(Value::Float(0.0)),
         }
-        if let Some(Value::Int(i)) = args.get(0) { return Ok(Value::Float(*i as f64)); }
-        if let Some(Value::Float(f)) = args.get(0) { return Ok(Value::Float(*f)); }
-        Ok(Value::Float(0.0))
     }));
 
-    env.set_func("str".to_string(), Function::Native(|args| {
-        if let Some(Value::Str(s)) = args.get(0) { return Ok(Value::Str(s.clone())); }
-        if let Some(Value::Int(i)) = args.get(0) { return Ok(Value::Str(i.to_string())); }
-        if let Some(Value::Float(f)) = args.get(0) { return Ok(Value::Str(f.to_string())); }
-        if let Some(Value::Bool(b)) = args.get(0) { return Ok(Value::Str(b.to_string())); }
-        Ok(Value::Str(String::new()))
+    // try_parse_int(s) / try_parse_float(s) -> [ok, value]; value is None when ok is False
+    fn try_parse<T: std::str::FromStr>(args: &[Value], wrap: fn(T) -> Value) -> anyhow::Result<Value> {
+        let parsed = match args.first() {
+            Some(Value::Str(s)) => s.trim().parse::<T>().ok().map(wrap),
+            _ => None,
+        };
+        Ok(Value::List(vec![Value::Bool(parsed.is_some()), parsed.unwrap_or(Value::None)]))
+    }
+    env.set_func("try_parse_int".to_string(), Function::Native(|args| try_parse(&args, Value::Int)));
+    env.set_func("try_parse_float".to_string(), Function::Native(|args| try_parse(&args, Value::Float)));
+
+    fn float_check(name: &str, args: &[Value], check: fn(f64) -> bool) -> anyhow::Result<Value> {
+        match args.first() {
+            Some(Value::Float(f)) => Ok(Value::Bool(check(*f))),
+            Some(Value::Int(_)) => Ok(Value::Bool(false)),
+            _ => Err(anyhow::anyhow!("{}() requires a number", name)),
+        }
+    }
+    env.set_func("is_nan".to_string(), Function::Native(|args| float_check("is_nan", &args, f64::is_nan)));
+    env.set_func("is_inf".to_string(), Function::Native(|args| float_check("is_inf", &args, f64::is_infinite)));
+
+    // breakpoint(): pause and inspect the calling scope from stdin
+    env.set_func("breakpoint".to_string(), Function::NativeEnv(|env, _args| {
+        crate::repl::inspect(env, &mut std::io::stdin().lock())?;
+        Ok(Value::None)
+    }));
+
+    env.set_func("str".to_string(), Function::NativeEnv(|env, args| match args.first() {
+        Some(v) => Ok(Value::Str(crate::eval::format_value(v, env)?)),
+        None => Ok(Value::Str(String::new())),
+    }));
+
+    env.set_func("Error".to_string(), Function::Native(|args| {
+        match args.first() {
+            Some(v) => Ok(Value::Error(v.to_string())),
+            None => Ok(Value::Error(String::new())),
+        }
     }));
 
     env.set_func("bool".to_string(), Function::Native(|args| {
@@ -274,11 +751,316 @@ pub fn register_stdlib(env: &mut Env) {
     }));
 
     env.set_func("len".to_string(), Function::Native(|args| {
-        if let Some(Value::Str(s)) = args.get(0) { return Ok(Value::Int(s.len() as i64)); }
-        if let Some(Value::List(l)) = args.get(0) { return Ok(Value::Int(l.len() as i64)); }
+        if let Some(Value::Str(s)) = args.first() { return Ok(Value::Int(s.chars().count() as i64)); }
+        if let Some(Value::List(l)) = args.first() { return Ok(Value::Int(l.len() as i64)); }
+        if let Some(Value::Bytes(b)) = args.first() { return Ok(Value::Int(b.len() as i64)); }
         Ok(Value::Int(0))
     }));
 
+    // word_count(s) / char_count(s) / line_count(s) -> quick text statistics
+    fn text_arg<'a>(name: &str, args: &'a [Value]) -> anyhow::Result<&'a str> {
+        match args.first() {
+            Some(Value::Str(s)) => Ok(s),
+            _ => Err(anyhow::anyhow!("{}() requires a string", name)),
+        }
+    }
+    env.set_func("word_count".to_string(), Function::Native(|args| {
+        Ok(Value::Int(strmod::word_count(text_arg("word_count", &args)?) as i64))
+    }));
+    env.set_func("char_count".to_string(), Function::Native(|args| {
+        Ok(Value::Int(strmod::char_count(text_arg("char_count", &args)?) as i64))
+    }));
+    env.set_func("line_count".to_string(), Function::Native(|args| {
+        Ok(Value::Int(strmod::line_count(text_arg("line_count", &args)?) as i64))
+    }));
+
+    // type(x) -> type name such as "int" or "list"; instances give their class name
+    env.set_func("type".to_string(), Function::Native(|args| match args.first() {
+        Some(v) => Ok(Value::Str(v.type_name())),
+        None => Err(anyhow::anyhow!("type() takes one argument")),
+    }));
+
+    // assert_type(x, "int") or assert_type(x, ["int", "float"]) -> x, or an error naming the mismatch
+    env.set_func("assert_type".to_string(), Function::Native(|args| {
+        let mut args = args.into_iter();
+        let (Some(value), Some(expected)) = (args.next(), args.next()) else {
+            return Err(anyhow::anyhow!("assert_type() takes a value and a type name or list of names"));
+        };
+        let allowed: Vec<String> = match expected {
+            Value::Str(name) => vec![name],
+            Value::List(names) => names.iter().map(|n| n.to_string()).collect(),
+            other => return Err(anyhow::anyhow!("assert_type() expected type names, got {}", other.to_string())),
+        };
+        let actual = value.type_name();
+        if !allowed.contains(&actual) {
+            return Err(anyhow::anyhow!("TypeError: expected {}, got {} ({})", allowed.join(" or "), actual, value.to_string()));
+        }
+        Ok(value)
+    }));
+
+    // sorted(list) -> new list in ascending order; lists compare lexicographically
+    env.set_func("sorted".to_string(), Function::Native(|args| {
+        let items = match args.first() {
+            Some(Value::List(l)) => l.clone(),
+            Some(Value::Range(s, e)) => (*s..*e).map(Value::Int).collect(),
+            _ => return Err(anyhow::anyhow!("sorted() requires a list")),
+        };
+        let items = listmod::sort_values(items).map_err(|e| anyhow::anyhow!("sorted(): {}", e))?;
+        Ok(Value::List(items))
+    }));
+
+    // argmin(list) / argmax(list) -> index of the smallest/largest element; ties keep the first
+    fn arg_extreme(name: &str, args: &[Value], wanted: std::cmp::Ordering) -> anyhow::Result<Value> {
+        let items = match args.first() {
+            Some(Value::List(l)) => l,
+            _ => return Err(anyhow::anyhow!("{}() requires a list", name)),
+        };
+        if items.is_empty() {
+            return Err(anyhow::anyhow!("{}() of an empty list", name));
+        }
+        let mut best = 0;
+        for (i, item) in items.iter().enumerate().skip(1) {
+            match item.partial_cmp(&items[best]) {
+                Some(ord) if ord == wanted => best = i,
+                Some(_) => {}
+                None => return Err(anyhow::anyhow!("{}() found values that cannot be compared", name)),
+            }
+        }
+        Ok(Value::Int(best as i64))
+    }
+    env.set_func("argmin".to_string(), Function::Native(|args| arg_extreme("argmin", &args, std::cmp::Ordering::Less)));
+    env.set_func("argmax".to_string(), Function::Native(|args| arg_extreme("argmax", &args, std::cmp::Ordering::Greater)));
+
+    // linspace(start, stop, n) -> n evenly spaced floats from start to stop inclusive
+    env.set_func("linspace".to_string(), Function::Native(|args| {
+        let num = |v: Option<&Value>| match v {
+            Some(Value::Int(i)) => Ok(*i as f64),
+            Some(Value::Float(f)) => Ok(*f),
+            _ => Err(anyhow::anyhow!("linspace() requires numeric start and stop")),
+        };
+        let (start, stop) = (num(args.first())?, num(args.get(1))?);
+        let n = match args.get(2) {
+            Some(Value::Int(n)) if *n >= 0 => *n,
+            _ => return Err(anyhow::anyhow!("linspace() requires a non-negative integer count")),
+        };
+        if n == 1 {
+            return Ok(Value::List(vec![Value::Float(start)]));
+        }
+        let step = (stop - start) / (n - 1).max(1) as f64;
+        // the last point is stop itself, so rounding never overshoots it
+        Ok(Value::List((0..n).map(|i| Value::Float(if i == n - 1 { stop } else { start + step * i as f64 })).collect()))
+    }));
+
+    // dot(a, b) -> dot product of two equal-length numeric lists
+    // matmul(A, B) -> product of two matrices given as lists of rows
+    // Results stay integers when every input is an integer.
+    fn dot_product(a: &[Value], b: &[Value]) -> anyhow::Result<Value> {
+        if a.len() != b.len() {
+            return Err(anyhow::anyhow!("dot() needs equal-length lists, got {} and {}", a.len(), b.len()));
+        }
+        let mut int_sum = Some(0i64);
+        let mut float_sum = 0.0;
+        for (x, y) in a.iter().zip(b) {
+            match (x, y) {
+                (Value::Int(x), Value::Int(y)) => {
+                    int_sum = int_sum.and_then(|s| x.checked_mul(*y).and_then(|p| s.checked_add(p)));
+                    float_sum += *x as f64 * *y as f64;
+                }
+                (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+                    let f = |v: &Value| match v { Value::Int(i) => *i as f64, Value::Float(f) => *f, _ => 0.0 };
+                    int_sum = None;
+                    float_sum += f(x) * f(y);
+                }
+                _ => return Err(anyhow::anyhow!("dot() requires numbers, got {} and {}", x.to_string(), y.to_string())),
+            }
+        }
+        Ok(int_sum.map(Value::Int).unwrap_or(Value::Float(float_sum)))
+    }
+    fn matrix_rows(name: &str, v: Option<&Value>) -> anyhow::Result<Vec<Vec<Value>>> {
+        let rows = match v {
+            Some(Value::List(rows)) => rows,
+            _ => return Err(anyhow::anyhow!("{}() requires lists of rows", name)),
+        };
+        let rows = rows.iter().map(|r| match r {
+            Value::List(cells) => Ok(cells.clone()),
+            other => Err(anyhow::anyhow!("{}() matrix row is not a list: {}", name, other.to_string())),
+        }).collect::<anyhow::Result<Vec<_>>>()?;
+        if rows.iter().any(|r| r.len() != rows[0].len()) {
+            return Err(anyhow::anyhow!("{}() matrix rows differ in length", name));
+        }
+        Ok(rows)
+    }
+    env.set_func("dot".to_string(), Function::Native(|args| match (args.first(), args.get(1)) {
+        (Some(Value::List(a)), Some(Value::List(b))) => dot_product(a, b),
+        _ => Err(anyhow::anyhow!("dot() requires two lists")),
+    }));
+    env.set_func("matmul".to_string(), Function::Native(|args| {
+        let a = matrix_rows("matmul", args.first())?;
+        let b = matrix_rows("matmul", args.get(1))?;
+        let inner = a.first().map_or(0, |r| r.len());
+        if inner != b.len() {
+            return Err(anyhow::anyhow!("matmul() shape mismatch: {} columns vs {} rows", inner, b.len()));
+        }
+        let cols = b.first().map_or(0, |r| r.len());
+        let columns: Vec<Vec<Value>> = (0..cols).map(|j| b.iter().map(|row| row[j].clone()).collect()).collect();
+        let product = a.iter().map(|row| {
+            columns.iter().map(|col| dot_product(row, col)).collect::<anyhow::Result<Vec<_>>>().map(Value::List)
+        }).collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Value::List(product))
+    }));
+
+    // format_table(rows[, headers]) -> aligned text table from lists or dicts
+    env.set_func("format_table".to_string(), Function::Native(|args| {
+        let rows = match args.first() {
+            Some(Value::List(rows)) => rows,
+            _ => return Err(anyhow::anyhow!("format_table() requires a list of rows")),
+        };
+        let headers = match args.get(1) {
+            Some(Value::List(h)) => Some(h.iter().map(|v| v.to_string()).collect::<Vec<_>>()),
+            None | Some(Value::None) => None,
+            Some(other) => return Err(anyhow::anyhow!("format_table() headers must be a list, got {}", other.to_string())),
+        };
+        Ok(Value::Str(term::format_table(rows, headers.as_deref())?))
+    }));
+
+    // mean/median/variance/stdev(list) -> float; variance and stdev are sample statistics
+    env.set_func("mean".to_string(), Function::Native(|args| {
+        Ok(Value::Float(statistics::mean(&statistics::numbers("mean", args.first())?)))
+    }));
+    env.set_func("median".to_string(), Function::Native(|args| {
+        Ok(Value::Float(statistics::median(&statistics::numbers("median", args.first())?)))
+    }));
+    env.set_func("variance".to_string(), Function::Native(|args| {
+        Ok(Value::Float(statistics::variance(&statistics::numbers("variance", args.first())?)?))
+    }));
+    env.set_func("stdev".to_string(), Function::Native(|args| {
+        Ok(Value::Float(statistics::stdev(&statistics::numbers("stdev", args.first())?)?))
+    }));
+
+    // map_values(dict, func) -> new dict with func applied to each value
+    env.set_func("map_values".to_string(), Function::NativeEnv(|env, args| {
+        match (args.first(), args.get(1)) {
+            (Some(Value::Dict(map)), Some(func)) => {
+                let mut out = std::collections::HashMap::new();
+                for (k, v) in map {
+                    out.insert(k.clone(), env.call_value(func, vec![v.clone()])?);
+                }
+                Ok(Value::Dict(out))
+            }
+            _ => Err(anyhow::anyhow!("map_values() requires a dict and a function")),
+        }
+    }));
+
+    // flatten(list, deep) -> removes one level of nesting, or all levels when deep is true
+    env.set_func("flatten".to_string(), Function::Native(|args| {
+        fn flatten_into(items: &[Value], deep: bool, out: &mut Vec<Value>) {
+            for item in items {
+                match item {
+                    Value::List(inner) if deep => flatten_into(inner, deep, out),
+                    Value::List(inner) => out.extend(inner.iter().cloned()),
+                    other => out.push(other.clone()),
+                }
+            }
+        }
+        let deep = args.get(1).map(|v| v.is_truthy()).unwrap_or(false);
+        match args.first() {
+            Some(Value::List(items)) => {
+                let mut out = Vec::new();
+                flatten_into(items, deep, &mut out);
+                Ok(Value::List(out))
+            }
+            _ => Err(anyhow::anyhow!("flatten() requires a list")),
+        }
+    }));
+
+    // chunk(list, size) -> list of sublists of at most size elements
+    env.set_func("chunk".to_string(), Function::Native(|args| {
+        match (args.first(), args.get(1)) {
+            (Some(Value::List(items)), Some(Value::Int(size))) => {
+                if *size <= 0 {
+                    return Err(anyhow::anyhow!("chunk() size must be positive, got {}", size));
+                }
+                Ok(Value::List(items.chunks(*size as usize).map(|c| Value::List(c.to_vec())).collect()))
+            }
+            _ => Err(anyhow::anyhow!("chunk() requires a list and an integer size")),
+        }
+    }));
+
+    // reduce(func, list, initial) -> folds func over the list; without initial the first element seeds it
+    env.set_func("reduce".to_string(), Function::NativeEnv(|env, args| {
+        let (func, items) = match (args.first(), args.get(1)) {
+            (Some(func), Some(Value::List(items))) => (func, items),
+            _ => return Err(anyhow::anyhow!("reduce() requires a function and a list")),
+        };
+        let mut rest = items.iter();
+        let mut acc = match args.get(2) {
+            Some(initial) => initial.clone(),
+            None => rest.next().cloned().ok_or_else(|| anyhow::anyhow!("reduce() of empty list with no initial value"))?,
+        };
+        for item in rest {
+            acc = env.call_value(func, vec![acc, item.clone()])?;
+        }
+        Ok(acc)
+    }));
+
+    // with_timeout(func, seconds, arg1, ...) -> func's result, or an error if it runs too long
+    env.set_func("with_timeout".to_string(), Function::NativeEnv(|env, args| {
+        let mut args = args.into_iter();
+        let func = match args.next() {
+            Some(Value::Func(_, f)) => f,
+            Some(Value::Str(name)) => match env.get_func(&name).cloned().or_else(|| match env.get(&name) {
+                Some(Value::Func(_, f)) => Some(f.clone()),
+                _ => None,
+            }) {
+                Some(f) => f,
+                None => return Err(anyhow::anyhow!("Undefined function: {}", name)),
+            },
+            _ => return Err(anyhow::anyhow!("with_timeout() requires a function and a number of seconds")),
+        };
+        let seconds = match args.next() {
+            Some(Value::Int(i)) => i as f64,
+            Some(Value::Float(f)) => f,
+            _ => return Err(anyhow::anyhow!("with_timeout() requires a function and a number of seconds")),
+        };
+        crate::stdlib::timemod::with_timeout(env, func, args.collect(), seconds)
+    }));
+
+    // retry(func, attempts, delay[, backoff]) -> first successful result, or the last error
+    env.set_func("retry".to_string(), Function::NativeEnv(|env, args| {
+        let seconds = |v: Option<&Value>, default: f64| match v {
+            None => Ok(default),
+            Some(Value::Int(i)) => Ok(*i as f64),
+            Some(Value::Float(f)) => Ok(*f),
+            Some(other) => Err(anyhow::anyhow!("retry() expected a number, got {}", other.to_string())),
+        };
+        let (Some(func), Some(Value::Int(attempts))) = (args.first(), args.get(1)) else {
+            return Err(anyhow::anyhow!("retry() requires a function and a number of attempts"));
+        };
+        let delay = seconds(args.get(2), 0.0)?;
+        let backoff = seconds(args.get(3), 1.0)?;
+        crate::stdlib::timemod::retry(env, func, *attempts, delay, backoff)
+    }));
+
+    // partial(func, arg1, ...) -> callable with the given leading arguments pre-bound
+    env.set_func("partial".to_string(), Function::Native(|args| {
+        let mut args = args.into_iter();
+        let func = args.next().unwrap_or(Value::None);
+        let name = match &func {
+            Value::Func(name, _) | Value::Str(name) => format!("partial({})", name),
+            _ => return Err(anyhow::anyhow!("partial() requires a function")),
+        };
+        Ok(Value::Func(name, Function::Partial { func: Box::new(func), args: args.collect() }))
+    }));
+
+    // copy(x) duplicates the outer container only; deepcopy(x) copies nested containers too
+    env.set_func("copy".to_string(), Function::Native(|args| {
+        Ok(args.into_iter().next().unwrap_or(Value::None))
+    }));
+
+    env.set_func("deepcopy".to_string(), Function::Native(|args| {
+        Ok(args.first().map(Value::deep_copy).unwrap_or(Value::None))
+    }));
+
     env.set_func("input".to_string(), Function::Native(|args| {
         use std::io::{self, Write};
         if let Some(Value::Str(prompt)) = args.get(0) { print!("{}", prompt); let _ = io::stdout().flush(); }
@@ -287,6 +1069,17 @@ pub fn register_stdlib(env: &mut Env) {
         Ok(Value::Str(s.trim_end().to_string()))
     }));
 
+    // input_int/input_float re-prompt on invalid input and error at end of input
+    env.set_func("input_int".to_string(), Function::Native(|args| {
+        let prompt = match args.first() { Some(Value::Str(p)) => p.as_str(), _ => "" };
+        Ok(Value::Int(iomod::read_parsed(prompt, &mut std::io::stdin().lock())?))
+    }));
+
+    env.set_func("input_float".to_string(), Function::Native(|args| {
+        let prompt = match args.first() { Some(Value::Str(p)) => p.as_str(), _ => "" };
+        Ok(Value::Float(iomod::read_parsed(prompt, &mut std::io::stdin().lock())?))
+    }));
+
     env.set_func("abs".to_string(), Function::Native(|args| {
         if let Some(Value::Int(i)) = args.get(0) { return Ok(Value::Int(i.abs())); }
         if let Some(Value::Float(f)) = args.get(0) { return Ok(Value::Float(f.abs())); }
@@ -315,14 +1108,20 @@ pub fn register_stdlib(env: &mut Env) {
 
     // iced window/button: if compiled with `real-iced` feature, use the real backend,
     // otherwise use the stubbed implementation.
-    env.set_func("iced_window".to_string(), Function::Native(|args| {
+    env.set_func("iced_window".to_string(), Function::NativeEnv(|_env, args| {
         if let Some(Value::Str(title)) = args.get(0) {
             #[cfg(feature = "real-iced")]
             {
-                // Create a temporary window in the registry and launch it
-                let obj = ui::UiObj::Window(ui::WindowObj { title: title.clone(), children: vec![], positions: std::collections::HashMap::new() });
+                // Create a temporary window in the registry and launch it, with the
+                // caller's bindings so callbacks named by the UI can be found
+                let obj = ui::UiObj::Window(ui::WindowObj { title: title.clone(), children: vec![], positions: std::collections::HashMap::new(), theme: ui::THEMES[0].to_string(), on_key: None });
                 let window_id = ui::insert(obj);
-                crate::stdlib::iced_stub::iced_real::launch_window(&window_id, &title);
+                let (vars, funcs) = _env.bindings();
+                let mut app_env = crate::runtime::Env::from_bindings(vars, funcs);
+                app_env.sandboxed = _env.sandboxed;
+                app_env.budget = _env.budget.clone();
+                let env = std::sync::Arc::new(std::sync::Mutex::new(app_env));
+                crate::stdlib::iced_stub::iced_real::launch_window(&window_id, &title, env);
             }
             #[cfg(not(feature = "real-iced"))]
             {
@@ -348,9 +1147,9 @@ pub fn register_stdlib(env: &mut Env) {
         Ok(Value::None)
     }));
 
-    // UI system: Window, Button, Slider, RadioButton, Column, Row constructors
+    // UI system: Window, Text, Button, Slider, ProgressBar, RadioButton, Dropdown, Image, Column, Row, Grid constructors
     fn ui_window_ctor(_args: Vec<Value>) -> anyhow::Result<Value> {
-        let obj = ui::UiObj::Window(ui::WindowObj { title: String::new(), children: vec![], positions: std::collections::HashMap::new() });
+        let obj = ui::UiObj::Window(ui::WindowObj { title: String::new(), children: vec![], positions: std::collections::HashMap::new(), theme: ui::THEMES[0].to_string(), on_key: None });
         let id = ui::insert(obj);
         let mut fields = std::collections::HashMap::new();
         fields.insert("__id".to_string(), Value::Str(id.clone()));
@@ -359,11 +1158,43 @@ pub fn register_stdlib(env: &mut Env) {
         methods.insert("set_position".to_string(), Function::Native(ui_window_set_position));
         methods.insert("set_size".to_string(), Function::Native(ui_set_size));
         methods.insert("set_icon".to_string(), Function::Native(ui_set_icon));
+        methods.insert("set_theme".to_string(), Function::Native(ui_set_theme));
+        methods.insert("get_theme".to_string(), Function::Native(ui_get_theme));
+        methods.insert("on_key".to_string(), Function::Native(ui_window_on_key));
         methods.insert("add".to_string(), Function::Native(ui_add_child));
-        methods.insert("run".to_string(), Function::Native(ui_run));
+        methods.insert("run".to_string(), Function::NativeEnv(ui_run));
         Ok(Value::Instance("Window".to_string(), fields, methods))
     }
 
+    fn ui_text_ctor(args: Vec<Value>) -> anyhow::Result<Value> {
+        let content = match args.first() {
+            Some(v) => v.to_string(),
+            None => String::new(),
+        };
+        let obj = ui::UiObj::Text(ui::TextObj { content });
+        let id = ui::insert(obj);
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("__id".to_string(), Value::Str(id.clone()));
+        let mut methods = std::collections::HashMap::new();
+        methods.insert("get_content".to_string(), Function::Native(ui_text_get_content));
+        methods.insert("set_content".to_string(), Function::Native(ui_text_set_content));
+        Ok(Value::Instance("Text".to_string(), fields, methods))
+    }
+
+    fn ui_text_get_content(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let Some(ui::UiObj::Text(t)) = instance_id(args.first()).and_then(|id| ui::get(&id)) {
+            return Ok(Value::Str(t.content));
+        }
+        Ok(Value::Str(String::new()))
+    }
+
+    fn ui_text_set_content(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let (Some(id), Some(content)) = (instance_id(args.first()), args.get(1)) {
+            ui::set_content(&id, &content.to_string());
+        }
+        Ok(Value::None)
+    }
+
     fn ui_button_ctor(args: Vec<Value>) -> anyhow::Result<Value> {
         let label = if let Some(Value::Str(s)) = args.get(0) { s.clone() } else { String::new() };
         let obj = ui::UiObj::Button(ui::ButtonObj { label: label.clone() });
@@ -373,21 +1204,48 @@ pub fn register_stdlib(env: &mut Env) {
         let mut methods = std::collections::HashMap::new();
         methods.insert("get_label".to_string(), Function::Native(ui_button_get_label));
         methods.insert("set_position".to_string(), Function::Native(ui_button_set_position));
+        methods.insert("set_label".to_string(), Function::Native(ui_button_set_label));
+        methods.insert("on_click".to_string(), Function::Native(ui_button_on_click));
+        methods.insert("click".to_string(), Function::NativeEnv(ui_button_click));
         Ok(Value::Instance("Button".to_string(), fields, methods))
     }
 
     fn ui_slider_ctor(args: Vec<Value>) -> anyhow::Result<Value> {
         let min = if let Some(Value::Int(i)) = args.get(0) { *i } else { 0 };
         let max = if let Some(Value::Int(i)) = args.get(1) { *i } else { 100 };
-        let obj = ui::UiObj::Slider(ui::SliderObj { min, max });
+        if min > max {
+            return Err(anyhow::anyhow!("Slider() min {} is greater than max {}", min, max));
+        }
+        // the midpoint of two i64s always fits, but their sum and difference may not
+        let value = ((min as i128 + max as i128) / 2) as i64;
+        let obj = ui::UiObj::Slider(ui::SliderObj { min, max, value });
         let id = ui::insert(obj);
         let mut fields = std::collections::HashMap::new();
         fields.insert("__id".to_string(), Value::Str(id.clone()));
         let mut methods = std::collections::HashMap::new();
         methods.insert("set_coordinates".to_string(), Function::Native(ui_slider_set_coords));
+        methods.insert("set_value".to_string(), Function::Native(ui_slider_set_value));
+        methods.insert("get_value".to_string(), Function::Native(ui_slider_get_value));
+        methods.insert("on_change".to_string(), Function::Native(ui_slider_on_change));
         Ok(Value::Instance("Slider".to_string(), fields, methods))
     }
 
+    fn ui_progress_ctor(args: Vec<Value>) -> anyhow::Result<Value> {
+        let min = if let Some(Value::Int(i)) = args.first() { *i } else { 0 };
+        let max = if let Some(Value::Int(i)) = args.get(1) { *i } else { 100 };
+        if min > max {
+            return Err(anyhow::anyhow!("ProgressBar() min {} is greater than max {}", min, max));
+        }
+        let obj = ui::UiObj::ProgressBar(ui::ProgressBarObj { min, max, value: min });
+        let id = ui::insert(obj);
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("__id".to_string(), Value::Str(id.clone()));
+        let mut methods = std::collections::HashMap::new();
+        methods.insert("set_value".to_string(), Function::Native(ui_progress_set_value));
+        methods.insert("get_value".to_string(), Function::Native(ui_progress_get_value));
+        Ok(Value::Instance("ProgressBar".to_string(), fields, methods))
+    }
+
     fn ui_radio_ctor(args: Vec<Value>) -> anyhow::Result<Value> {
         let label = if let Some(Value::Str(s)) = args.get(0) { s.clone() } else { String::new() };
         let obj = ui::UiObj::Radio(ui::RadioObj { label: label.clone() });
@@ -400,6 +1258,55 @@ pub fn register_stdlib(env: &mut Env) {
         Ok(Value::Instance("RadioButton".to_string(), fields, methods))
     }
 
+    fn ui_dropdown_ctor(args: Vec<Value>) -> anyhow::Result<Value> {
+        let options: Vec<String> = match args.first() {
+            Some(Value::List(items)) => items.iter().map(|v| v.to_string()).collect(),
+            _ => vec![],
+        };
+        let selected = options.first().cloned();
+        let obj = ui::UiObj::Dropdown(ui::DropdownObj { options, selected });
+        let id = ui::insert(obj);
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("__id".to_string(), Value::Str(id.clone()));
+        let mut methods = std::collections::HashMap::new();
+        methods.insert("get_selected".to_string(), Function::Native(ui_dropdown_get_selected));
+        methods.insert("select".to_string(), Function::Native(ui_dropdown_select));
+        methods.insert("on_select".to_string(), Function::Native(ui_dropdown_on_select));
+        Ok(Value::Instance("Dropdown".to_string(), fields, methods))
+    }
+
+    fn ui_dropdown_get_selected(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let Some(ui::UiObj::Dropdown(d)) = instance_id(args.first()).and_then(|id| ui::get(&id)) {
+            if let Some(sel) = d.selected {
+                return Ok(Value::Str(sel));
+            }
+        }
+        Ok(Value::None)
+    }
+
+    fn ui_dropdown_select(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let (Some(id), Some(Value::Str(value))) = (instance_id(args.first()), args.get(1)) {
+            ui::set_selected(&id, value);
+        }
+        Ok(Value::None)
+    }
+
+    fn ui_dropdown_on_select(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let (Some(id), Some(Value::Str(func))) = (instance_id(args.first()), args.get(1)) {
+            ui::set_callback(&id, "select", func, args[0].clone());
+        }
+        Ok(Value::None)
+    }
+
+    fn ui_image_ctor(args: Vec<Value>) -> anyhow::Result<Value> {
+        let path = if let Some(Value::Str(s)) = args.first() { s.clone() } else { String::new() };
+        let obj = ui::UiObj::Image(ui::ImageObj { path });
+        let id = ui::insert(obj);
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("__id".to_string(), Value::Str(id.clone()));
+        Ok(Value::Instance("Image".to_string(), fields, std::collections::HashMap::new()))
+    }
+
     fn ui_button_get_label(args: Vec<Value>) -> anyhow::Result<Value> {
         if let Some(selfv) = args.get(0) {
             if let crate::runtime::Value::Instance(_, fields, _) = selfv {
@@ -415,6 +1322,38 @@ pub fn register_stdlib(env: &mut Env) {
         Ok(Value::Str(String::new()))
     }
 
+    // registry id stored on a UI instance's `__id` field
+    fn instance_id(selfv: Option<&Value>) -> Option<String> {
+        match selfv {
+            Some(Value::Instance(_, fields, _)) => match fields.get("__id") {
+                Some(Value::Str(id)) => Some(id.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn ui_button_set_label(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let (Some(id), Some(Value::Str(label))) = (instance_id(args.first()), args.get(1)) {
+            ui::set_label(&id, label);
+        }
+        Ok(Value::None)
+    }
+
+    fn ui_button_on_click(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let (Some(id), Some(Value::Str(func))) = (instance_id(args.first()), args.get(1)) {
+            ui::set_callback(&id, "click", func, args[0].clone());
+        }
+        Ok(Value::None)
+    }
+
+    fn ui_button_click(env: &mut crate::runtime::Env, args: Vec<Value>) -> anyhow::Result<Value> {
+        match instance_id(args.first()) {
+            Some(id) => ui::dispatch(env, &id, "click", vec![]),
+            None => Ok(Value::None),
+        }
+    }
+
     fn ui_button_set_position(args: Vec<Value>) -> anyhow::Result<Value> {
         if let Some(selfv) = args.get(0) {
             if let crate::runtime::Value::Instance(_, fields, _) = selfv {
@@ -441,6 +1380,41 @@ pub fn register_stdlib(env: &mut Env) {
         Ok(Value::None)
     }
 
+    fn ui_slider_set_value(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let (Some(id), Some(Value::Int(v))) = (instance_id(args.first()), args.get(1)) {
+            ui::set_slider_value(&id, *v);
+        }
+        Ok(Value::None)
+    }
+
+    fn ui_slider_get_value(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let Some(ui::UiObj::Slider(s)) = instance_id(args.first()).and_then(|id| ui::get(&id)) {
+            return Ok(Value::Int(s.value));
+        }
+        Ok(Value::None)
+    }
+
+    fn ui_slider_on_change(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let (Some(id), Some(Value::Str(func))) = (instance_id(args.first()), args.get(1)) {
+            ui::set_callback(&id, "change", func, args[0].clone());
+        }
+        Ok(Value::None)
+    }
+
+    fn ui_progress_set_value(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let (Some(id), Some(Value::Int(v))) = (instance_id(args.first()), args.get(1)) {
+            ui::set_progress(&id, *v);
+        }
+        Ok(Value::None)
+    }
+
+    fn ui_progress_get_value(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let Some(ui::UiObj::ProgressBar(p)) = instance_id(args.first()).and_then(|id| ui::get(&id)) {
+            return Ok(Value::Int(p.value));
+        }
+        Ok(Value::None)
+    }
+
     fn ui_radio_is_selected(_args: Vec<Value>) -> anyhow::Result<Value> {
         // For now, always return false - real implementation would check state
         Ok(Value::Bool(false))
@@ -482,19 +1456,6 @@ pub fn register_stdlib(env: &mut Env) {
         Ok(Value::None)
     }
 
-    fn ui_layout_add_child(args: Vec<Value>) -> anyhow::Result<Value> {
-        if let Some(selfv) = args.get(0) {
-            if let crate::runtime::Value::Instance(_, fields, _) = selfv {
-                if let Some(crate::runtime::Value::Str(id)) = fields.get("__id") {
-                    if let Some(crate::runtime::Value::Str(child_id)) = args.get(1) {
-                        ui::set_position(id, child_id, 0, 0);
-                    }
-                }
-            }
-        }
-        Ok(Value::None)
-    }
-
     fn ui_column_ctor(_args: Vec<Value>) -> anyhow::Result<Value> {
         let obj = ui::UiObj::Column(ui::ColumnObj { children: vec![] });
         let id = ui::insert(obj);
@@ -503,7 +1464,7 @@ pub fn register_stdlib(env: &mut Env) {
         let mut methods = std::collections::HashMap::new();
         methods.insert("set_position".to_string(), Function::Native(ui_layout_set_position));
         methods.insert("set_spacing".to_string(), Function::Native(ui_layout_set_spacing));
-        methods.insert("add".to_string(), Function::Native(ui_layout_add_child));
+        methods.insert("add".to_string(), Function::Native(ui_add_child));
         Ok(Value::Instance("Column".to_string(), fields, methods))
     }
 
@@ -515,10 +1476,26 @@ pub fn register_stdlib(env: &mut Env) {
         let mut methods = std::collections::HashMap::new();
         methods.insert("set_position".to_string(), Function::Native(ui_layout_set_position));
         methods.insert("set_spacing".to_string(), Function::Native(ui_layout_set_spacing));
-        methods.insert("add".to_string(), Function::Native(ui_layout_add_child));
+        methods.insert("add".to_string(), Function::Native(ui_add_child));
         Ok(Value::Instance("Row".to_string(), fields, methods))
     }
 
+    fn ui_grid_ctor(args: Vec<Value>) -> anyhow::Result<Value> {
+        let columns = match args.first() {
+            Some(Value::Int(n)) if *n > 0 => *n as usize,
+            _ => return Err(anyhow::anyhow!("Grid(columns) requires a positive column count")),
+        };
+        let obj = ui::UiObj::Grid(ui::GridObj { columns, children: vec![] });
+        let id = ui::insert(obj);
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("__id".to_string(), Value::Str(id.clone()));
+        let mut methods = std::collections::HashMap::new();
+        methods.insert("set_position".to_string(), Function::Native(ui_layout_set_position));
+        methods.insert("set_spacing".to_string(), Function::Native(ui_layout_set_spacing));
+        methods.insert("add".to_string(), Function::Native(ui_add_child));
+        Ok(Value::Instance("Grid".to_string(), fields, methods))
+    }
+
     // native methods operate on 'self' as first arg
     fn ui_set_title(args: Vec<Value>) -> anyhow::Result<Value> {
         if let Some(selfv) = args.get(0) {
@@ -573,6 +1550,27 @@ pub fn register_stdlib(env: &mut Env) {
         Ok(Value::None)
     }
 
+    fn ui_set_theme(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let (Some(id), Some(Value::Str(theme))) = (instance_id(args.first()), args.get(1)) {
+            ui::set_theme(&id, theme);
+        }
+        Ok(Value::None)
+    }
+
+    fn ui_get_theme(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let Some(ui::UiObj::Window(w)) = instance_id(args.first()).and_then(|id| ui::get(&id)) {
+            return Ok(Value::Str(w.theme));
+        }
+        Ok(Value::None)
+    }
+
+    fn ui_window_on_key(args: Vec<Value>) -> anyhow::Result<Value> {
+        if let (Some(id), Some(Value::Str(func))) = (instance_id(args.first()), args.get(1)) {
+            ui::set_key_handler(&id, func, args[0].clone());
+        }
+        Ok(Value::None)
+    }
+
     fn ui_add_child(args: Vec<Value>) -> anyhow::Result<Value> {
         if let Some(selfv) = args.get(0) {
             if let crate::runtime::Value::Instance(_, fields, _) = selfv {
@@ -590,7 +1588,7 @@ pub fn register_stdlib(env: &mut Env) {
         Ok(Value::None)
     }
 
-    fn ui_run(args: Vec<Value>) -> anyhow::Result<Value> {
+    fn ui_run(_env: &mut crate::runtime::Env, args: Vec<Value>) -> anyhow::Result<Value> {
         if let Some(selfv) = args.get(0) {
             if let crate::runtime::Value::Instance(_, fields, _) = selfv {
                 if let Some(crate::runtime::Value::Str(id)) = fields.get("__id") {
@@ -599,8 +1597,12 @@ pub fn register_stdlib(env: &mut Env) {
                             ui::UiObj::Window(_w) => {
                                 #[cfg(feature = "real-iced")]
                                 {
-                                    // Pass both the window ID and its title to iced_real
-                                    crate::stdlib::iced_stub::iced_real::launch_window(id, &_w.title);
+                                    // Lend the script env to the app so callbacks can run,
+                                    // then take it back once the window closes
+                                    use std::sync::{Arc, Mutex};
+                                    let shared = Arc::new(Mutex::new(std::mem::replace(_env, crate::runtime::Env::new())));
+                                    crate::stdlib::iced_stub::iced_real::launch_window(id, &_w.title, shared.clone());
+                                    *_env = std::mem::replace(&mut *shared.lock().unwrap(), crate::runtime::Env::new());
                                 }
                             }
                             _ => {}
@@ -614,11 +1616,32 @@ pub fn register_stdlib(env: &mut Env) {
 
     // register constructors as functions
     env.set_func("Window".to_string(), Function::Native(ui_window_ctor));
+    env.set_func("Text".to_string(), Function::Native(ui_text_ctor));
     env.set_func("Button".to_string(), Function::Native(ui_button_ctor));
     env.set_func("Slider".to_string(), Function::Native(ui_slider_ctor));
+    env.set_func("ProgressBar".to_string(), Function::Native(ui_progress_ctor));
     env.set_func("RadioButton".to_string(), Function::Native(ui_radio_ctor));
+    env.set_func("Dropdown".to_string(), Function::Native(ui_dropdown_ctor));
+    env.set_func("Image".to_string(), Function::Native(ui_image_ctor));
     env.set_func("Column".to_string(), Function::Native(ui_column_ctor));
     env.set_func("Row".to_string(), Function::Native(ui_row_ctor));
+    env.set_func("Grid".to_string(), Function::Native(ui_grid_ctor));
+
+    // Inspect a widget by registry id
+    env.set_func("ui_get".to_string(), Function::Native(|args| {
+        if let Some(Value::Str(id)) = args.first() {
+            Ok(ui::describe(id).unwrap_or(Value::None))
+        } else {
+            Ok(Value::None)
+        }
+    }));
+
+    env.set_func("ui_render".to_string(), Function::Native(|args| {
+        match args.first() {
+            Some(Value::Str(id)) => Ok(Value::Str(ui::render(id))),
+            _ => Err(anyhow::anyhow!("ui_render() requires a widget id")),
+        }
+    }));
 
     // OpenCV image processing functions
     env.set_func("cv_load_image".to_string(), Function::Native(|args| {
@@ -645,6 +1668,14 @@ pub fn register_stdlib(env: &mut Env) {
         }
     }));
 
+    env.set_func("cv_convert_color".to_string(), Function::Native(|args| {
+        if let (Some(Value::Str(img_id)), Some(Value::Str(code))) = (args.first(), args.get(1)) {
+            Ok(Value::Str(opencv::opencv::convert_color(img_id, code)?))
+        } else {
+            Ok(Value::Str("[OpenCV] Error: image_id and conversion code required".to_string()))
+        }
+    }));
+
     env.set_func("cv_draw_rect".to_string(), Function::Native(|args| {
         if args.len() >= 9 {
             if let (Some(Value::Str(img)), Some(Value::Int(x1)), Some(Value::Int(y1)), 
@@ -717,6 +1748,51 @@ pub fn register_stdlib(env: &mut Env) {
         Ok(Value::Str(opencv::opencv::start_camera()))
     }));
 
+    env.set_func("cv_read_frame".to_string(), Function::Native(|args| {
+        if let Some(Value::Str(camera_id)) = args.first() {
+            Ok(Value::Str(opencv::opencv::read_frame(camera_id)?))
+        } else {
+            Ok(Value::Str("[OpenCV] Error: camera_id required".to_string()))
+        }
+    }));
+
+    env.set_func("cv_release_camera".to_string(), Function::Native(|args| {
+        if let Some(Value::Str(camera_id)) = args.first() {
+            Ok(Value::Str(opencv::opencv::release_camera(camera_id)?))
+        } else {
+            Ok(Value::Str("[OpenCV] Error: camera_id required".to_string()))
+        }
+    }));
+
+    env.set_func("cv_open_writer".to_string(), Function::Native(|args| {
+        let fps = match args.get(1) {
+            Some(Value::Float(f)) => *f,
+            Some(Value::Int(i)) => *i as f64,
+            _ => 0.0,
+        };
+        if let (Some(Value::Str(path)), Some(Value::Int(w)), Some(Value::Int(h))) = (args.first(), args.get(2), args.get(3)) {
+            Ok(Value::Str(opencv::opencv::open_writer(path, fps, *w as i32, *h as i32)?))
+        } else {
+            Ok(Value::Str("[OpenCV] Error: path, fps, width and height required".to_string()))
+        }
+    }));
+
+    env.set_func("cv_write_frame".to_string(), Function::Native(|args| {
+        if let (Some(Value::Str(writer)), Some(Value::Str(img_id))) = (args.first(), args.get(1)) {
+            Ok(Value::Str(opencv::opencv::write_frame(writer, img_id)?))
+        } else {
+            Ok(Value::Str("[OpenCV] Error: writer and image_id required".to_string()))
+        }
+    }));
+
+    env.set_func("cv_close_writer".to_string(), Function::Native(|args| {
+        if let Some(Value::Str(writer)) = args.first() {
+            Ok(Value::Str(opencv::opencv::close_writer(writer)?))
+        } else {
+            Ok(Value::Str("[OpenCV] Error: writer required".to_string()))
+        }
+    }));
+
     env.set_func("cv_detect_faces".to_string(), Function::Native(|args| {
         if let Some(Value::Str(img_id)) = args.get(0) {
             let cascade_path = match args.get(1) {
@@ -802,21 +1878,62 @@ pub fn register_stdlib(env: &mut Env) {
     // Subprocess module functions
     env.set_func("subprocess_run".to_string(), Function::Native(crate::stdlib::subprocess::subprocess_run));
     env.set_func("subprocess_popen".to_string(), Function::Native(crate::stdlib::subprocess::subprocess_popen));
+    env.set_func("subprocess_pipe".to_string(), Function::Native(crate::stdlib::subprocess::subprocess_pipe));
+    env.set_func("on_signal".to_string(), Function::Native(|args| {
+        match (args.first(), args.get(1)) {
+            (Some(Value::Str(name)), Some(handler @ (Value::Str(_) | Value::Func(_, _)))) => {
+                crate::stdlib::signals::register(name, handler.clone())?;
+                Ok(Value::None)
+            }
+            _ => Err(anyhow::anyhow!("on_signal() requires a signal name and a handler function")),
+        }
+    }));
+    env.set_func("kill_pid".to_string(), Function::Native(crate::stdlib::subprocess::kill_pid));
 
     // Help system function
     env.set_func("help".to_string(), Function::Native(|args| {
         if let Some(Value::Str(topic)) = args.get(0) {
             match topic.as_str() {
                 "print" => Ok(Value::Str("print(value) - Prints a value to standard output\n  Example: print(\"Hello, World!\")".to_string())),
-                "len" => Ok(Value::Str("len(obj) - Returns the length of a string or list\n  Example: len(\"hello\") returns 5".to_string())),
+                "len" => Ok(Value::Str("len(obj) - Returns the length of a string (in characters), list or bytes\n  Example: len(\"hello\") returns 5".to_string())),
                 "range" => Ok(Value::Str("range(end) or range(start, end) - Creates a range object\n  Example: for i in range(1, 5): print(i)".to_string())),
                 "str" | "int" | "float" | "bool" => Ok(Value::Str(format!("{}(obj) - Converts object to {} type", topic, topic))),
+                "try_parse_int" | "try_parse_float" => Ok(Value::Str("try_parse_int(s) / try_parse_float(s) - Parse without raising\n  Returns [True, value] on success and [False, None] otherwise\n  Example: ok, n = try_parse_int(\"42\")".to_string())),
+                "is_nan" | "is_inf" => Ok(Value::Str("is_nan(x) - True when x is NaN\nis_inf(x) - True when x is positive or negative infinity\n  Integers are never NaN or infinite\n  Example: bad = is_nan(float(\"nan\"))".to_string())),
+                "argmin" | "argmax" => Ok(Value::Str("argmin(list) / argmax(list) - Index of the smallest / largest element\n  Ties return the first index; an empty list is an error\n  Example: i = argmax([1, 5, 3])".to_string())),
+                "mean" | "median" | "variance" | "stdev" => Ok(Value::Str("mean(xs), median(xs), variance(xs), stdev(xs) - Summary statistics of a list of numbers\n  variance and stdev are sample statistics and need at least two values\n  Example: avg = mean([1, 2, 3, 4])".to_string())),
+                "linspace" => Ok(Value::Str("linspace(start, stop, n) - n evenly spaced floats from start to stop inclusive\n  n = 1 gives [start], n = 0 gives []\n  Example: xs = linspace(0, 1, 5)".to_string())),
+                "dot" | "matmul" => Ok(Value::Str("dot(a, b) - Dot product of two equal-length lists of numbers\nmatmul(A, B) - Multiply two matrices given as lists of rows\n  Mismatched dimensions are an error\n  Example: c = matmul([[1, 2], [3, 4]], [[5, 6], [7, 8]])".to_string())),
+                "format_table" => Ok(Value::Str("format_table(rows[, headers]) - Aligned text table from a list of rows\n  Rows are lists (by position) or dicts (by header name); missing cells are empty\n  Example: print(format_table([[\"Ann\", 31]], [\"name\", \"age\"]))".to_string())),
+                "word_count" | "char_count" | "line_count" => Ok(Value::Str("word_count(s), char_count(s), line_count(s) - Count words, characters or lines\n  Words are split on any whitespace; a trailing newline does not add a line\n  Example: n = word_count(read_file(\"notes.txt\"))".to_string())),
+                "type" | "assert_type" => Ok(Value::Str("type(x) - Type name of x: int, float, bool, str, bytes, list, dict, range, function, None, or an instance's class\nassert_type(x, names) - Return x if its type is names (a name or list of names), else raise\n  Example: n = assert_type(count, [\"int\", \"float\"])".to_string())),
+                "json_dumps" | "json_loads" => Ok(Value::Str("json_dumps(value) - Serialize a value to a JSON string (dict keys sorted)\njson_loads(text) - Parse a JSON string into lists, dicts and scalars\n  Example: cfg = json_loads(read_file(\"config.json\"))".to_string())),
+                "floor" | "ceil" | "round" => Ok(Value::Str("floor(x), ceil(x), round(x) - Round down, up or to nearest, returning an int\n  round(x, digits) returns a float with that many decimal places\n  Example: n = floor(3.7)".to_string())),
+                "min" | "max" => Ok(Value::Str("min(a, b) / max(a, b) or min(list) / max(list) - Smallest / largest value\n  Example: top = max([1, 5, 2])".to_string())),
+                "pi" | "e" => Ok(Value::Str("pi, e - Math constants as floats\n  Example: area = pi * r ** 2".to_string())),
+                "random" | "randint" | "uniform" | "choice" | "shuffle" | "random_bool" | "seed" => Ok(Value::Str("random() - Float in [0, 1)\nrandint(a, b) - Integer from a to b inclusive\nuniform(a, b) - Float between a and b\nchoice(list) - Random element\nshuffle(list) - Shuffled copy of the list\nrandom_bool() - True or False\nseed(n) - Make the following random values reproducible\n  Example: seed(42)".to_string())),
+                "breakpoint" => Ok(Value::Str("breakpoint() - Pause and inspect the current scope\n  Type an expression to see its value or a statement to run it\n  vars lists variables; continue resumes; exit aborts the script\n  Example: def f(x):\n      breakpoint()".to_string())),
+                "open" => Ok(Value::Str("open(path, mode=\"r\") - Open a file, returning a File object\n  Modes: \"r\" read, \"w\" truncate and write, \"a\" append\n  Methods: read() rest of the file, readline() next line (\"\" at the end), write(s), close()\n  Example: f = open(\"log.txt\", \"a\"); f.write(\"started\\n\"); f.close()".to_string())),
+                "path_join" | "path_basename" | "path_dirname" | "path_splitext" | "path_exists" => Ok(Value::Str("path_join(a, b, ...) - Join components with the platform separator\n  path_basename(p) / path_dirname(p) - Last component / everything before it\n  path_splitext(p) - [root, ext], e.g. [\"out/log\", \".txt\"]\n  path_exists(p) - True if a file or directory exists at p\n  Example: stem = path_splitext(path_basename(f))[0]".to_string())),
+                "tcp_connect" | "tcp_listen" | "tcp_accept" | "tcp_send" | "tcp_recv" | "tcp_close" => Ok(Value::Str("tcp_connect(host, port) / tcp_listen(port) / tcp_accept(listener) - Open TCP sockets, returning a handle string\n  tcp_send(handle, data) sends a string; tcp_recv(handle, n) reads up to n bytes (\"\" when the peer has closed)\n  tcp_close(handle) closes a connection or listener\n  Example: c = tcp_connect(\"localhost\", 8080); tcp_send(c, \"hi\"); print(tcp_recv(c, 1024))".to_string())),
+                "http_get" | "http_post" => Ok(Value::Str("http_get(url) / http_post(url, body) - Blocking HTTP request\n  Returns {\"status\", \"body\", \"headers\"}, or {\"error\"} when no response was received\n  A non-string body is sent as JSON\n  Example: r = http_get(\"https://example.com\")".to_string())),
                 "sin" | "cos" | "sqrt" => Ok(Value::Str(format!("{}(x) - Math function\n  Example: {} = {}(1.57)", topic, topic, topic))),
-                "subprocess_run" => Ok(Value::Str("subprocess_run(cmd) - Execute command and wait for completion\n  Returns dict with stdout, stderr, exit_code\n  Example: result = subprocess_run(\"echo hello\")".to_string())),
+                "subprocess_run" => Ok(Value::Str("subprocess_run(cmd[, opts]) - Execute command and wait for completion\n  Returns dict with stdout, stderr, exit_code\n  opts: {\"merge_stderr\": true} folds stderr into stdout\n  Example: result = subprocess_run(\"echo hello\")".to_string())),
+                "subprocess_pipe" => Ok(Value::Str("subprocess_pipe([cmd1, cmd2, ...]) - Run commands as a pipeline\n  Each command's stdout feeds the next one's stdin; returns the final stdout\n  Example: out = subprocess_pipe([\"ls\", \"grep fm\"])".to_string())),
+                "env" => Ok(Value::Str("env - Live view of the process environment\n  env[\"KEY\"] reads a variable (error if unset), env[\"KEY\"] = \"val\" sets it\n  env.keys() lists the variable names\n  Example: path = env[\"PATH\"]".to_string())),
+                "exit" | "version" | "argv" => Ok(Value::Str("exit([code]) - End the program immediately with the given status (default 0)\nversion() - The Ferrum interpreter version, e.g. \"0.1.0\"\nargv - The script path followed by its command-line arguments\n  Example: if len(argv) < 2:\n      exit(1)".to_string())),
+                "chdir" | "mkdir" | "makedirs" | "remove_file" | "remove_dir" | "remove_dir_recursive" => Ok(Value::Str("chdir(path), mkdir(path), makedirs(path) - Change directory, create one directory, or create it with missing parents\nremove_file(path), remove_dir(path), remove_dir_recursive(path) - Delete a file, an empty directory, or a directory tree\n  Failures raise an error naming the path\n  Example: makedirs(\"out/logs\")".to_string())),
+                "isdir" | "isfile" => Ok(Value::Str("isdir(path) / isfile(path) - True if path is an existing directory / regular file\n  Example: if isfile(\"config.json\"): ...".to_string())),
+                "getenv" | "setenv" => Ok(Value::Str("getenv(name[, default]) - Environment variable, or default (None) when unset\nsetenv(name, value) - Set an environment variable for this process and its children\n  Example: home = getenv(\"HOME\", \"/tmp\")".to_string())),
+                "tempfile" | "tempdir" => Ok(Value::Str("tempfile([create]) - Unique scratch file path; creates an empty file unless create is False\ntempdir() - Create a fresh scratch directory and return its path\n  Neither is deleted automatically; remove them when done\n  Example: path = tempfile()".to_string())),
+                "retry" => Ok(Value::Str("retry(func, attempts[, delay[, backoff]]) - Call func until it succeeds\n  Sleeps delay seconds after each failure, multiplying the delay by backoff (default 1)\n  Returns the first result, or raises the last error\n  Example: data = retry(\"fetch\", 3, 0.5, 2)".to_string())),
+                "with_timeout" => Ok(Value::Str("with_timeout(func, seconds[, args...]) - Run func on a worker thread with a time limit\n  Returns func's result, or raises an error when the limit passes\n  Note: a timed-out call can't be interrupted; it finishes in the background and its result is dropped\n  Example: r = with_timeout(\"fetch\", 2.5)".to_string())),
+                "on_signal" => Ok(Value::Str("on_signal(name, handler) - Run handler when the process receives a signal\n  The handler runs between statements and gets the signal name\n  Example: on_signal(\"SIGINT\", \"shutdown\")".to_string())),
+                "kill_pid" => Ok(Value::Str("kill_pid(pid[, signal]) - Terminate a process by pid\n  signal (Unix only) is a number or name such as \"KILL\"; default is TERM\n  Example: kill_pid(proc[\"pid\"])".to_string())),
                 "subprocess_popen" => Ok(Value::Str("subprocess_popen(cmd) - Spawn process for live interaction\n  Returns dict with pid\n  Example: proc = subprocess_popen(\"ping localhost\")".to_string())),
                 _ => {
                     let help_text = format!(
-                        "No help available for '{}'\n\nAvailable topics:\n  print, len, range, str, int, float, bool\n  sin, cos, sqrt, pow, abs, exp\n  read_file, write_file, input\n  subprocess_run, subprocess_popen\n  Window, Button, Slider, RadioButton\n\nFor OpenCV: cv_load_image, cv_gaussian_blur, cv_draw_rect, cv_draw_circle, cv_detect_faces",
+                        "No help available for '{}'\n\nAvailable topics:\n  print, len, range, str, int, float, bool\n  sin, cos, sqrt, pow, abs, exp\n  read_file, write_file, input\n  subprocess_run, subprocess_popen, subprocess_pipe\n  Window, Button, Slider, RadioButton\n\nFor OpenCV: cv_load_image, cv_gaussian_blur, cv_draw_rect, cv_draw_circle, cv_detect_faces",
                         topic
                     );
                     Ok(Value::Str(help_text))
@@ -827,3 +1944,404 @@ pub fn register_stdlib(env: &mut Env) {
         }
     }));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn stdlib_env() -> Env {
+        let mut env = Env::new();
+        register_stdlib(&mut env);
+        env
+    }
+
+    fn list(items: Vec<Value>) -> Value {
+        Value::List(items)
+    }
+
+    #[test]
+    fn test_flatten_shallow_and_deep() {
+        let mut env = stdlib_env();
+        let nested = list(vec![
+            list(vec![Value::Int(1), Value::Int(2)]),
+            list(vec![Value::Int(3), list(vec![Value::Int(4)])]),
+            Value::Int(5),
+        ]);
+        env.set("nested".to_string(), nested);
+        crate::parser::parse_and_run("shallow = flatten(nested)\ndeep = flatten(nested, True)", &mut env).unwrap();
+        let shallow = list(vec![Value::Int(1), Value::Int(2), Value::Int(3), list(vec![Value::Int(4)]), Value::Int(5)]);
+        let deep = list((1..=5).map(Value::Int).collect());
+        assert!(env.get("shallow") == Some(&shallow));
+        assert!(env.get("deep") == Some(&deep));
+    }
+
+    #[test]
+    fn test_chunk_splits_list() {
+        let mut env = stdlib_env();
+        env.set("xs".to_string(), list((1..=5).map(Value::Int).collect()));
+        crate::parser::parse_and_run("chunks = chunk(xs, 2)", &mut env).unwrap();
+        let expected = list(vec![
+            list(vec![Value::Int(1), Value::Int(2)]),
+            list(vec![Value::Int(3), Value::Int(4)]),
+            list(vec![Value::Int(5)]),
+        ]);
+        assert!(env.get("chunks") == Some(&expected));
+        assert!(crate::parser::parse_and_run("bad = chunk(xs, 0)", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_reduce_sum_and_max() {
+        let mut env = stdlib_env();
+        env.set_func("add".to_string(), Function::Native(|args| match (args.first(), args.get(1)) {
+            (Some(Value::Int(a)), Some(Value::Int(b))) => Ok(Value::Int(a + b)),
+            _ => Ok(Value::None),
+        }));
+        env.set_func("bigger".to_string(), Function::Native(|args| match (args.first(), args.get(1)) {
+            (Some(a), Some(b)) => Ok(if a >= b { a.clone() } else { b.clone() }),
+            _ => Ok(Value::None),
+        }));
+        env.set("xs".to_string(), list(vec![Value::Int(3), Value::Int(9), Value::Int(4)]));
+        env.set("empty".to_string(), list(vec![]));
+        crate::parser::parse_and_run("total = reduce(add, xs, 10)\nbiggest = reduce(bigger, xs)", &mut env).unwrap();
+        assert!(env.get("total") == Some(&Value::Int(26)));
+        assert!(env.get("biggest") == Some(&Value::Int(9)));
+        assert!(crate::parser::parse_and_run("x = reduce(add, empty)", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_partial_binds_leading_args() {
+        let mut env = stdlib_env();
+        env.set_func("sub".to_string(), Function::Native(|args| match (args.first(), args.get(1)) {
+            (Some(Value::Int(a)), Some(Value::Int(b))) => Ok(Value::Int(a - b)),
+            _ => Ok(Value::None),
+        }));
+        crate::parser::parse_and_run("from_ten = partial(sub, 10)\nr = from_ten(3)", &mut env).unwrap();
+        assert!(env.get("r") == Some(&Value::Int(7)));
+    }
+
+    #[test]
+    fn test_map_values_doubles_dict() {
+        let mut env = stdlib_env();
+        env.set_func("double".to_string(), Function::Native(|args| match args.first() {
+            Some(Value::Int(i)) => Ok(Value::Int(i * 2)),
+            _ => Ok(Value::None),
+        }));
+        let mut d = HashMap::new();
+        d.insert("a".to_string(), Value::Int(1));
+        d.insert("b".to_string(), Value::Int(5));
+        env.set("d".to_string(), Value::Dict(d));
+        crate::parser::parse_and_run("doubled = map_values(d, double)", &mut env).unwrap();
+        match env.get("doubled") {
+            Some(Value::Dict(m)) => {
+                assert!(m.get("a") == Some(&Value::Int(2)));
+                assert!(m.get("b") == Some(&Value::Int(10)));
+            }
+            other => panic!("unexpected value: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_sandbox_disables_dangerous_builtins() {
+        let mut env = Env::new_sandboxed();
+        let err = crate::parser::parse_and_run("r = subprocess_run(\"echo hi\")", &mut env).unwrap_err();
+        assert!(err.to_string().contains("subprocess_run() is disabled in sandbox"), "{}", err);
+        assert!(crate::parser::parse_and_run("t = read_file(\"/etc/passwd\")", &mut env).is_err());
+        // breakpoint() would read the host's stdin
+        assert!(crate::parser::parse_and_run("breakpoint()", &mut env).is_err());
+
+        crate::parser::parse_and_run("x = 6 * 7\ns = \"ab\" + \"cd\"\nn = len(s)", &mut env).unwrap();
+        assert_eq!(env.get("x"), Some(&Value::Int(42)));
+        assert_eq!(env.get("s"), Some(&Value::Str("abcd".to_string())));
+        assert_eq!(env.get("n"), Some(&Value::Int(4)));
+    }
+
+    #[test]
+    fn test_budget_stops_infinite_loop() {
+        let mut env = Env::new_sandboxed();
+        env.set_budget(Some(1000), None);
+        let src = "def spin():\n    while true:\n        x = 1\ntry:\n    spin()\nexcept:\n    y = 2";
+        let err = crate::parser::parse_and_run(src, &mut env).unwrap_err();
+        assert!(err.to_string().contains("budget exceeded"), "{}", err);
+
+        let mut env = stdlib_env();
+        env.set_budget(None, Some(std::time::Duration::from_millis(50)));
+        let err = crate::parser::parse_and_run("while true:\n    x = 1", &mut env).unwrap_err();
+        assert!(err.to_string().contains("budget exceeded"), "{}", err);
+
+        // no budget by default
+        let mut env = stdlib_env();
+        crate::parser::parse_and_run("i = 0\nwhile i < 5000:\n    i = i + 1", &mut env).unwrap();
+    }
+
+    #[test]
+    fn test_json_get_with_default() {
+        let mut env = stdlib_env();
+        let src = "cfg = {\"server\": {\"ports\": [80, 443]}}\np = json_get(cfg, \"server.ports.1\")\nq = json_get(cfg, \"server.host\", \"localhost\")\nr = json_get(cfg, \"nope\")";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert_eq!(env.get("p"), Some(&Value::Int(443)));
+        assert_eq!(env.get("q"), Some(&Value::Str("localhost".to_string())));
+        assert_eq!(env.get("r"), Some(&Value::None));
+    }
+
+    #[test]
+    fn test_read_file_lines_in_for_loop() {
+        let path = std::env::temp_dir().join(format!("ferrum_read_lines_{}.txt", std::process::id()));
+        std::fs::write(&path, "a\nbb\nccc\n").unwrap();
+        let mut env = stdlib_env();
+        env.set("path".to_string(), Value::Str(path.to_string_lossy().into_owned()));
+        crate::parser::parse_and_run("out = \"\"\nn = 0\nfor line in read_file_lines(path):\n    out += line + \"|\"\n    n += 1", &mut env).unwrap();
+        assert_eq!(env.get("out"), Some(&Value::Str("a|bb|ccc|".to_string())));
+        assert_eq!(env.get("n"), Some(&Value::Int(3)));
+        std::fs::remove_file(&path).unwrap();
+        assert!(crate::parser::parse_and_run("x = read_file_lines(\"/no/such/file\")", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_read_file_lines_closed_when_loop_exits_early() {
+        let path = std::env::temp_dir().join(format!("ferrum_read_lines_break_{}.txt", std::process::id()));
+        std::fs::write(&path, "a\nbb\nccc\n").unwrap();
+        let mut env = stdlib_env();
+        env.set("path".to_string(), Value::Str(path.to_string_lossy().into_owned()));
+        let src = "r = read_file_lines(path)\nfor line in r:\n    if line == \"bb\":\n        break\ns = read_file_lines(path)\ntry:\n    for line in s:\n        raise \"stop\"\nexcept:\n    pass";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        // a closed handle reads as exhausted instead of yielding "ccc"
+        for name in ["r", "s"] {
+            let err = crate::parser::parse_and_run(&format!("x = {}.__next__()", name), &mut env).unwrap_err();
+            assert!(err.to_string().starts_with(crate::eval::STOP_ITERATION), "{}: {}", name, err);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_env_reads_and_writes_process_environment() {
+        let mut env = stdlib_env();
+        let src = "env[\"FERRUM_ENV_TEST\"] = \"on\"\nv = env[\"FERRUM_ENV_TEST\"]";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert_eq!(std::env::var("FERRUM_ENV_TEST").as_deref(), Ok("on"));
+        assert_eq!(env.get("v"), Some(&Value::Str("on".to_string())));
+        assert!(crate::parser::parse_and_run("x = env[\"FERRUM_ENV_UNSET_VAR\"]", &mut env).is_err());
+
+        let mut sandboxed = Env::new_sandboxed();
+        register_stdlib_sandboxed(&mut sandboxed);
+        assert!(crate::parser::parse_and_run("x = env[\"FERRUM_ENV_TEST\"]", &mut sandboxed).is_err());
+    }
+
+    #[test]
+    fn test_float_parsing_and_nan_checks() {
+        let mut env = stdlib_env();
+        assert!(crate::parser::parse_and_run("x = float(\"abc\")", &mut env).is_err());
+        let src = "a = float(\" 2.5 \")\nn = is_nan(float(\"nan\"))\ni = is_inf(float(\"-inf\"))\nf = is_nan(1.5)\ng = is_inf(3)";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert_eq!(env.get("a"), Some(&Value::Float(2.5)));
+        assert_eq!(env.get("n"), Some(&Value::Bool(true)));
+        assert_eq!(env.get("i"), Some(&Value::Bool(true)));
+        assert_eq!(env.get("f"), Some(&Value::Bool(false)));
+        assert_eq!(env.get("g"), Some(&Value::Bool(false)));
+    }
+
+    #[test]
+    fn test_argmin_argmax() {
+        let mut env = stdlib_env();
+        crate::parser::parse_and_run("a = argmax([1, 5, 3])\nb = argmin([4, 2, 2, 9])\nc = argmax([2.5, 7, 7])", &mut env).unwrap();
+        assert_eq!(env.get("a"), Some(&Value::Int(1)));
+        assert_eq!(env.get("b"), Some(&Value::Int(1)));
+        assert_eq!(env.get("c"), Some(&Value::Int(1)));
+        assert!(crate::parser::parse_and_run("x = argmin([])", &mut env).is_err());
+        assert!(crate::parser::parse_and_run("x = argmax([1, \"a\"])", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_statistics_builtins() {
+        let mut env = stdlib_env();
+        crate::parser::parse_and_run("m = mean([1, 2, 3, 4])\nodd = median([5, 1, 3])\neven = median([1, 2, 3, 4])", &mut env).unwrap();
+        assert_eq!(env.get("m"), Some(&Value::Float(2.5)));
+        assert_eq!(env.get("odd"), Some(&Value::Float(3.0)));
+        assert_eq!(env.get("even"), Some(&Value::Float(2.5)));
+        assert!(crate::parser::parse_and_run("x = mean([])", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_linspace() {
+        let mut env = stdlib_env();
+        crate::parser::parse_and_run("a = linspace(0, 1, 5)\nb = linspace(2, 3, 1)\nc = linspace(0, 1, 0)", &mut env).unwrap();
+        let floats = |xs: &[f64]| Value::List(xs.iter().map(|f| Value::Float(*f)).collect());
+        assert_eq!(env.get("a"), Some(&floats(&[0.0, 0.25, 0.5, 0.75, 1.0])));
+        assert_eq!(env.get("b"), Some(&floats(&[2.0])));
+        assert_eq!(env.get("c"), Some(&floats(&[])));
+        assert!(crate::parser::parse_and_run("x = linspace(0, 1, -1)", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_dot_and_matmul() {
+        let mut env = stdlib_env();
+        crate::parser::parse_and_run("d = dot([1, 2, 3], [4, 5, 6])\nf = dot([0.5, 2], [2, 1])\nm = matmul([[1, 2], [3, 4]], [[5, 6], [7, 8]])", &mut env).unwrap();
+        assert_eq!(env.get("d"), Some(&Value::Int(32)));
+        assert_eq!(env.get("f"), Some(&Value::Float(3.0)));
+        let row = |a: i64, b: i64| list(vec![Value::Int(a), Value::Int(b)]);
+        assert_eq!(env.get("m"), Some(&list(vec![row(19, 22), row(43, 50)])));
+        assert!(crate::parser::parse_and_run("x = dot([1, 2], [3])", &mut env).is_err());
+        assert!(crate::parser::parse_and_run("x = matmul([[1, 2]], [[1, 2]])", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_try_parse() {
+        let mut env = stdlib_env();
+        crate::parser::parse_and_run("a = try_parse_int(\"42\")\nb = try_parse_int(\"x\")\nok, f = try_parse_float(\"2.5\")\nc = try_parse_float(3)", &mut env).unwrap();
+        assert_eq!(env.get("a"), Some(&list(vec![Value::Bool(true), Value::Int(42)])));
+        assert_eq!(env.get("b"), Some(&list(vec![Value::Bool(false), Value::None])));
+        assert_eq!(env.get("ok"), Some(&Value::Bool(true)));
+        assert_eq!(env.get("f"), Some(&Value::Float(2.5)));
+        assert_eq!(env.get("c"), Some(&list(vec![Value::Bool(false), Value::None])));
+    }
+
+    #[test]
+    fn test_type_and_assert_type() {
+        let mut env = stdlib_env();
+        crate::parser::parse_and_run("t = type([1])\nn = assert_type(5, \"int\") + 1\nf = assert_type(2.5, [\"int\", \"float\"])", &mut env).unwrap();
+        assert_eq!(env.get("t"), Some(&Value::Str("list".to_string())));
+        assert_eq!(env.get("n"), Some(&Value::Int(6)));
+        assert_eq!(env.get("f"), Some(&Value::Float(2.5)));
+        let err = crate::parser::parse_and_run("x = assert_type(\"5\", \"int\")", &mut env).unwrap_err();
+        assert_eq!(err.to_string(), "TypeError: expected int, got str (5)");
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut env = stdlib_env();
+        crate::parser::parse_and_run("d = {\"name\": \"fm\", \"tags\": [1, 2.5, True, None], \"nested\": {\"k\": \"v\"}}\ns = json_dumps(d)\nsame = json_loads(s) == d", &mut env).unwrap();
+        assert_eq!(env.get("same"), Some(&Value::Bool(true)));
+        assert!(crate::parser::parse_and_run("x = json_loads(\"{bad\")", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_math_constants_and_rounding() {
+        let mut env = stdlib_env();
+        let src = "f = floor(3.7)\nc = ceil(3.2)\nr = round(2.5)\nr2 = round(pi, 2)\nbig = max([1, 5, 2])\nsmall = min(4, 2.5)\nt = atan(1) * 4 == pi";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert_eq!(env.get("f"), Some(&Value::Int(3)));
+        assert_eq!(env.get("c"), Some(&Value::Int(4)));
+        assert_eq!(env.get("r"), Some(&Value::Int(3)));
+        assert_eq!(env.get("r2"), Some(&Value::Float(3.14)));
+        assert_eq!(env.get("big"), Some(&Value::Int(5)));
+        assert_eq!(env.get("small"), Some(&Value::Float(2.5)));
+        assert_eq!(env.get("t"), Some(&Value::Bool(true)));
+        assert!(matches!(env.get("e"), Some(Value::Float(x)) if *x == std::f64::consts::E));
+        assert!(crate::parser::parse_and_run("x = max([])", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_seeded_random_builtins_are_reproducible() {
+        let mut env = stdlib_env();
+        let src = "seed(42)\na = [randint(1, 6), randint(1, 6), choice([\"x\", \"y\", \"z\"])]\nseed(42)\nb = [randint(1, 6), randint(1, 6), choice([\"x\", \"y\", \"z\"])]\ns = sorted(shuffle([3, 1, 2]))\nu = uniform(2, 3)";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert_eq!(env.get("a"), env.get("b"));
+        assert_eq!(env.get("s"), Some(&list(vec![Value::Int(1), Value::Int(2), Value::Int(3)])));
+        assert!(matches!(env.get("u"), Some(Value::Float(u)) if (2.0..3.0).contains(u)));
+        assert!(crate::parser::parse_and_run("x = randint(5, 1)", &mut env).is_err());
+        crate::parser::parse_and_run("m = randint(9223372036854775806, 9223372036854775807)", &mut env).unwrap();
+        assert!(matches!(env.get("m"), Some(Value::Int(n)) if *n >= i64::MAX - 1));
+        assert!(crate::parser::parse_and_run("x = uniform(0, 1e308 * 10)", &mut env).is_err());
+        assert!(crate::parser::parse_and_run("x = uniform(0 - 1e308, 1e308)", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_open_write_then_read_back() {
+        let path = std::env::temp_dir().join(format!("ferrum_open_{}.txt", std::process::id()));
+        let mut env = stdlib_env();
+        env.set("path".to_string(), Value::Str(path.to_string_lossy().into_owned()));
+        let src = "f = open(path, \"w\")\nf.write(\"one\\n\")\nf.write(\"two\\n\")\nf.close()\n\
+                   f = open(path, \"a\")\nf.write(\"three\")\nf.close()\n\
+                   f = open(path, \"r\")\nfirst = f.readline()\nrest = f.read()\nend = f.readline()\nf.close()";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert_eq!(env.get("first"), Some(&Value::Str("one\n".to_string())));
+        assert_eq!(env.get("rest"), Some(&Value::Str("two\nthree".to_string())));
+        assert_eq!(env.get("end"), Some(&Value::Str(String::new())));
+        // closed handles and wrong modes are errors, as are unknown modes
+        assert!(crate::parser::parse_and_run("f.read()", &mut env).is_err());
+        assert!(crate::parser::parse_and_run("g = open(path)\ng.write(\"x\")", &mut env).is_err());
+        assert!(crate::parser::parse_and_run("h = open(path, \"rw\")", &mut env).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mkdir_isdir_remove_dir_round_trip() {
+        let root = osmod::tempdir().unwrap();
+        let mut env = stdlib_env();
+        env.set("dir".to_string(), Value::Str(osmod::path_join(&[root.clone(), "made".to_string()])));
+        env.set("nested".to_string(), Value::Str(osmod::path_join(&[root.clone(), "a".to_string(), "b".to_string()])));
+        let src = "before = isdir(dir)\nmkdir(dir)\nafter = isdir(dir)\nas_file = isfile(dir)\nremove_dir(dir)\ngone = isdir(dir)\n\
+                   makedirs(nested)\ndeep = isdir(nested)";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        for (name, expected) in [("before", false), ("after", true), ("as_file", false), ("gone", false), ("deep", true)] {
+            assert_eq!(env.get(name), Some(&Value::Bool(expected)), "{}", name);
+        }
+        // errors name the failing path instead of passing silently
+        let err = crate::parser::parse_and_run("remove_dir(dir)", &mut env).unwrap_err();
+        assert!(err.to_string().starts_with("remove_dir("), "{}", err);
+        env.set("root".to_string(), Value::Str(root.clone()));
+        crate::parser::parse_and_run("remove_dir_recursive(root)", &mut env).unwrap();
+        assert!(!std::path::Path::new(&root).exists());
+    }
+
+    #[test]
+    fn test_getenv_and_setenv() {
+        let mut env = stdlib_env();
+        let src = "setenv(\"FERRUM_TEST_SETENV\", 5)\nv = getenv(\"FERRUM_TEST_SETENV\")\n\
+                   unset = getenv(\"FERRUM_TEST_NOT_SET\")\nfallback = getenv(\"FERRUM_TEST_NOT_SET\", \"x\")";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        assert_eq!(env.get("v"), Some(&Value::Str("5".to_string())));
+        assert_eq!(env.get("unset"), Some(&Value::None));
+        assert_eq!(env.get("fallback"), Some(&Value::Str("x".to_string())));
+    }
+
+    #[test]
+    fn test_sorted_rejects_mixed_list() {
+        let mut env = stdlib_env();
+        let mixed: Vec<Value> = (0..60).map(|i| if i % 3 == 0 { Value::Str(i.to_string()) } else { Value::Int(60 - i) }).collect();
+        env.set("mixed".to_string(), list(mixed));
+        env.set("nums".to_string(), list(vec![Value::Int(3), Value::Float(1.5), Value::Int(2)]));
+        let err = crate::parser::parse_and_run("s = sorted(mixed)", &mut env).unwrap_err();
+        assert!(err.to_string().contains("cannot compare"), "{}", err);
+        crate::parser::parse_and_run("s = sorted(nums)", &mut env).unwrap();
+        assert_eq!(env.get("s"), Some(&list(vec![Value::Float(1.5), Value::Int(2), Value::Int(3)])));
+    }
+
+    #[test]
+    fn test_slider_rejects_inverted_bounds() {
+        let mut env = stdlib_env();
+        let err = crate::parser::parse_and_run("s = Slider(10, 0)", &mut env).unwrap_err();
+        assert!(err.to_string().contains("greater than max"), "{}", err);
+        crate::parser::parse_and_run("s = Slider(-9223372036854775807, 9223372036854775807)\nv = s.get_value()", &mut env).unwrap();
+        assert_eq!(env.get("v"), Some(&Value::Int(0)));
+    }
+
+    #[test]
+    fn test_progress_bar_rejects_inverted_bounds() {
+        let mut env = stdlib_env();
+        let err = crate::parser::parse_and_run("p = ProgressBar(100, 0)", &mut env).unwrap_err();
+        assert!(err.to_string().contains("greater than max"), "{}", err);
+        crate::parser::parse_and_run("p = ProgressBar(0, 10)\np.set_value(25)\nv = p.get_value()", &mut env).unwrap();
+        assert_eq!(env.get("v"), Some(&Value::Int(10)));
+    }
+
+    #[test]
+    fn test_sleep_rejects_unrepresentable_durations() {
+        let mut env = stdlib_env();
+        let src = "msgs = []\nfor d in [1e300, -1, 0 - 0.5]:\n    try:\n        sleep(d)\n    except Exception as e:\n        msgs.append(str(e))\ntry:\n    sleep_until(1e300)\nexcept Exception as e:\n    msgs.append(str(e))";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        match env.get("msgs") {
+            Some(Value::List(msgs)) => assert_eq!(msgs.len(), 4, "{:?}", msgs),
+            other => panic!("expected a list, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_len_counts_characters_like_indexing() {
+        let mut env = stdlib_env();
+        crate::parser::parse_and_run("s = \"héllo\"\nn = len(s)\nout = \"\"\nfor i in range(0, len(s)):\n    out += s[i]\nb = len(s.encode())", &mut env).unwrap();
+        assert_eq!(env.get("n"), Some(&Value::Int(5)));
+        assert_eq!(env.get("out"), Some(&Value::Str("héllo".to_string())));
+        assert_eq!(env.get("b"), Some(&Value::Int(6)));
+    }
+}
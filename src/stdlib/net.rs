@@ -0,0 +1,231 @@
+//! Network module - blocking HTTP client and TCP sockets
+//!
+//! Requests return a dict with `status`, `body` and `headers`, or a dict with
+//! a single `error` key when the request could not be made at all (bad URL,
+//! refused connection, timeout). HTTP error statuses such as 404 are normal
+//! responses, not errors.
+//!
+//! Sockets live in [`SOCKETS`], keyed by string handles such as `tcp-3` that
+//! scripts pass back to `tcp_send`, `tcp_recv`, `tcp_accept` and `tcp_close`.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+
+use crate::runtime::Value;
+
+/// How long a request may take before it is reported as an error
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// GET `url`
+pub fn http_get(url: &str) -> Value {
+    let url = url.to_string();
+    request(move |client| client.get(url))
+}
+
+/// POST `body` to `url`
+pub fn http_post(url: &str, body: &str) -> Value {
+    let (url, body) = (url.to_string(), body.to_string());
+    request(move |client| client.post(url).body(body))
+}
+
+fn request<F>(build: F) -> Value
+where
+    F: FnOnce(&reqwest::blocking::Client) -> reqwest::blocking::RequestBuilder + Send + 'static,
+{
+    // reqwest's blocking client panics when used on a tokio runtime thread,
+    // which is where `ferrum run` executes scripts, so run it on its own thread
+    let worker = std::thread::spawn(move || -> Result<Value, String> {
+        let client = reqwest::blocking::Client::builder().timeout(TIMEOUT).build().map_err(|e| e.to_string())?;
+        let response = build(&client).send().map_err(|e| e.to_string())?;
+        let status = response.status().as_u16() as i64;
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.as_str().to_string(), Value::Str(String::from_utf8_lossy(v.as_bytes()).into_owned())))
+            .collect();
+        let body = response.text().map_err(|e| e.to_string())?;
+        let mut out = HashMap::new();
+        out.insert("status".to_string(), Value::Int(status));
+        out.insert("body".to_string(), Value::Str(body));
+        out.insert("headers".to_string(), Value::Dict(headers));
+        Ok(Value::Dict(out))
+    });
+    let result = worker.join().unwrap_or_else(|_| Err("HTTP worker thread panicked".to_string()));
+    result.unwrap_or_else(|e| {
+        let mut out = HashMap::new();
+        out.insert("error".to_string(), Value::Str(e));
+        Value::Dict(out)
+    })
+}
+
+/// An open socket owned by a script
+#[derive(Debug)]
+pub enum Socket {
+    Stream(TcpStream),
+    Listener(TcpListener),
+}
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+pub static SOCKETS: Lazy<Mutex<HashMap<String, Socket>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn insert(socket: Socket) -> Value {
+    let kind = match socket {
+        Socket::Stream(_) => "tcp",
+        Socket::Listener(_) => "listener",
+    };
+    let id = format!("{}-{}", kind, NEXT_ID.fetch_add(1, Ordering::SeqCst));
+    SOCKETS.lock().unwrap().insert(id.clone(), socket);
+    Value::Str(id)
+}
+
+/// A clone of the stream behind `handle`, so blocking reads and writes don't
+/// hold the registry lock
+fn stream(handle: &str) -> Result<TcpStream> {
+    match SOCKETS.lock().unwrap().get(handle) {
+        Some(Socket::Stream(s)) => Ok(s.try_clone()?),
+        Some(Socket::Listener(_)) => Err(anyhow!("{} is a listener, not a connection", handle)),
+        None => Err(anyhow!("Unknown socket handle: {}", handle)),
+    }
+}
+
+/// Connect to `host:port` and return the connection's handle
+pub fn tcp_connect(host: &str, port: u16) -> Result<Value> {
+    let stream = TcpStream::connect((host, port)).map_err(|e| anyhow!("tcp_connect({}:{}): {}", host, port, e))?;
+    Ok(insert(Socket::Stream(stream)))
+}
+
+/// Listen on all interfaces at `port` (0 picks a free port) and return the
+/// listener's handle
+pub fn tcp_listen(port: u16) -> Result<Value> {
+    let listener = TcpListener::bind(("0.0.0.0", port)).map_err(|e| anyhow!("tcp_listen({}): {}", port, e))?;
+    Ok(insert(Socket::Listener(listener)))
+}
+
+/// Wait for the next client on a listener and return its connection handle
+pub fn tcp_accept(handle: &str) -> Result<Value> {
+    let listener = match SOCKETS.lock().unwrap().get(handle) {
+        Some(Socket::Listener(l)) => l.try_clone()?,
+        Some(Socket::Stream(_)) => return Err(anyhow!("{} is a connection, not a listener", handle)),
+        None => return Err(anyhow!("Unknown socket handle: {}", handle)),
+    };
+    let (stream, _) = listener.accept()?;
+    Ok(insert(Socket::Stream(stream)))
+}
+
+/// Send all of `data`, returning the number of bytes written
+pub fn tcp_send(handle: &str, data: &str) -> Result<Value> {
+    stream(handle)?.write_all(data.as_bytes())?;
+    Ok(Value::Int(data.len() as i64))
+}
+
+/// Read up to `n` bytes, blocking until some arrive. An empty string means
+/// the peer closed the connection.
+pub fn tcp_recv(handle: &str, n: usize) -> Result<Value> {
+    let mut buf = vec![0u8; n];
+    let read = stream(handle)?.read(&mut buf)?;
+    Ok(Value::Str(String::from_utf8_lossy(&buf[..read]).into_owned()))
+}
+
+/// Close a connection or listener and forget its handle
+pub fn tcp_close(handle: &str) -> Result<Value> {
+    match SOCKETS.lock().unwrap().remove(handle) {
+        Some(_) => Ok(Value::None),
+        None => Err(anyhow!("Unknown socket handle: {}", handle)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field<'a>(v: &'a Value, key: &str) -> Option<&'a Value> {
+        match v {
+            Value::Dict(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_connection_error_is_reported_in_dict() {
+        // bind then drop a listener so the port is known to be closed
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let result = http_get(&format!("http://127.0.0.1:{}/", port));
+        assert!(matches!(field(&result, "error"), Some(Value::Str(_))), "{:?}", result);
+        assert!(field(&result, "status").is_none());
+        assert!(matches!(field(&http_get("not a url"), "error"), Some(Value::Str(_))));
+    }
+
+    fn handle(v: Result<Value>) -> String {
+        match v.unwrap() {
+            Value::Str(id) => id,
+            other => panic!("expected handle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_tcp_echo_between_handles() {
+        let server = handle(tcp_listen(0));
+        let port = match SOCKETS.lock().unwrap().get(&server) {
+            Some(Socket::Listener(l)) => l.local_addr().unwrap().port(),
+            _ => panic!("listener missing from registry"),
+        };
+        let client_thread = std::thread::spawn(move || {
+            let client = handle(tcp_connect("127.0.0.1", port));
+            assert_eq!(tcp_send(&client, "ping").unwrap(), Value::Int(4));
+            let reply = tcp_recv(&client, 64).unwrap();
+            tcp_close(&client).unwrap();
+            reply
+        });
+        let conn = handle(tcp_accept(&server));
+        let msg = match tcp_recv(&conn, 64).unwrap() {
+            Value::Str(s) => s,
+            other => panic!("expected str, got {:?}", other),
+        };
+        tcp_send(&conn, &format!("echo: {}", msg)).unwrap();
+        assert_eq!(client_thread.join().unwrap(), Value::Str("echo: ping".to_string()));
+        // the client has closed, so the next read hits end of stream
+        assert_eq!(tcp_recv(&conn, 64).unwrap(), Value::Str(String::new()));
+        tcp_close(&conn).unwrap();
+        tcp_close(&server).unwrap();
+        assert!(tcp_send(&conn, "late").is_err());
+        assert!(tcp_accept(&conn).is_err());
+    }
+
+    /// Round trip against a one-shot local HTTP server. Run with
+    /// `cargo test --features net-tests`.
+    #[cfg(feature = "net-tests")]
+    #[test]
+    fn test_post_against_local_server() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            // read until the 5-byte body after the blank line has arrived
+            while !String::from_utf8_lossy(&request).contains("\r\n\r\nhello") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 2\r\nX-Test: yes\r\nConnection: close\r\n\r\nok")
+                .unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+        let result = http_post(&format!("http://127.0.0.1:{}/items", port), "hello");
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /items HTTP/1.1"), "{}", request);
+        assert_eq!(field(&result, "status"), Some(&Value::Int(201)));
+        assert_eq!(field(&result, "body"), Some(&Value::Str("ok".to_string())));
+        let headers = field(&result, "headers").unwrap();
+        assert_eq!(field(headers, "x-test"), Some(&Value::Str("yes".to_string())));
+    }
+}
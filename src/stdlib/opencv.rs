@@ -14,6 +14,24 @@
 #[cfg(feature = "opencv-support")]
 pub mod opencv_real {
     use anyhow::Result;
+    use once_cell::sync::Lazy;
+    use opencv::core::Mat;
+    use opencv::videoio::{VideoCapture, VideoWriter};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Registry of decoded images keyed by their image ID
+    static MATS: Lazy<Mutex<HashMap<String, Mat>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+    /// Registry of open capture devices keyed by camera ID
+    static CAMERAS: Lazy<Mutex<HashMap<String, VideoCapture>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+    /// Registry of open video writers keyed by writer ID
+    static WRITERS: Lazy<Mutex<HashMap<String, VideoWriter>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+    static NEXT_FRAME: AtomicUsize = AtomicUsize::new(1);
+    static NEXT_WRITER: AtomicUsize = AtomicUsize::new(1);
 
     /// Load an image from disk
     /// 
@@ -24,18 +42,60 @@ pub mod opencv_real {
     /// Image handle as a string ID, or error message
     pub fn load_image(path: &str) -> Result<String> {
         use opencv::imgcodecs::imread;
-        use opencv::highgui::named_window;
         use std::path::Path;
         
         if !Path::new(path).exists() {
             return Err(anyhow::anyhow!("Image file not found: {}", path));
         }
         
-        let _mat = imread(path, opencv::imgcodecs::IMREAD_COLOR)?;
+        let mat = imread(path, opencv::imgcodecs::IMREAD_COLOR)?;
         let img_id = format!("img-{}", path);
+        MATS.lock().unwrap().insert(img_id.clone(), mat);
         Ok(img_id)
     }
 
+    /// Map a conversion name such as "BGR2GRAY" to its OpenCV constant
+    fn color_conversion_code(code: &str) -> Option<i32> {
+        use opencv::imgproc::*;
+        let c = match code.to_uppercase().as_str() {
+            "BGR2GRAY" => COLOR_BGR2GRAY,
+            "RGB2GRAY" => COLOR_RGB2GRAY,
+            "GRAY2BGR" => COLOR_GRAY2BGR,
+            "BGR2RGB" => COLOR_BGR2RGB,
+            "RGB2BGR" => COLOR_RGB2BGR,
+            "BGR2HSV" => COLOR_BGR2HSV,
+            "HSV2BGR" => COLOR_HSV2BGR,
+            "BGR2LAB" => COLOR_BGR2Lab,
+            "LAB2BGR" => COLOR_Lab2BGR,
+            "BGR2YCRCB" => COLOR_BGR2YCrCb,
+            "YCRCB2BGR" => COLOR_YCrCb2BGR,
+            _ => return None,
+        };
+        Some(c)
+    }
+
+    /// Convert an image between color spaces
+    /// 
+    /// # Arguments
+    /// * `image_id` - Source image handle
+    /// * `code` - Conversion name, e.g. "BGR2GRAY" or "BGR2HSV"
+    /// 
+    /// # Returns
+    /// New image ID holding the converted image
+    pub fn convert_color(image_id: &str, code: &str) -> Result<String> {
+        let cv_code = color_conversion_code(code)
+            .ok_or_else(|| anyhow::anyhow!("Unknown color conversion: {}", code))?;
+        let mut mats = MATS.lock().unwrap();
+        let src = mats
+            .get(image_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown image: {}", image_id))?;
+        let mut dst = Mat::default();
+        opencv::imgproc::cvt_color(src, &mut dst, cv_code, 0)?;
+        let new_id = format!("{}-{}", image_id, code.to_lowercase());
+        mats.insert(new_id.clone(), dst);
+        Ok(new_id)
+    }
+
     /// Display an image in a named window
     /// 
     /// # Arguments
@@ -121,9 +181,90 @@ pub mod opencv_real {
     /// # Returns
     /// Camera capture handle as string ID
     pub fn start_camera() -> Result<String> {
-        use opencv::videoio::VideoCapture;
-        let _cap = VideoCapture::new(0, opencv::videoio::CAP_ANY)?;
-        Ok("camera-0".to_string())
+        let cap = VideoCapture::new(0, opencv::videoio::CAP_ANY)?;
+        let camera_id = "camera-0".to_string();
+        CAMERAS.lock().unwrap().insert(camera_id.clone(), cap);
+        Ok(camera_id)
+    }
+
+    /// Grab the next frame from an open camera
+    /// 
+    /// # Arguments
+    /// * `camera_id` - Camera handle from start_camera()
+    /// 
+    /// # Returns
+    /// Image ID of the captured frame
+    pub fn read_frame(camera_id: &str) -> Result<String> {
+        use opencv::prelude::VideoCaptureTrait;
+        let mut cameras = CAMERAS.lock().unwrap();
+        let cap = cameras
+            .get_mut(camera_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown camera: {}", camera_id))?;
+        let mut frame = Mat::default();
+        if !cap.read(&mut frame)? {
+            return Err(anyhow::anyhow!("Failed to read frame from {}", camera_id));
+        }
+        let n = NEXT_FRAME.fetch_add(1, Ordering::SeqCst);
+        let frame_id = format!("{}-frame-{}", camera_id, n);
+        MATS.lock().unwrap().insert(frame_id.clone(), frame);
+        Ok(frame_id)
+    }
+
+    /// Release a camera and free the capture device
+    pub fn release_camera(camera_id: &str) -> Result<String> {
+        use opencv::prelude::VideoCaptureTrait;
+        let mut cap = CAMERAS
+            .lock()
+            .unwrap()
+            .remove(camera_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown camera: {}", camera_id))?;
+        cap.release()?;
+        Ok(format!("[OpenCV] Camera {} released", camera_id))
+    }
+
+    /// Open a video file for writing
+    /// 
+    /// # Arguments
+    /// * `path` - Output video path (encoded as MJPG)
+    /// * `fps` - Frames per second
+    /// * `width, height` - Frame size in pixels
+    /// 
+    /// # Returns
+    /// Writer handle as string ID
+    pub fn open_writer(path: &str, fps: f64, width: i32, height: i32) -> Result<String> {
+        use opencv::core::Size;
+        let fourcc = VideoWriter::fourcc('M', 'J', 'P', 'G')?;
+        let writer = VideoWriter::new(path, fourcc, fps, Size::new(width, height), true)?;
+        let writer_id = format!("writer-{}", NEXT_WRITER.fetch_add(1, Ordering::SeqCst));
+        WRITERS.lock().unwrap().insert(writer_id.clone(), writer);
+        Ok(writer_id)
+    }
+
+    /// Append an image to an open video writer
+    pub fn write_frame(writer_id: &str, image_id: &str) -> Result<String> {
+        use opencv::prelude::VideoWriterTrait;
+        let mut writers = WRITERS.lock().unwrap();
+        let writer = writers
+            .get_mut(writer_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown video writer: {}", writer_id))?;
+        let mats = MATS.lock().unwrap();
+        let frame = mats
+            .get(image_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown image: {}", image_id))?;
+        writer.write(frame)?;
+        Ok(format!("[OpenCV] Frame {} written to {}", image_id, writer_id))
+    }
+
+    /// Finish and close a video writer
+    pub fn close_writer(writer_id: &str) -> Result<String> {
+        use opencv::prelude::VideoWriterTrait;
+        let mut writer = WRITERS
+            .lock()
+            .unwrap()
+            .remove(writer_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown video writer: {}", writer_id))?;
+        writer.release()?;
+        Ok(format!("[OpenCV] Video writer {} closed", writer_id))
     }
 
     /// Detect faces in an image using cascade classifier
@@ -173,6 +314,24 @@ pub mod opencv_real {
 /// Stub API for OpenCV - works without the real OpenCV library
 /// Provides the same interface for testing and scripting
 pub mod opencv {
+    use once_cell::sync::Lazy;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Frames read so far per camera ID
+    static CAMERA_FRAMES: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+    /// Frames written so far per open video writer ID
+    static WRITER_FRAMES: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+    static NEXT_WRITER: AtomicUsize = AtomicUsize::new(1);
+
+    /// Color conversion names understood by `convert_color`
+    pub const COLOR_CONVERSIONS: &[&str] = &[
+        "BGR2GRAY", "RGB2GRAY", "GRAY2BGR", "BGR2RGB", "RGB2BGR",
+        "BGR2HSV", "HSV2BGR", "BGR2LAB", "LAB2BGR", "BGR2YCRCB", "YCRCB2BGR",
+    ];
 
     /// Image handle in stub mode
     pub struct Image {
@@ -261,6 +420,15 @@ pub mod opencv {
         format!("[OpenCV] Gaussian blur applied (kernel {}x{}): {}", kernel_size, kernel_size, blurred_id)
     }
 
+    /// Convert an image between color spaces, returning the new image ID
+    pub fn convert_color(image_id: &str, code: &str) -> anyhow::Result<String> {
+        let upper = code.to_uppercase();
+        if !COLOR_CONVERSIONS.contains(&upper.as_str()) {
+            return Err(anyhow::anyhow!("Unknown color conversion: {}", code));
+        }
+        Ok(format!("{}-{}", image_id, code.to_lowercase()))
+    }
+
     /// Draw a rectangle on an image
     pub fn draw_rectangle(image_id: &str, x1: i32, y1: i32, x2: i32, y2: i32,
                          color_r: i32, color_g: i32, color_b: i32, thickness: i32) -> String {
@@ -294,6 +462,50 @@ pub mod opencv {
         "[OpenCV] Camera started".to_string()
     }
 
+    /// Read a frame from a camera, returning a synthetic frame ID
+    pub fn read_frame(camera_id: &str) -> anyhow::Result<String> {
+        let mut frames = CAMERA_FRAMES.lock().unwrap();
+        let count = frames.entry(camera_id.to_string()).or_insert(0);
+        *count += 1;
+        Ok(format!("{}-frame-{}", camera_id, count))
+    }
+
+    /// Release a camera
+    pub fn release_camera(camera_id: &str) -> anyhow::Result<String> {
+        CAMERA_FRAMES.lock().unwrap().remove(camera_id);
+        Ok(format!("[OpenCV] Camera {} released", camera_id))
+    }
+
+    /// Open a video writer, returning its handle ID
+    pub fn open_writer(path: &str, fps: f64, width: i32, height: i32) -> anyhow::Result<String> {
+        if fps <= 0.0 || width <= 0 || height <= 0 {
+            return Err(anyhow::anyhow!("Invalid video writer settings for {}", path));
+        }
+        let writer_id = format!("writer-{}", NEXT_WRITER.fetch_add(1, Ordering::SeqCst));
+        WRITER_FRAMES.lock().unwrap().insert(writer_id.clone(), 0);
+        Ok(writer_id)
+    }
+
+    /// Record a frame written to an open video writer
+    pub fn write_frame(writer_id: &str, image_id: &str) -> anyhow::Result<String> {
+        let mut writers = WRITER_FRAMES.lock().unwrap();
+        let count = writers
+            .get_mut(writer_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown video writer: {}", writer_id))?;
+        *count += 1;
+        Ok(format!("[OpenCV] Frame {} written to {} (frame {})", image_id, writer_id, count))
+    }
+
+    /// Close a video writer, reporting how many frames it received
+    pub fn close_writer(writer_id: &str) -> anyhow::Result<String> {
+        let count = WRITER_FRAMES
+            .lock()
+            .unwrap()
+            .remove(writer_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown video writer: {}", writer_id))?;
+        Ok(format!("[OpenCV] Video writer {} closed ({} frames)", writer_id, count))
+    }
+
     /// Detect faces in an image
     pub fn detect_faces(_image_id: &str, _cascade_path: &str) -> Vec<(i32, i32, i32, i32)> {
         vec![(100, 100, 150, 150), (400, 50, 150, 150)] // Stub: return sample faces
@@ -341,3 +553,37 @@ pub mod opencv {
         format!("[OpenCV] Image {} saved to {}", image_id, output_path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::opencv;
+
+    #[test]
+    fn test_convert_color_known_code() {
+        let id = opencv::convert_color("img-photo", "BGR2GRAY").unwrap();
+        assert_eq!(id, "img-photo-bgr2gray");
+    }
+
+    #[test]
+    fn test_convert_color_unknown_code() {
+        assert!(opencv::convert_color("img-photo", "BGR2NOPE").is_err());
+    }
+
+    #[test]
+    fn test_read_frame_and_release() {
+        assert_eq!(opencv::read_frame("camera-test").unwrap(), "camera-test-frame-1");
+        assert_eq!(opencv::read_frame("camera-test").unwrap(), "camera-test-frame-2");
+        assert!(opencv::release_camera("camera-test").is_ok());
+    }
+
+    #[test]
+    fn test_video_writer_counts_frames() {
+        let writer = opencv::open_writer("out.avi", 30.0, 640, 480).unwrap();
+        for i in 0..3 {
+            opencv::write_frame(&writer, &format!("frame-{}", i)).unwrap();
+        }
+        let msg = opencv::close_writer(&writer).unwrap();
+        assert!(msg.ends_with("(3 frames)"));
+        assert!(opencv::write_frame(&writer, "frame-4").is_err());
+    }
+}
@@ -4,7 +4,7 @@
 //! such as file/directory operations, environment variables, and process control.
 
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::env;
 
 /// List files and directories in a directory
@@ -87,3 +87,207 @@ pub fn get_username() -> Option<String> {
     env::var("USER").ok()
         .or_else(|| env::var("USERNAME").ok())
 }
+
+/// Check that `path` is a file the current user could execute
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0).unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+/// Find an executable by name on `PATH`, returning its full path.
+/// 
+/// Names containing a path separator are checked as given. On Windows each
+/// extension in `PATHEXT` (e.g. `.EXE`) is also tried.
+pub fn which(name: &str) -> Option<String> {
+    let mut exts = vec![String::new()];
+    if cfg!(windows) {
+        let pathext = env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+        exts.extend(pathext.split(';').filter(|e| !e.is_empty()).map(|e| e.to_string()));
+    }
+    let candidates = |base: PathBuf| exts.iter().map(move |ext| {
+        let mut p = base.clone().into_os_string();
+        p.push(ext);
+        PathBuf::from(p)
+    });
+    if name.contains(['/', '\\']) {
+        return candidates(PathBuf::from(name)).find(|p| is_executable(p)).map(|p| p.to_string_lossy().into_owned());
+    }
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .flat_map(|dir| candidates(dir.join(name)))
+        .find(|p| is_executable(p))
+        .map(|p| p.to_string_lossy().into_owned())
+}
+
+/// Every file under `root`, recursively, as sorted paths. Symlinked
+/// directories are followed, but each real directory is visited only once, so
+/// symlink loops terminate.
+pub fn walk(root: &str) -> Result<Vec<String>, std::io::Error> {
+    fn visit(dir: &Path, seen: &mut std::collections::HashSet<PathBuf>, files: &mut Vec<String>) -> Result<(), std::io::Error> {
+        if !seen.insert(fs::canonicalize(dir)?) {
+            return Ok(());
+        }
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            // fs::metadata follows symlinks; broken links are skipped
+            match fs::metadata(&path) {
+                Ok(meta) if meta.is_dir() => visit(&path, seen, files)?,
+                Ok(_) => files.push(path.to_string_lossy().into_owned()),
+                Err(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    visit(Path::new(root), &mut std::collections::HashSet::new(), &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Unique path for a scratch file in the system temp directory. With
+/// `create` an empty file is made there; otherwise nothing exists at the path
+/// yet. The file is not removed automatically, since scripts may use it after
+/// the call; delete it with `remove_file` when done.
+pub fn tempfile(create: bool) -> Result<String, std::io::Error> {
+    let file = tempfile::Builder::new().prefix("ferrum-").tempfile()?;
+    let path = file.path().to_string_lossy().into_owned();
+    if create {
+        file.keep().map_err(|e| e.error)?;
+    }
+    // otherwise dropping `file` removes it again, leaving a free unique name
+    Ok(path)
+}
+
+/// Create a fresh, empty directory in the system temp directory and return
+/// its path. Like [`tempfile`] it is left in place for the script to remove.
+pub fn tempdir() -> Result<String, std::io::Error> {
+    let dir = tempfile::Builder::new().prefix("ferrum-").tempdir()?;
+    Ok(dir.keep().to_string_lossy().into_owned())
+}
+
+/// Join path components with the platform separator. As with
+/// `os.path.join`, an absolute component discards everything before it.
+pub fn path_join(parts: &[String]) -> String {
+    let mut path = PathBuf::new();
+    for part in parts {
+        path.push(part);
+    }
+    path.to_string_lossy().into_owned()
+}
+
+/// Final component of `path`, or "" when there is none (e.g. `/` or `..`)
+pub fn path_basename(path: &str) -> String {
+    Path::new(path).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Everything before the final component, or "" for a bare file name
+pub fn path_dirname(path: &str) -> String {
+    Path::new(path).parent().map(|p| p.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Split off the extension: `dir/archive.tar.gz` gives `dir/archive.tar`
+/// and `.gz`. Dotfiles such as `.bashrc` have no extension.
+pub fn path_splitext(path: &str) -> (String, String) {
+    let name_start = path.rfind(std::path::is_separator).map(|i| i + 1).unwrap_or(0);
+    let name = &path[name_start..];
+    // leading dots belong to the name, not an extension
+    let dots = name.len() - name.trim_start_matches('.').len();
+    match name[dots..].rfind('.') {
+        Some(i) => {
+            let split = name_start + dots + i;
+            (path[..split].to_string(), path[split..].to_string())
+        }
+        None => (path.to_string(), String::new()),
+    }
+}
+
+/// Whether anything (file, directory, or a symlink's target) exists at `path`
+pub fn path_exists(path: &str) -> bool {
+    Path::new(path).exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tempfile_paths_are_distinct() {
+        let a = tempfile(true).unwrap();
+        let b = tempfile(true).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(fs::metadata(&a).unwrap().len(), 0);
+        assert_eq!(fs::metadata(&b).unwrap().len(), 0);
+        fs::remove_file(&a).unwrap();
+        fs::remove_file(&b).unwrap();
+
+        let c = tempfile(false).unwrap();
+        assert!(!Path::new(&c).exists());
+    }
+
+    #[test]
+    fn test_walk_lists_nested_files() {
+        let root = tempdir().unwrap();
+        let root_path = Path::new(&root);
+        makedirs(root_path.join("a/b").to_str().unwrap()).unwrap();
+        for f in ["top.txt", "a/mid.txt", "a/b/deep.txt"] {
+            fs::write(root_path.join(f), "x").unwrap();
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root_path.join("a/loop")).unwrap();
+
+        let mut expected: Vec<String> = ["a/b/deep.txt", "a/mid.txt", "top.txt"]
+            .iter()
+            .map(|f| root_path.join(f).to_string_lossy().into_owned())
+            .collect();
+        expected.sort();
+        assert_eq!(walk(&root).unwrap(), expected);
+        fs::remove_dir_all(&root).unwrap();
+        assert!(walk(&root).is_err());
+    }
+
+    #[test]
+    fn test_tempdir_is_empty_directory() {
+        let dir = tempdir().unwrap();
+        assert!(isdir(&dir));
+        assert_eq!(listdir(&dir).unwrap().len(), 0);
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_which_finds_system_binary() {
+        let sh = which("sh").expect("sh should be on PATH");
+        assert!(sh.ends_with("/sh"));
+        assert_eq!(which("/bin/sh").as_deref(), Some("/bin/sh"));
+    }
+
+    #[test]
+    fn test_which_missing_binary() {
+        assert_eq!(which("definitely-not-a-real-ferrum-binary"), None);
+    }
+
+    #[test]
+    fn test_path_join_and_split() {
+        let parts: Vec<String> = ["data", "2024", "logs", "run.tar.gz"].iter().map(|s| s.to_string()).collect();
+        let joined = path_join(&parts);
+        let sep = std::path::MAIN_SEPARATOR;
+        assert_eq!(joined, format!("data{0}2024{0}logs{0}run.tar.gz", sep));
+        assert_eq!(path_basename(&joined), "run.tar.gz");
+        assert_eq!(path_dirname(&joined), format!("data{0}2024{0}logs", sep));
+        assert_eq!(path_splitext(&joined), (format!("data{0}2024{0}logs{0}run.tar", sep), ".gz".to_string()));
+        assert_eq!(path_join(&[path_dirname(&joined), path_basename(&joined)]), joined);
+
+        assert_eq!(path_splitext(".bashrc"), (".bashrc".to_string(), String::new()));
+        assert_eq!(path_splitext("v1.2/notes"), ("v1.2/notes".to_string(), String::new()));
+        assert_eq!(path_dirname("file.txt"), "");
+        assert_eq!(path_basename("/"), "");
+    }
+}
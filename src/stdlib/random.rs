@@ -1,45 +1,78 @@
 //! Random module - random number generation
 //!
 //! This module provides functions for generating random numbers and making random selections.
+//! Every function draws from the current thread's generator: the OS-seeded
+//! `thread_rng` until [`seed`] installs a deterministic one.
 
-use rand::Rng;
+use std::cell::RefCell;
 
-/// Generate a random integer between min (inclusive) and max (exclusive)
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+thread_local! {
+    static SEEDED: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+/// Make this thread's random sequence reproducible: the same seed always
+/// yields the same values afterwards
+pub fn seed(n: u64) {
+    SEEDED.with(|rng| *rng.borrow_mut() = Some(StdRng::seed_from_u64(n)));
+}
+
+/// Run `f` with the seeded generator if there is one, else `thread_rng`
+fn with_rng<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    SEEDED.with(|seeded| match seeded.borrow_mut().as_mut() {
+        Some(rng) => f(rng),
+        None => f(&mut rand::thread_rng()),
+    })
+}
+
+/// Generate a random integer between min and max, both inclusive
 pub fn randint(min: i64, max: i64) -> i64 {
-    let mut rng = rand::thread_rng();
-    rng.gen_range(min..max)
+    with_rng(|rng| rng.gen_range(min..=max))
 }
 
 /// Generate a random float between 0.0 and 1.0
 pub fn random() -> f64 {
-    let mut rng = rand::thread_rng();
-    rng.gen::<f64>()
+    with_rng(|rng| rng.gen::<f64>())
 }
 
 /// Generate a random float between min and max
 pub fn uniform(min: f64, max: f64) -> f64 {
-    let mut rng = rand::thread_rng();
-    rng.gen_range(min..max)
+    with_rng(|rng| rng.gen_range(min..max))
 }
 
 /// Randomly shuffle a vector
 pub fn shuffle<T: Clone>(items: &[T]) -> Vec<T> {
     use rand::seq::SliceRandom;
-    let mut rng = rand::thread_rng();
     let mut shuffled = items.to_vec();
-    shuffled.shuffle(&mut rng);
+    with_rng(|mut rng| shuffled.shuffle(&mut rng));
     shuffled
 }
 
 /// Pick a random element from a list
 pub fn choice<T: Clone>(items: &[T]) -> Option<T> {
     use rand::seq::SliceRandom;
-    let mut rng = rand::thread_rng();
-    items.choose(&mut rng).cloned()
+    with_rng(|mut rng| items.choose(&mut rng).cloned())
 }
 
 /// Generate random boolean
 pub fn random_bool() -> bool {
-    let mut rng = rand::thread_rng();
-    rng.gen_bool(0.5)
+    with_rng(|rng| rng.gen_bool(0.5))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_makes_sequence_reproducible() {
+        seed(7);
+        let first: Vec<i64> = (0..5).map(|_| randint(0, 999)).collect();
+        let picked = choice(&[1, 2, 3, 4, 5]);
+        seed(7);
+        let second: Vec<i64> = (0..5).map(|_| randint(0, 999)).collect();
+        assert_eq!(first, second);
+        assert_eq!(choice(&[1, 2, 3, 4, 5]), picked);
+    }
 }
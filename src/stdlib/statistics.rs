@@ -0,0 +1,86 @@
+//! Statistics module - summary statistics over numeric lists
+//!
+//! `variance` and `stdev` are sample statistics (divided by n - 1), so they
+//! need at least two values; every function errors on an empty list.
+
+use anyhow::{anyhow, Result};
+
+use crate::runtime::Value;
+
+/// Collect a list of ints/floats as f64s for the builtin `name`
+pub fn numbers(name: &str, value: Option<&Value>) -> Result<Vec<f64>> {
+    let items = match value {
+        Some(Value::List(items)) => items,
+        _ => return Err(anyhow!("{}() requires a list of numbers", name)),
+    };
+    let xs = items
+        .iter()
+        .map(|v| match v {
+            Value::Int(i) => Ok(*i as f64),
+            Value::Float(f) => Ok(*f),
+            other => Err(anyhow!("{}() requires numbers, got {}", name, other.to_string())),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if xs.is_empty() {
+        return Err(anyhow!("{}() of an empty list", name));
+    }
+    Ok(xs)
+}
+
+/// Arithmetic mean
+pub fn mean(xs: &[f64]) -> f64 {
+    xs.iter().sum::<f64>() / xs.len() as f64
+}
+
+/// Middle value, or the mean of the two middle values for even lengths
+pub fn median(xs: &[f64]) -> f64 {
+    let mut sorted = xs.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Sample variance
+pub fn variance(xs: &[f64]) -> Result<f64> {
+    if xs.len() < 2 {
+        return Err(anyhow!("variance requires at least two values"));
+    }
+    let m = mean(xs);
+    Ok(xs.iter().map(|x| (x - m).powi(2)).sum::<f64>() / (xs.len() - 1) as f64)
+}
+
+/// Sample standard deviation
+pub fn stdev(xs: &[f64]) -> Result<f64> {
+    variance(xs).map(f64::sqrt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mean_and_median() {
+        assert_eq!(mean(&[1.0, 2.0, 3.0, 4.0]), 2.5);
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+    }
+
+    #[test]
+    fn test_variance_and_stdev() {
+        let xs = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        assert!((variance(&xs).unwrap() - 32.0 / 7.0).abs() < 1e-12);
+        assert!((stdev(&xs).unwrap() - (32.0f64 / 7.0).sqrt()).abs() < 1e-12);
+        assert!(stdev(&[1.0]).is_err());
+    }
+
+    #[test]
+    fn test_numbers_rejects_empty_and_non_numeric() {
+        assert!(numbers("mean", Some(&Value::List(Vec::new()))).is_err());
+        assert!(numbers("mean", Some(&Value::List(vec![Value::Str("a".to_string())]))).is_err());
+        assert_eq!(numbers("mean", Some(&Value::List(vec![Value::Int(1), Value::Float(0.5)]))).unwrap(), vec![1.0, 0.5]);
+    }
+}
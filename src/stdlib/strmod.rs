@@ -0,0 +1,278 @@
+//! String module - methods callable on string and bytes values
+//!
+//! `eval_expr` routes `s.method(args)` here whenever the receiver evaluates
+//! to a string or bytes.
+
+use anyhow::{anyhow, Result};
+
+use crate::runtime::Value;
+
+/// Call the string method `name` on `s` with the evaluated `args`
+pub fn call_method(s: &str, name: &str, args: Vec<Value>) -> Result<Value> {
+    match name {
+        "encode" => Ok(Value::Bytes(encode(s, encoding_arg(&args)?)?)),
+        "splitlines" => Ok(Value::List(splitlines(s).into_iter().map(Value::Str).collect())),
+        "upper" => Ok(Value::Str(s.to_uppercase())),
+        "lower" => Ok(Value::Str(s.to_lowercase())),
+        "strip" => Ok(Value::Str(s.trim().to_string())),
+        "split" => {
+            // without a separator, split on runs of whitespace like Python
+            let parts: Vec<&str> = match args.first() {
+                None => s.split_whitespace().collect(),
+                Some(Value::Str(sep)) if !sep.is_empty() => s.split(sep.as_str()).collect(),
+                Some(_) => return Err(anyhow!("split() separator must be a non-empty string")),
+            };
+            Ok(Value::List(parts.into_iter().map(|p| Value::Str(p.to_string())).collect()))
+        }
+        "replace" => Ok(Value::Str(s.replace(str_arg(name, &args, 0)?, str_arg(name, &args, 1)?))),
+        "startswith" => Ok(Value::Bool(s.starts_with(str_arg(name, &args, 0)?))),
+        "endswith" => Ok(Value::Bool(s.ends_with(str_arg(name, &args, 0)?))),
+        "find" => Ok(Value::Int(find(s, str_arg(name, &args, 0)?))),
+        "contains" => Ok(Value::Bool(s.contains(str_arg(name, &args, 0)?))),
+        "count" => Ok(Value::Int(count(s, str_arg(name, &args, 0)?) as i64)),
+        "zfill" => Ok(Value::Str(zfill(s, width_arg(name, &args)?))),
+        "ljust" | "rjust" | "center" => {
+            let fill = match args.get(1) {
+                None => ' ',
+                Some(Value::Str(f)) if f.chars().count() == 1 => f.chars().next().unwrap_or(' '),
+                Some(_) => return Err(anyhow!("{}() fill must be a single character", name)),
+            };
+            let align = match name {
+                "ljust" => Align::Left,
+                "rjust" => Align::Right,
+                _ => Align::Center,
+            };
+            Ok(Value::Str(pad(s, width_arg(name, &args)?, fill, align)))
+        }
+        _ => Err(anyhow!("Unknown string method: {}", name)),
+    }
+}
+
+/// Call the bytes method `name` on `b` with the evaluated `args`
+pub fn call_bytes_method(b: &[u8], name: &str, args: Vec<Value>) -> Result<Value> {
+    match name {
+        "decode" => Ok(Value::Str(decode(b, encoding_arg(&args)?)?)),
+        _ => Err(anyhow!("Unknown bytes method: {}", name)),
+    }
+}
+
+/// The optional encoding argument of `encode`/`decode`, defaulting to UTF-8
+fn encoding_arg(args: &[Value]) -> Result<&str> {
+    match args.first() {
+        None => Ok("utf-8"),
+        Some(Value::Str(e)) => Ok(e),
+        Some(_) => Err(anyhow!("encoding must be a string")),
+    }
+}
+
+/// Encode text as bytes. Supports "utf-8" and "ascii"; ASCII encoding
+/// fails on characters outside the ASCII range.
+pub fn encode(s: &str, encoding: &str) -> Result<Vec<u8>> {
+    match encoding.to_lowercase().as_str() {
+        "utf-8" | "utf8" => Ok(s.as_bytes().to_vec()),
+        "ascii" => match s.chars().position(|c| !c.is_ascii()) {
+            Some(pos) => Err(anyhow!("'ascii' codec can't encode character at position {}", pos)),
+            None => Ok(s.as_bytes().to_vec()),
+        },
+        other => Err(anyhow!("Unknown encoding: {}", other)),
+    }
+}
+
+/// Decode bytes as text, failing on invalid input for the encoding
+pub fn decode(b: &[u8], encoding: &str) -> Result<String> {
+    match encoding.to_lowercase().as_str() {
+        "utf-8" | "utf8" => String::from_utf8(b.to_vec())
+            .map_err(|e| anyhow!("'utf-8' codec can't decode byte at position {}", e.utf8_error().valid_up_to())),
+        "ascii" => match b.iter().position(|c| !c.is_ascii()) {
+            Some(pos) => Err(anyhow!("'ascii' codec can't decode byte at position {}", pos)),
+            None => Ok(String::from_utf8_lossy(b).into_owned()),
+        },
+        other => Err(anyhow!("Unknown encoding: {}", other)),
+    }
+}
+
+/// Character index of the first occurrence of `sub` in `s`, or -1
+pub fn find(s: &str, sub: &str) -> i64 {
+    match s.find(sub) {
+        Some(byte) => s[..byte].chars().count() as i64,
+        None => -1,
+    }
+}
+
+fn str_arg<'a>(method: &str, args: &'a [Value], i: usize) -> Result<&'a str> {
+    match args.get(i) {
+        Some(Value::Str(s)) => Ok(s),
+        _ => Err(anyhow!("{}() expects a string argument", method)),
+    }
+}
+
+fn width_arg(method: &str, args: &[Value]) -> Result<usize> {
+    match args.first() {
+        Some(Value::Int(w)) => Ok((*w).max(0) as usize),
+        _ => Err(anyhow!("{}() expects an integer width", method)),
+    }
+}
+
+/// Where the text sits within the padded width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// Pad `s` with `fill` to `width` characters. Strings already at least
+/// `width` long are returned unchanged. Centering splits odd padding the
+/// way Python's `str.center` does, e.g. `"ab"` centered to 5 is `"  ab "`.
+pub fn pad(s: &str, width: usize, fill: char, align: Align) -> String {
+    let len = s.chars().count();
+    if len >= width {
+        return s.to_string();
+    }
+    let total = width - len;
+    let (left, right) = match align {
+        Align::Left => (0, total),
+        Align::Right => (total, 0),
+        Align::Center => {
+            // CPython's rule: the extra character goes left when `width` is odd
+            let left = total / 2 + (total & width & 1);
+            (left, total - left)
+        }
+    };
+    let fill = fill.to_string();
+    format!("{}{}{}", fill.repeat(left), s, fill.repeat(right))
+}
+
+/// Left-pad with zeros to `width`, keeping a leading sign in front
+pub fn zfill(s: &str, width: usize) -> String {
+    match s.strip_prefix(['-', '+']) {
+        Some(digits) => format!("{}{}", &s[..1], pad(digits, width.saturating_sub(1), '0', Align::Right)),
+        None => pad(s, width, '0', Align::Right),
+    }
+}
+
+/// Number of non-overlapping occurrences of `sub` in `s`. An empty `sub`
+/// matches between every character, as in Python.
+pub fn count(s: &str, sub: &str) -> usize {
+    if sub.is_empty() {
+        return s.chars().count() + 1;
+    }
+    s.matches(sub).count()
+}
+
+/// Number of words: runs of non-whitespace separated by any Unicode whitespace
+pub fn word_count(s: &str) -> usize {
+    s.split_whitespace().count()
+}
+
+/// Number of characters (Unicode scalar values, not bytes)
+pub fn char_count(s: &str) -> usize {
+    s.chars().count()
+}
+
+/// Number of lines as split by [`splitlines`], so a trailing newline does
+/// not add an empty line
+pub fn line_count(s: &str) -> usize {
+    splitlines(s).len()
+}
+
+/// Split on `\n`, `\r\n` and `\r` line endings. A trailing line ending does
+/// not produce an empty final line, matching Python's `str.splitlines`.
+pub fn splitlines(s: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => lines.push(std::mem::take(&mut current)),
+            '\r' => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                lines.push(std::mem::take(&mut current));
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splitlines() {
+        assert_eq!(splitlines("a\nb\n"), vec!["a", "b"]);
+        assert_eq!(splitlines("a\r\nb\rc"), vec!["a", "b", "c"]);
+        assert_eq!(splitlines("a\n\nb"), vec!["a", "", "b"]);
+        assert!(splitlines("").is_empty());
+    }
+
+    #[test]
+    fn test_contains_and_count() {
+        assert!(matches!(call_method("banana", "count", vec![Value::Str("a".into())]).unwrap(), Value::Int(3)));
+        assert!(matches!(call_method("aaaa", "count", vec![Value::Str("aa".into())]).unwrap(), Value::Int(2)));
+        assert!(matches!(call_method("banana", "contains", vec![Value::Str("nan".into())]).unwrap(), Value::Bool(true)));
+        assert!(matches!(call_method("banana", "contains", vec![Value::Str("x".into())]).unwrap(), Value::Bool(false)));
+        assert!(call_method("banana", "count", vec![Value::Int(1)]).is_err());
+    }
+
+    #[test]
+    fn test_case_strip_and_affix_methods() {
+        let call = |s: &str, name: &str, args: &[&str]| call_method(s, name, args.iter().map(|a| Value::Str(a.to_string())).collect()).unwrap();
+        assert_eq!(call("Ferrum", "upper", &[]), Value::Str("FERRUM".into()));
+        assert_eq!(call("Ferrum", "lower", &[]), Value::Str("ferrum".into()));
+        assert_eq!(call("  pad\t\n", "strip", &[]), Value::Str("pad".into()));
+        assert_eq!(call("a-b-a", "replace", &["a", "x"]), Value::Str("x-b-x".into()));
+        assert_eq!(call("main.fm", "startswith", &["main"]), Value::Bool(true));
+        assert_eq!(call("main.fm", "endswith", &[".rs"]), Value::Bool(false));
+        assert!(call_method("x", "replace", vec![Value::Str("x".into())]).is_err());
+    }
+
+    #[test]
+    fn test_split_and_find() {
+        let strs = |xs: &[&str]| Value::List(xs.iter().map(|x| Value::Str(x.to_string())).collect());
+        assert_eq!(call_method("a,b,,c", "split", vec![Value::Str(",".into())]).unwrap(), strs(&["a", "b", "", "c"]));
+        assert_eq!(call_method("  one two\tthree ", "split", vec![]).unwrap(), strs(&["one", "two", "three"]));
+        assert!(call_method("abc", "split", vec![Value::Str(String::new())]).is_err());
+        assert_eq!(find("héllo", "llo"), 2);
+        assert_eq!(find("hello", "z"), -1);
+    }
+
+    #[test]
+    fn test_text_counts() {
+        let text = "The  quick\tbrown fox\u{00a0}jumps\r\nover the\n\nlazy dög\n";
+        assert_eq!(word_count(text), 9);
+        assert_eq!(line_count(text), 4);
+        assert_eq!(char_count(text), 47);
+        assert_eq!((word_count(""), line_count(""), char_count("")), (0, 0, 0));
+        assert_eq!(line_count("no newline"), 1);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let bytes = encode("héllo", "utf-8").unwrap();
+        assert_eq!(bytes.len(), 6);
+        assert_eq!(decode(&bytes, "utf-8").unwrap(), "héllo");
+        assert!(decode(&[0x66, 0xff, 0x6f], "utf-8").is_err());
+        assert!(encode("héllo", "ascii").is_err());
+    }
+
+    #[test]
+    fn test_padding() {
+        assert_eq!(zfill("42", 5), "00042");
+        assert_eq!(zfill("-42", 5), "-0042");
+        assert_eq!(pad("ab", 5, ' ', Align::Left), "ab   ");
+        assert_eq!(pad("ab", 5, ' ', Align::Right), "   ab");
+        assert_eq!(pad("ab", 5, '*', Align::Center), "**ab*");
+        assert_eq!(pad("abc", 6, '*', Align::Center), "*abc**");
+        assert_eq!(pad("ab", 6, '*', Align::Center), "**ab**");
+        assert!(matches!(call_method("ab", "center", vec![Value::Int(5)]).unwrap(), Value::Str(s) if s == "  ab "));
+        assert_eq!(pad("abcdef", 3, ' ', Align::Center), "abcdef");
+        assert!(matches!(call_method("x", "rjust", vec![Value::Int(3), Value::Str(".".into())]).unwrap(), Value::Str(s) if s == "..x"));
+    }
+}
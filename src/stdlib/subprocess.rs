@@ -3,6 +3,8 @@
 /// Provides process execution capabilities:
 /// - subprocess.run(cmd) - Execute command and wait for completion
 /// - subprocess.popen(cmd) - Spawn process with live I/O
+/// - subprocess.pipe([cmd1, cmd2, ...]) - Chain commands like a shell pipeline
+/// - kill_pid(pid[, signal]) - Terminate a process by pid
 ///
 /// All functions return clean Ferrum-native objects (no panics, no hangs)
 
@@ -29,30 +31,181 @@ impl ProcessResult {
     }
 }
 
+/// Build the platform shell invocation for `cmd`
+fn shell_command(cmd: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", cmd]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(cmd);
+        command
+    }
+}
+
 /// Run a command synchronously and return stdout, stderr, exit_code
 pub fn run_command(cmd: &str) -> Result<ProcessResult> {
-    let output = if cfg!(target_os = "windows") {
-        Command::new("cmd")
-            .args(["/C", cmd])
-            .output()?
-    } else {
-        Command::new("sh")
-            .arg("-c")
-            .arg(cmd)
-            .output()?
-    };
+    run_command_with(cmd, false)
+}
+
+/// Run a command synchronously; with `merge_stderr` both streams share one pipe,
+/// so `stdout` holds the interleaved output and `stderr` is empty
+pub fn run_command_with(cmd: &str, merge_stderr: bool) -> Result<ProcessResult> {
+    if !merge_stderr {
+        let output = shell_command(cmd).output()?;
+        return Ok(ProcessResult {
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: output.status.code().unwrap_or(-1),
+        });
+    }
+
+    let (mut reader, writer) = std::io::pipe()?;
+    let mut command = shell_command(cmd);
+    command.stdin(Stdio::null()).stdout(writer.try_clone()?).stderr(writer);
+    let mut child = command.spawn()?;
+    // the Command still owns the write ends; drop it so the read sees EOF
+    drop(command);
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-    let exit_code = output.status.code().unwrap_or(-1);
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    let status = child.wait()?;
 
     Ok(ProcessResult {
-        stdout,
-        stderr,
-        exit_code,
+        stdout: String::from_utf8_lossy(&buf).to_string(),
+        stderr: String::new(),
+        exit_code: status.code().unwrap_or(-1),
     })
 }
 
+/// Run `cmds` as a pipeline, each stage's stdout feeding the next stage's stdin,
+/// and return the last stage's stdout. The first stage that exits non-zero is
+/// reported along with its stderr.
+pub fn run_pipeline(cmds: &[String]) -> Result<String> {
+    if cmds.is_empty() {
+        return Err(anyhow::anyhow!("subprocess.pipe() needs at least one command"));
+    }
+    let mut children: Vec<Child> = Vec::with_capacity(cmds.len());
+    // stderr is drained on its own thread per stage, so a chatty stage
+    // can't fill the pipe and stall the whole pipeline
+    let mut stderrs = Vec::with_capacity(cmds.len());
+    for (i, cmd) in cmds.iter().enumerate() {
+        let stdin = match children.last_mut().and_then(|prev| prev.stdout.take()) {
+            Some(out) => Stdio::from(out),
+            None => Stdio::null(),
+        };
+        let spawned = shell_command(cmd)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                // don't leave the stages already running behind as zombies
+                for mut started in children {
+                    let _ = started.kill();
+                    let _ = started.wait();
+                }
+                return Err(anyhow::anyhow!("pipe stage {} (`{}`) failed to start: {}", i + 1, cmd, e));
+            }
+        };
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        stderrs.push(std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        }));
+        children.push(child);
+    }
+
+    // Drain the last stage first so upstream stages never block on a full pipe
+    let last = children.pop().expect("at least one stage");
+    let last_output = last.wait_with_output()?;
+    let mut statuses = Vec::with_capacity(cmds.len());
+    for mut child in children {
+        statuses.push(child.wait()?);
+    }
+    statuses.push(last_output.status);
+
+    for (i, (status, stderr)) in statuses.iter().zip(stderrs).enumerate() {
+        let stderr = stderr.join().unwrap_or_default();
+        if !status.success() && !killed_by_sigpipe(status) {
+            return Err(anyhow::anyhow!(
+                "pipe stage {} (`{}`) exited with code {}: {}",
+                i + 1,
+                cmds[i],
+                status.code().unwrap_or(-1),
+                String::from_utf8_lossy(&stderr).trim_end()
+            ));
+        }
+    }
+    Ok(String::from_utf8_lossy(&last_output.stdout).to_string())
+}
+
+/// An upstream stage dying of SIGPIPE just means a later stage stopped reading,
+/// which shells don't treat as a failure either.
+#[cfg(unix)]
+fn killed_by_sigpipe(status: &std::process::ExitStatus) -> bool {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal() == Some(13)
+}
+
+#[cfg(not(unix))]
+fn killed_by_sigpipe(_status: &std::process::ExitStatus) -> bool {
+    false
+}
+
+/// Send `signal` (default SIGTERM) to the process `pid`
+#[cfg(unix)]
+pub fn kill_process(pid: i64, signal: Option<&Value>) -> Result<()> {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+    use std::str::FromStr;
+
+    let signal = match signal {
+        None => Signal::SIGTERM,
+        Some(Value::Int(n)) => Signal::try_from(*n as i32).map_err(|_| anyhow::anyhow!("Unknown signal: {}", n))?,
+        Some(Value::Str(name)) => {
+            let name = name.to_uppercase();
+            let name = if name.starts_with("SIG") { name } else { format!("SIG{}", name) };
+            Signal::from_str(&name).map_err(|_| anyhow::anyhow!("Unknown signal: {}", name))?
+        }
+        Some(other) => return Err(anyhow::anyhow!("kill_pid() signal must be an int or name, got {}", other.to_string())),
+    };
+    let raw = i32::try_from(pid).ok().filter(|p| *p > 0).ok_or_else(|| anyhow::anyhow!("Invalid pid: {}", pid))?;
+    kill(Pid::from_raw(raw), signal).map_err(|e| match e {
+        nix::errno::Errno::ESRCH => anyhow::anyhow!("No such process: {}", pid),
+        e => anyhow::anyhow!("Failed to kill process {}: {}", pid, e),
+    })
+}
+
+/// Terminate the process `pid`; Windows has no signals, so `signal` must be omitted
+#[cfg(windows)]
+pub fn kill_process(pid: i64, signal: Option<&Value>) -> Result<()> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+
+    if signal.is_some() {
+        return Err(anyhow::anyhow!("kill_pid() signals are not supported on Windows"));
+    }
+    let raw = u32::try_from(pid).map_err(|_| anyhow::anyhow!("Invalid pid: {}", pid))?;
+    // SAFETY: the handle is checked for null and closed before returning
+    unsafe {
+        let handle = OpenProcess(PROCESS_TERMINATE, 0, raw);
+        if handle.is_null() {
+            return Err(anyhow::anyhow!("No such process: {}", pid));
+        }
+        let ok = TerminateProcess(handle, 1);
+        CloseHandle(handle);
+        if ok == 0 {
+            return Err(anyhow::anyhow!("Failed to kill process {}: {}", pid, std::io::Error::last_os_error()));
+        }
+    }
+    Ok(())
+}
+
 /// Spawn a process for live interaction
 pub struct Process {
     child: Option<Child>,
@@ -62,22 +215,11 @@ pub struct Process {
 impl Process {
     /// Spawn a new process
     pub fn spawn(cmd: &str) -> Result<Self> {
-        let child = if cfg!(target_os = "windows") {
-            Command::new("cmd")
-                .args(["/C", cmd])
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()?
-        } else {
-            Command::new("sh")
-                .arg("-c")
-                .arg(cmd)
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()?
-        };
+        let child = shell_command(cmd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
 
         let pid = child.id();
         Ok(Process {
@@ -140,9 +282,16 @@ impl Process {
 }
 
 /// Ferrum-callable wrapper for subprocess.run()
+///
+/// An optional options dict accepts `merge_stderr` to fold stderr into stdout.
 pub fn subprocess_run(args: Vec<Value>) -> anyhow::Result<Value> {
     if let Some(Value::Str(cmd)) = args.get(0) {
-        match run_command(cmd) {
+        let merge_stderr = match args.get(1) {
+            None => false,
+            Some(Value::Dict(opts)) => opts.get("merge_stderr").is_some_and(|v| v.is_truthy()),
+            Some(_) => return Err(anyhow::anyhow!("subprocess.run() options must be a dict")),
+        };
+        match run_command_with(cmd, merge_stderr) {
             Ok(result) => Ok(result.to_dict()),
             Err(e) => {
                 let mut map = std::collections::HashMap::new();
@@ -157,6 +306,34 @@ pub fn subprocess_run(args: Vec<Value>) -> anyhow::Result<Value> {
     }
 }
 
+/// Ferrum-callable wrapper for subprocess.pipe()
+pub fn subprocess_pipe(args: Vec<Value>) -> anyhow::Result<Value> {
+    match args.first() {
+        Some(Value::List(items)) => {
+            let cmds = items
+                .iter()
+                .map(|v| match v {
+                    Value::Str(s) => Ok(s.clone()),
+                    other => Err(anyhow::anyhow!("subprocess.pipe() commands must be strings, got {}", other.to_string())),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Value::Str(run_pipeline(&cmds)?))
+        }
+        _ => Err(anyhow::anyhow!("subprocess.pipe() requires a list of commands")),
+    }
+}
+
+/// Ferrum-callable `kill_pid(pid[, signal])`
+pub fn kill_pid(args: Vec<Value>) -> anyhow::Result<Value> {
+    match args.first() {
+        Some(Value::Int(pid)) => {
+            kill_process(*pid, args.get(1))?;
+            Ok(Value::None)
+        }
+        _ => Err(anyhow::anyhow!("kill_pid() requires an integer pid")),
+    }
+}
+
 /// Ferrum-callable wrapper for subprocess.popen()
 pub fn subprocess_popen(args: Vec<Value>) -> anyhow::Result<Value> {
     if let Some(Value::Str(cmd)) = args.get(0) {
@@ -177,3 +354,85 @@ pub fn subprocess_popen(args: Vec<Value>) -> anyhow::Result<Value> {
         Err(anyhow::anyhow!("subprocess.popen() requires a string command"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_merge_stderr_interleaves_streams() {
+        let cmd = "echo out1; echo err1 1>&2; echo out2";
+        let separate = run_command(cmd).unwrap();
+        assert_eq!(separate.stdout, "out1\nout2\n");
+        assert_eq!(separate.stderr, "err1\n");
+
+        let merged = run_command_with(cmd, true).unwrap();
+        assert_eq!(merged.stdout, "out1\nerr1\nout2\n");
+        assert_eq!(merged.stderr, "");
+        assert_eq!(merged.exit_code, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pipe_two_stages() {
+        let cmds = vec![
+            Value::Str("printf 'apple\\nbanana\\ncherry\\n'".to_string()),
+            Value::Str("grep an".to_string()),
+        ];
+        assert!(matches!(subprocess_pipe(vec![Value::List(cmds)]).unwrap(), Value::Str(s) if s == "banana\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pipe_reports_failing_stage() {
+        let cmds = vec!["echo hi".to_string(), "echo broken 1>&2; exit 2".to_string(), "cat".to_string()];
+        let err = run_pipeline(&cmds).unwrap_err().to_string();
+        assert!(err.contains("stage 2"), "{}", err);
+        assert!(err.contains("broken"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pipe_stage_with_heavy_stderr_does_not_deadlock() {
+        // far more stderr than a pipe buffer holds
+        let cmds = vec!["head -c 1000000 /dev/zero 1>&2; echo done".to_string(), "cat".to_string()];
+        assert_eq!(run_pipeline(&cmds).unwrap(), "done\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_kill_pid_terminates_process() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        kill_pid(vec![Value::Int(child.id() as i64)]).unwrap();
+        assert_eq!(child.wait().unwrap().signal(), Some(15));
+
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        kill_pid(vec![Value::Int(child.id() as i64), Value::Str("KILL".to_string())]).unwrap();
+        assert_eq!(child.wait().unwrap().signal(), Some(9));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_kill_pid_missing_process() {
+        let err = kill_pid(vec![Value::Int(i32::MAX as i64)]).unwrap_err();
+        assert!(err.to_string().contains("No such process"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_subprocess_run_accepts_options_dict() {
+        let mut opts = std::collections::HashMap::new();
+        opts.insert("merge_stderr".to_string(), Value::Bool(true));
+        let result = subprocess_run(vec![Value::Str("echo a 1>&2; exit 3".to_string()), Value::Dict(opts)]).unwrap();
+        match result {
+            Value::Dict(map) => {
+                assert!(matches!(map.get("stdout"), Some(Value::Str(s)) if s == "a\n"));
+                assert!(matches!(map.get("exit_code"), Some(Value::Int(3))));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -2,8 +2,23 @@
 //!
 //! This module provides functions to access system information and control program flow.
 
+use once_cell::sync::Lazy;
 use std::env;
 use std::process;
+use std::sync::Mutex;
+
+/// Arguments passed to the running script, after the script path
+static SCRIPT_ARGS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Record the arguments given to the script being run
+pub fn set_script_args(args: Vec<String>) {
+    *SCRIPT_ARGS.lock().unwrap() = args;
+}
+
+/// Arguments given to the script being run (empty in the REPL)
+pub fn script_args() -> Vec<String> {
+    SCRIPT_ARGS.lock().unwrap().clone()
+}
 
 /// Get command-line arguments
 pub fn argv() -> Vec<String> {
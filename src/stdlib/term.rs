@@ -0,0 +1,111 @@
+//! Terminal module - plain-text formatting for CLI output
+
+use anyhow::{anyhow, Result};
+
+use crate::runtime::Value;
+
+/// Render rows as an aligned table:
+///
+/// ```text
+/// name | age | city
+/// -----+-----+-----
+/// Ann  | 31  | Oslo
+/// ```
+///
+/// Rows are lists (cells by position) or dicts (cells by header name).
+/// Missing cells render empty. Without headers, dict rows use their sorted
+/// keys as columns and no header line is printed.
+pub fn format_table(rows: &[Value], headers: Option<&[String]>) -> Result<String> {
+    let columns: Vec<String> = match headers {
+        Some(h) => h.to_vec(),
+        None => {
+            let mut keys: Vec<String> = rows
+                .iter()
+                .filter_map(|r| match r {
+                    Value::Dict(map) => Some(map.keys().cloned()),
+                    _ => None,
+                })
+                .flatten()
+                .collect();
+            keys.sort();
+            keys.dedup();
+            keys
+        }
+    };
+
+    let mut table: Vec<Vec<String>> = Vec::new();
+    for row in rows {
+        let cells = match row {
+            Value::List(items) => items.iter().map(|v| v.to_string()).collect(),
+            Value::Dict(map) => columns
+                .iter()
+                .map(|c| map.get(c).map(|v| v.to_string()).unwrap_or_default())
+                .collect(),
+            other => return Err(anyhow!("format_table() rows must be lists or dicts, got {}", other.to_string())),
+        };
+        table.push(cells);
+    }
+
+    let ncols = table.iter().map(Vec::len).chain(std::iter::once(columns.len())).max().unwrap_or(0);
+    let mut widths = vec![0; ncols];
+    let header_cells = headers.map(|_| &columns);
+    for cells in header_cells.into_iter().chain(table.iter()) {
+        for (i, cell) in cells.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let render = |cells: &[String]| {
+        let padded: Vec<String> = (0..ncols)
+            .map(|i| format!("{:<width$}", cells.get(i).map(String::as_str).unwrap_or(""), width = widths[i]))
+            .collect();
+        padded.join(" | ").trim_end().to_string()
+    };
+
+    let mut lines = Vec::new();
+    if let Some(header) = header_cells {
+        lines.push(render(header));
+        lines.push(widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join("-+-"));
+    }
+    lines.extend(table.iter().map(|cells| render(cells)));
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(text: &str) -> Value {
+        Value::Str(text.to_string())
+    }
+
+    #[test]
+    fn test_format_table_aligns_columns() {
+        let rows = vec![
+            Value::List(vec![s("Ann"), Value::Int(31), s("Oslo")]),
+            Value::List(vec![s("Bartholomew"), Value::Int(7), s("Rome")]),
+        ];
+        let headers = ["name".to_string(), "age".to_string(), "city".to_string()];
+        let out = format_table(&rows, Some(&headers)).unwrap();
+        assert_eq!(
+            out,
+            "name        | age | city\n\
+             ------------+-----+-----\n\
+             Ann         | 31  | Oslo\n\
+             Bartholomew | 7   | Rome"
+        );
+    }
+
+    #[test]
+    fn test_format_table_dict_rows_with_missing_fields() {
+        let mut a = std::collections::HashMap::new();
+        a.insert("x".to_string(), Value::Int(1));
+        a.insert("y".to_string(), Value::Int(22));
+        let mut b = std::collections::HashMap::new();
+        b.insert("x".to_string(), Value::Int(333));
+        let rows = vec![Value::Dict(a), Value::Dict(b)];
+        assert_eq!(format_table(&rows, None).unwrap(), "1   | 22\n333 |");
+        let headers = ["y".to_string(), "x".to_string()];
+        assert_eq!(format_table(&rows, Some(&headers)).unwrap(), "y  | x\n---+----\n22 | 1\n   | 333");
+    }
+}
@@ -2,7 +2,13 @@
 //!
 //! This module provides functions for working with time, dates, and delays.
 
-use std::time::{SystemTime, UNIX_EPOCH, Duration};
+use once_cell::sync::Lazy;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::runtime::{Env, Function, Value};
+
+/// Reference point for `monotonic()`, fixed on first use
+static MONOTONIC_START: Lazy<Instant> = Lazy::new(Instant::now);
 
 /// Get current Unix timestamp (seconds since epoch)
 pub fn time() -> i64 {
@@ -20,9 +26,13 @@ pub fn time_ms() -> i64 {
         .as_millis() as i64
 }
 
-/// Sleep for a given number of seconds
-pub fn sleep(seconds: f64) {
-    std::thread::sleep(Duration::from_secs_f64(seconds));
+/// Sleep for a given number of seconds. Negative, NaN and too-large
+/// durations are an error rather than a panic.
+pub fn sleep(seconds: f64) -> anyhow::Result<()> {
+    let duration = Duration::try_from_secs_f64(seconds)
+        .map_err(|_| anyhow::anyhow!("cannot sleep for {} seconds", seconds))?;
+    std::thread::sleep(duration);
+    Ok(())
 }
 
 /// Sleep for a given number of milliseconds
@@ -30,6 +40,79 @@ pub fn sleep_ms(milliseconds: i64) {
     std::thread::sleep(Duration::from_millis(milliseconds as u64));
 }
 
+/// Seconds elapsed on a monotonic clock. Unlike `time()` it is unaffected
+/// by wall-clock changes, so only differences between readings are meaningful.
+pub fn monotonic() -> f64 {
+    MONOTONIC_START.elapsed().as_secs_f64()
+}
+
+/// Sleep until `monotonic()` reaches `deadline`; returns at once if it already has
+pub fn sleep_until(deadline: f64) -> anyhow::Result<()> {
+    let remaining = deadline - monotonic();
+    if remaining > 0.0 || remaining.is_nan() {
+        sleep(remaining)?;
+    }
+    Ok(())
+}
+
+/// Call `func` on a worker thread and wait at most `seconds` for its result.
+///
+/// The worker gets its own copy of every binding visible from `env`, so the
+/// call can't change the caller's variables. A call that runs past the limit
+/// can't be interrupted: it keeps running in the background and its result is
+/// discarded.
+pub fn with_timeout(env: &Env, func: Function, args: Vec<Value>, seconds: f64) -> anyhow::Result<Value> {
+    if !(seconds >= 0.0 && seconds.is_finite()) {
+        return Err(anyhow::anyhow!("with_timeout() needs a non-negative number of seconds"));
+    }
+    let (vars, funcs) = env.bindings();
+    let sandboxed = env.sandboxed;
+    let budget = env.budget.clone();
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut worker = Env::from_bindings(vars, funcs);
+        worker.sandboxed = sandboxed;
+        worker.budget = budget;
+        // the receiver is gone once the caller has timed out
+        let _ = tx.send(worker.call_function(&func, args));
+    });
+    match rx.recv_timeout(Duration::from_secs_f64(seconds)) {
+        Ok(result) => result,
+        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+            Err(anyhow::anyhow!("with_timeout(): call did not finish within {} seconds", seconds))
+        }
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+            Err(anyhow::anyhow!("with_timeout(): worker thread panicked"))
+        }
+    }
+}
+
+/// Call `func` up to `attempts` times until it succeeds, sleeping `delay`
+/// seconds after each failure and multiplying the delay by `backoff`.
+/// The last error is returned when every attempt fails, and an error is
+/// returned early if the delay grows too large to sleep for.
+pub fn retry(env: &mut Env, func: &Value, attempts: i64, delay: f64, backoff: f64) -> anyhow::Result<Value> {
+    if attempts < 1 {
+        return Err(anyhow::anyhow!("retry() needs at least one attempt"));
+    }
+    if !(delay >= 0.0 && delay.is_finite() && backoff >= 0.0 && backoff.is_finite()) {
+        return Err(anyhow::anyhow!("retry() needs a non-negative delay and backoff"));
+    }
+    let mut delay = delay;
+    for attempt in 1..=attempts {
+        match env.call_value(func, Vec::new()) {
+            Ok(v) => return Ok(v),
+            Err(e) if attempt == attempts => return Err(e),
+            Err(_) => {
+                // a large backoff can push the delay past what a Duration holds
+                sleep(delay).map_err(|_| anyhow::anyhow!("retry(): delay grew to {} seconds after attempt {}", delay, attempt))?;
+                delay *= backoff;
+            }
+        }
+    }
+    unreachable!("the last attempt always returns")
+}
+
 /// Get the current system time as a string representation
 pub fn now_string() -> String {
     SystemTime::now()
@@ -42,3 +125,71 @@ pub fn now_string() -> String {
 pub fn elapsed_time(start_time: i64) -> i64 {
     time() - start_time
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script_env(src: &str) -> Env {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        env
+    }
+
+    #[test]
+    fn test_with_timeout_returns_result() {
+        let env = script_env("base = 40\ndef quick(n):\n    return base + n");
+        let func = env.get_func("quick").cloned().unwrap();
+        let result = with_timeout(&env, func, vec![Value::Int(2)], 5.0).unwrap();
+        assert_eq!(result, Value::Int(42));
+    }
+
+    #[test]
+    fn test_with_timeout_expires() {
+        let env = script_env("def slow():\n    sleep(2)\n    return 1");
+        let func = env.get_func("slow").cloned().unwrap();
+        let started = Instant::now();
+        let err = with_timeout(&env, func, vec![], 0.1).unwrap_err();
+        assert!(err.to_string().contains("did not finish"), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_retry_until_success() {
+        let mut env = script_env("def flaky():\n    n = int(env[\"FERRUM_RETRY_TEST\"]) + 1\n    env[\"FERRUM_RETRY_TEST\"] = str(n)\n    if n < 3:\n        raise \"not yet\"\n    return n\ndef broken():\n    raise \"boom\"");
+        std::env::set_var("FERRUM_RETRY_TEST", "0");
+        let result = retry(&mut env, &Value::Str("flaky".to_string()), 5, 0.01, 2.0).unwrap();
+        assert_eq!(result, Value::Int(3));
+        let err = retry(&mut env, &Value::Str("broken".to_string()), 2, 0.0, 1.0).unwrap_err();
+        assert_eq!(err.to_string(), "boom");
+    }
+
+    #[test]
+    fn test_retry_with_huge_backoff_is_an_error() {
+        let mut env = script_env("def broken():\n    raise \"boom\"");
+        let err = retry(&mut env, &Value::Str("broken".to_string()), 5, 0.001, 1e300).unwrap_err();
+        assert!(err.to_string().contains("delay grew to"), "{}", err);
+        let err = retry(&mut env, &Value::Str("broken".to_string()), 2, 1e300, 1.0).unwrap_err();
+        assert!(err.to_string().contains("delay grew to"), "{}", err);
+    }
+
+    #[test]
+    fn test_monotonic_is_non_decreasing() {
+        let a = monotonic();
+        let b = monotonic();
+        assert!(b >= a);
+    }
+
+    #[test]
+    fn test_sleep_until_waits_for_deadline() {
+        let deadline = monotonic() + 0.02;
+        sleep_until(deadline).unwrap();
+        assert!(monotonic() >= deadline);
+        // a past deadline returns immediately
+        sleep_until(0.0).unwrap();
+        // a deadline too far away for a Duration is an error, not a panic
+        assert!(sleep_until(1e300).is_err());
+        assert!(sleep_until(f64::NAN).is_err());
+    }
+}
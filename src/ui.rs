@@ -1,33 +1,63 @@
-/// UI Registry and Object Management
-/// 
-/// This module maintains a global registry of UI objects created in Ferrum scripts.
-/// The registry allows UI elements to be created in Ferrum code and referenced
-/// by their unique IDs for later manipulation.
-/// 
-/// UI Objects can be:
-/// - Windows (top-level containers)
-/// - Buttons (interactive elements)
-/// - Sliders (value input widgets)
-/// - RadioButtons (selection widgets)
-/// - Columns (vertical layout)
-/// - Rows (horizontal layout)
+//! UI Registry and Object Management
+//! 
+//! This module maintains a global registry of UI objects created in Ferrum scripts.
+//! The registry allows UI elements to be created in Ferrum code and referenced
+//! by their unique IDs for later manipulation.
+//! 
+//! UI Objects can be:
+//! - Windows (top-level containers)
+//! - Text labels (static text)
+//! - Buttons (interactive elements)
+//! - Sliders (value input widgets)
+//! - ProgressBars (read-only progress indicators)
+//! - RadioButtons (selection widgets)
+//! - Dropdowns (pick lists of string options)
+//! - Images (pictures loaded from a file path)
+//! - Columns (vertical layout)
+//! - Rows (horizontal layout)
+//! - Grids (fixed number of columns, filled row by row)
+//! 
+//! ## Event model
+//! 
+//! Scripts register Ferrum callbacks by function name on a widget event
+//! (e.g. `button.on_click("handler")`). Events are delivered through
+//! [`dispatch`], which calls the handler with the widget instance as its
+//! first argument. Handlers change the UI by calling widget methods, which
+//! write to the registry.
+//! 
+//! With the real Iced backend the event loop owns the thread while a window
+//! is open: `window.run()` hands the script's `Env` to the app for the
+//! lifetime of the window, `update` dispatches each widget message to its
+//! callback synchronously on the UI thread, and the view is rebuilt from the
+//! registry afterwards. The `Env` is handed back to the script, with any
+//! changes the callbacks made, once the window closes. In stub mode
+//! `window.run()` returns immediately and events can be simulated with
+//! methods such as `button.click()`.
 
+use anyhow::Result;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::sync::{Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::runtime::{Env, Value};
+
 static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
 
 /// UI object type enum supporting all Ferrum GUI elements
 #[derive(Debug, Clone)]
 pub enum UiObj {
     Window(WindowObj),
+    Text(TextObj),
     Button(ButtonObj),
     Slider(SliderObj),
+    ProgressBar(ProgressBarObj),
     Radio(RadioObj),
+    Dropdown(DropdownObj),
+    Image(ImageObj),
     Column(ColumnObj),
     Row(RowObj),
+    Grid(GridObj),
 }
 
 #[derive(Debug, Clone)]
@@ -35,40 +65,89 @@ pub struct WindowObj {
     pub title: String,
     pub children: Vec<String>,
     pub positions: HashMap<String, (i64, i64)>,
+    pub theme: String,
+    /// Name of the Ferrum function handling key presses, if any
+    pub on_key: Option<String>,
 }
 
+/// Theme names accepted by `set_theme`; the first is the default
+pub const THEMES: &[&str] = &["light", "dark"];
+
+#[derive(Debug, Clone)]
+pub struct TextObj { pub content: String }
+
 #[derive(Debug, Clone)]
 pub struct ButtonObj { pub label: String }
 
+/// A Ferrum function registered to handle a widget event
+#[derive(Debug, Clone)]
+pub struct Callback {
+    pub func: String,
+    pub widget: Value,
+}
+
 #[derive(Debug, Clone)]
-pub struct SliderObj { pub min: i64, pub max: i64 }
+pub struct SliderObj { pub min: i64, pub max: i64, pub value: i64 }
+
+#[derive(Debug, Clone)]
+pub struct ProgressBarObj { pub min: i64, pub max: i64, pub value: i64 }
 
 #[derive(Debug, Clone)]
 pub struct RadioObj { pub label: String }
 
+#[derive(Debug, Clone)]
+pub struct DropdownObj { pub options: Vec<String>, pub selected: Option<String> }
+
+#[derive(Debug, Clone)]
+pub struct ImageObj { pub path: String }
+
 #[derive(Debug, Clone)]
 pub struct ColumnObj { pub children: Vec<String> }
 
 #[derive(Debug, Clone)]
 pub struct RowObj { pub children: Vec<String> }
 
+#[derive(Debug, Clone)]
+pub struct GridObj { pub columns: usize, pub children: Vec<String> }
+
+impl GridObj {
+    /// Children grouped into rows of `columns` entries; the last row may be shorter
+    pub fn rows(&self) -> Vec<Vec<String>> {
+        self.children.chunks(self.columns.max(1)).map(|r| r.to_vec()).collect()
+    }
+}
+
 pub static REGISTRY: Lazy<Mutex<HashMap<String, UiObj>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
+/// Callbacks keyed by (widget id, event name)
+pub static CALLBACKS: Lazy<Mutex<HashMap<(String, String), Callback>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
 pub fn create_id(kind: &str) -> String {
     let n = NEXT_ID.fetch_add(1, Ordering::SeqCst);
     format!("{}-{}", kind, n)
 }
 
+impl UiObj {
+    /// Short type name used for registry ids and descriptions
+    pub fn kind(&self) -> &'static str {
+        match self {
+            UiObj::Window(_) => "window",
+            UiObj::Text(_) => "text",
+            UiObj::Button(_) => "button",
+            UiObj::Slider(_) => "slider",
+            UiObj::ProgressBar(_) => "progressbar",
+            UiObj::Radio(_) => "radio",
+            UiObj::Dropdown(_) => "dropdown",
+            UiObj::Image(_) => "image",
+            UiObj::Column(_) => "column",
+            UiObj::Row(_) => "row",
+            UiObj::Grid(_) => "grid",
+        }
+    }
+}
+
 pub fn insert(obj: UiObj) -> String {
-    let kind = match &obj {
-        UiObj::Window(_) => "window",
-        UiObj::Button(_) => "button",
-        UiObj::Slider(_) => "slider",
-        UiObj::Radio(_) => "radio",
-        UiObj::Column(_) => "column",
-        UiObj::Row(_) => "row",
-    };
-    let id = create_id(kind);
+    let id = create_id(obj.kind());
     let mut reg = REGISTRY.lock().unwrap();
     reg.insert(id.clone(), obj);
     id
@@ -88,6 +167,199 @@ pub fn set_title(id: &str, title: &str) {
     }
 }
 
+pub fn set_label(id: &str, label: &str) {
+    let mut reg = REGISTRY.lock().unwrap();
+    if let Some(UiObj::Button(b)) = reg.get_mut(id) {
+        b.label = label.to_string();
+    }
+}
+
+/// Set a slider's current value, clamped to its range
+pub fn set_slider_value(id: &str, value: i64) {
+    let mut reg = REGISTRY.lock().unwrap();
+    if let Some(UiObj::Slider(s)) = reg.get_mut(id) {
+        s.value = value.clamp(s.min, s.max);
+    }
+}
+
+/// Set a progress bar's current value, clamped to its range
+pub fn set_progress(id: &str, value: i64) {
+    let mut reg = REGISTRY.lock().unwrap();
+    if let Some(UiObj::ProgressBar(p)) = reg.get_mut(id) {
+        p.value = value.clamp(p.min, p.max);
+    }
+}
+
+pub fn set_content(id: &str, content: &str) {
+    let mut reg = REGISTRY.lock().unwrap();
+    if let Some(UiObj::Text(t)) = reg.get_mut(id) {
+        t.content = content.to_string();
+    }
+}
+
+pub fn set_selected(id: &str, value: &str) {
+    let mut reg = REGISTRY.lock().unwrap();
+    if let Some(UiObj::Dropdown(d)) = reg.get_mut(id) {
+        if d.options.iter().any(|o| o == value) {
+            d.selected = Some(value.to_string());
+        }
+    }
+}
+
+/// Describe the widget at `id` as a dict of its type and properties
+/// (label, value, children, ...), or `None` if the id is unknown.
+pub fn describe(id: &str) -> Option<Value> {
+    let obj = get(id)?;
+    let strs = |items: &[String]| Value::List(items.iter().map(|c| Value::Str(c.clone())).collect());
+    let mut map = HashMap::new();
+    map.insert("id".to_string(), Value::Str(id.to_string()));
+    map.insert("type".to_string(), Value::Str(obj.kind().to_string()));
+    match obj {
+        UiObj::Window(w) => {
+            map.insert("title".to_string(), Value::Str(w.title));
+            map.insert("theme".to_string(), Value::Str(w.theme));
+            map.insert("on_key".to_string(), w.on_key.map(Value::Str).unwrap_or(Value::None));
+            map.insert("children".to_string(), strs(&w.children));
+        }
+        UiObj::Text(t) => { map.insert("content".to_string(), Value::Str(t.content)); }
+        UiObj::Button(b) => { map.insert("label".to_string(), Value::Str(b.label)); }
+        UiObj::Slider(s) => {
+            map.insert("min".to_string(), Value::Int(s.min));
+            map.insert("max".to_string(), Value::Int(s.max));
+            map.insert("value".to_string(), Value::Int(s.value));
+        }
+        UiObj::ProgressBar(p) => {
+            map.insert("min".to_string(), Value::Int(p.min));
+            map.insert("max".to_string(), Value::Int(p.max));
+            map.insert("value".to_string(), Value::Int(p.value));
+        }
+        UiObj::Radio(r) => { map.insert("label".to_string(), Value::Str(r.label)); }
+        UiObj::Dropdown(d) => {
+            map.insert("options".to_string(), strs(&d.options));
+            map.insert("selected".to_string(), d.selected.map(Value::Str).unwrap_or(Value::None));
+        }
+        UiObj::Image(i) => { map.insert("path".to_string(), Value::Str(i.path)); }
+        UiObj::Column(c) => { map.insert("children".to_string(), strs(&c.children)); }
+        UiObj::Row(r) => { map.insert("children".to_string(), strs(&r.children)); }
+        UiObj::Grid(g) => {
+            map.insert("columns".to_string(), Value::Int(g.columns as i64));
+            map.insert("children".to_string(), strs(&g.children));
+        }
+    }
+    Some(Value::Dict(map))
+}
+
+/// Render the widget at `id` and its nested children as a deterministic,
+/// indented text snapshot, one widget per line.
+/// 
+/// Leaf widgets reuse the stub `render()` output, so the snapshot matches what
+/// stub mode prints. Positions come from the enclosing window's
+/// `set_position` calls and default to (0, 0). Unknown ids render as
+/// `[Missing] '<id>'`.
+pub fn render(id: &str) -> String {
+    let mut lines = Vec::new();
+    render_into(id, 0, &HashMap::new(), &mut lines);
+    lines.join("\n")
+}
+
+fn render_into(id: &str, depth: usize, positions: &HashMap<String, (i64, i64)>, lines: &mut Vec<String>) {
+    use crate::stdlib::iced_stub::iced;
+
+    let indent = "  ".repeat(depth);
+    let (x, y) = positions.get(id).map(|&(x, y)| (x as i32, y as i32)).unwrap_or((0, 0));
+    let Some(obj) = get(id) else {
+        lines.push(format!("{}[Missing] '{}'", indent, id));
+        return;
+    };
+    let (line, children, child_positions) = match obj {
+        UiObj::Window(w) => {
+            let line = format!("[Window] '{}' theme {}", w.title, w.theme);
+            (line, w.children, w.positions)
+        }
+        UiObj::Text(t) => {
+            let mut text = iced::Text::new(&t.content);
+            text.set_position(x, y);
+            (text.render(), Vec::new(), HashMap::new())
+        }
+        UiObj::Button(b) => {
+            let mut button = iced::Button::new(&b.label);
+            button.set_position(x, y);
+            (button.render(), Vec::new(), HashMap::new())
+        }
+        UiObj::Slider(s) => {
+            let mut slider = iced::Slider::new(s.min as i32, s.max as i32);
+            slider.set_position(s.value as i32);
+            slider.set_coordinates(x, y);
+            (slider.render(), Vec::new(), HashMap::new())
+        }
+        UiObj::ProgressBar(p) => {
+            let line = format!("[ProgressBar] Value: {} (range {}-{}) at ({}, {})", p.value, p.min, p.max, x, y);
+            (line, Vec::new(), HashMap::new())
+        }
+        UiObj::Radio(r) => {
+            let mut radio = iced::RadioButton::new(&r.label);
+            radio.set_position(x, y);
+            (radio.render(), Vec::new(), HashMap::new())
+        }
+        UiObj::Dropdown(d) => {
+            let selected = d.selected.unwrap_or_default();
+            let line = format!("[Dropdown] '{}' of [{}] at ({}, {})", selected, d.options.join(", "), x, y);
+            (line, Vec::new(), HashMap::new())
+        }
+        UiObj::Image(i) => (format!("[Image] '{}' at ({}, {})", i.path, x, y), Vec::new(), HashMap::new()),
+        UiObj::Column(c) => {
+            let mut column = iced::Column::new();
+            column.set_position(x, y);
+            column.children = c.children.clone();
+            (column.render(), c.children, HashMap::new())
+        }
+        UiObj::Row(r) => {
+            let mut row = iced::Row::new();
+            row.set_position(x, y);
+            row.children = r.children.clone();
+            (row.render(), r.children, HashMap::new())
+        }
+        UiObj::Grid(g) => {
+            let line = format!("[Grid] {} children in {} columns at ({}, {})", g.children.len(), g.columns, x, y);
+            (line, g.children, HashMap::new())
+        }
+    };
+    lines.push(format!("{}{}", indent, line));
+    for child in &children {
+        render_into(child, depth + 1, &child_positions, lines);
+    }
+}
+
+/// Register `func` as the key press handler for window `id`.
+/// 
+/// The handler is called as `func(window, key)` where `key` is the key name
+/// reported by Iced (e.g. "A", "Enter", "Space", "Left").
+pub fn set_key_handler(id: &str, func: &str, window: Value) {
+    {
+        let mut reg = REGISTRY.lock().unwrap();
+        if let Some(UiObj::Window(w)) = reg.get_mut(id) {
+            w.on_key = Some(func.to_string());
+        }
+    }
+    set_callback(id, "key", func, window);
+}
+
+/// Set a window's theme. Unknown names fall back to the default theme
+/// with a warning.
+pub fn set_theme(id: &str, theme: &str) {
+    let name = theme.to_lowercase();
+    let name = if THEMES.contains(&name.as_str()) {
+        name
+    } else {
+        eprintln!("[ui] Unknown theme '{}', using '{}'", theme, THEMES[0]);
+        THEMES[0].to_string()
+    };
+    let mut reg = REGISTRY.lock().unwrap();
+    if let Some(UiObj::Window(w)) = reg.get_mut(id) {
+        w.theme = name;
+    }
+}
+
 pub fn set_position(id: &str, child_id: &str, x: i64, y: i64) {
     let mut reg = REGISTRY.lock().unwrap();
     if let Some(obj) = reg.get_mut(id) {
@@ -125,7 +397,219 @@ pub fn add_child(id: &str, child_id: &str) {
             UiObj::Window(w) => { w.children.push(child_id.to_string()); }
             UiObj::Column(c) => { c.children.push(child_id.to_string()); }
             UiObj::Row(r) => { r.children.push(child_id.to_string()); }
+            UiObj::Grid(g) => { g.children.push(child_id.to_string()); }
             _ => {}
         }
     }
 }
+
+/// Register `func` as the handler for `event` on widget `id`
+pub fn set_callback(id: &str, event: &str, func: &str, widget: Value) {
+    let mut cbs = CALLBACKS.lock().unwrap();
+    cbs.insert((id.to_string(), event.to_string()), Callback { func: func.to_string(), widget });
+}
+
+pub fn get_callback(id: &str, event: &str) -> Option<Callback> {
+    let cbs = CALLBACKS.lock().unwrap();
+    cbs.get(&(id.to_string(), event.to_string())).cloned()
+}
+
+/// Run the callback registered for `event` on widget `id`, if any.
+/// 
+/// The handler receives the widget instance followed by `args`.
+/// Returns `Value::None` when no handler is registered.
+pub fn dispatch(env: &mut Env, id: &str, event: &str, args: Vec<Value>) -> Result<Value> {
+    match get_callback(id, event) {
+        Some(cb) => {
+            let mut call_args = vec![cb.widget];
+            call_args.extend(args);
+            env.call(&cb.func, call_args)
+        }
+        None => Ok(Value::None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn widget_id(env: &Env, name: &str) -> String {
+        match env.get(name) {
+            Some(Value::Instance(_, fields, _)) => match fields.get("__id") {
+                Some(Value::Str(id)) => id.clone(),
+                _ => panic!("{} has no __id", name),
+            },
+            _ => panic!("{} is not an instance", name),
+        }
+    }
+
+    #[test]
+    fn test_click_callback_mutates_registry() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let src = "def handle(button):\n    button.set_label(\"Clicked\")\nb = Button(\"Press\")\nb.on_click(\"handle\")\nb.click()\nlabel = b.get_label()";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        match env.get("label") {
+            Some(Value::Str(s)) => assert_eq!(s, "Clicked"),
+            other => panic!("unexpected label: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_text_label_added_to_window() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let src = "w = Window()\nt = Text(\"Hello\")\nt.set_content(\"Hello Ferrum\")\nw.add(t)";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        let id_of = |name: &str| widget_id(&env, name);
+        let text_id = id_of("t");
+        match get(&id_of("w")) {
+            Some(UiObj::Window(w)) => assert_eq!(w.children, vec![text_id.clone()]),
+            other => panic!("unexpected window: {:?}", other),
+        }
+        match get(&text_id) {
+            Some(UiObj::Text(t)) => assert_eq!(t.content, "Hello Ferrum"),
+            other => panic!("unexpected text: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_column_nested_in_window() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let src = "w = Window()\nc = Column()\nok = Button(\"OK\")\ncancel = Button(\"Cancel\")\nc.add(ok)\nc.add(cancel)\nw.add(c)";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        let id_of = |name: &str| widget_id(&env, name);
+        match get(&id_of("w")) {
+            Some(UiObj::Window(w)) => assert_eq!(w.children, vec![id_of("c")]),
+            other => panic!("unexpected window: {:?}", other),
+        }
+        match get(&id_of("c")) {
+            Some(UiObj::Column(c)) => assert_eq!(c.children, vec![id_of("ok"), id_of("cancel")]),
+            other => panic!("unexpected column: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_grid_groups_children_into_rows() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let src = "g = Grid(2)\nb1 = Button(\"1\")\nb2 = Button(\"2\")\nb3 = Button(\"3\")\nb4 = Button(\"4\")\nb5 = Button(\"5\")\ng.add(b1)\ng.add(b2)\ng.add(b3)\ng.add(b4)\ng.add(b5)";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        let id_of = |name: &str| widget_id(&env, name);
+        match get(&id_of("g")) {
+            Some(UiObj::Grid(g)) => assert_eq!(
+                g.rows(),
+                vec![
+                    vec![id_of("b1"), id_of("b2")],
+                    vec![id_of("b3"), id_of("b4")],
+                    vec![id_of("b5")],
+                ]
+            ),
+            other => panic!("unexpected grid: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_window_theme_round_trip() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let src = "w = Window()\ndefault = w.get_theme()\nw.set_theme(\"dark\")\ndark = w.get_theme()\nw.set_theme(\"neon\")\nfallback = w.get_theme()";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        for (name, expected) in [("default", "light"), ("dark", "dark"), ("fallback", "light")] {
+            match env.get(name) {
+                Some(Value::Str(s)) => assert_eq!(s, expected),
+                other => panic!("unexpected {}: {:?}", name, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_slider_value_round_trip() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let src = "s = Slider(0, 10)\nstart = s.get_value()\ns.set_value(7)\nmoved = s.get_value()\ns.set_value(42)\nclamped = s.get_value()";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        for (name, expected) in [("start", 5), ("moved", 7), ("clamped", 10)] {
+            match env.get(name) {
+                Some(Value::Int(v)) => assert_eq!(*v, expected),
+                other => panic!("unexpected {}: {:?}", name, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_image_widget_in_registry() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        crate::parser::parse_and_run("img = Image(\"icon/icon.png\")", &mut env).unwrap();
+        match get(&widget_id(&env, "img")) {
+            Some(UiObj::Image(i)) => assert_eq!(i.path, "icon/icon.png"),
+            other => panic!("unexpected image: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_progress_bar_clamps_value() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let src = "p = ProgressBar(0, 100)\nstart = p.get_value()\np.set_value(40)\nhalf = p.get_value()\np.set_value(150)\nhigh = p.get_value()\np.set_value(-5)\nlow = p.get_value()";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        for (name, expected) in [("start", 0), ("half", 40), ("high", 100), ("low", 0)] {
+            match env.get(name) {
+                Some(Value::Int(v)) => assert_eq!(*v, expected),
+                other => panic!("unexpected {}: {:?}", name, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_ui_get_describes_button() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        crate::parser::parse_and_run("b = Button(\"Save\")\ninfo = ui_get(b.__id)", &mut env).unwrap();
+        match env.get("info") {
+            Some(Value::Dict(map)) => {
+                assert!(matches!(map.get("type"), Some(Value::Str(t)) if t == "button"));
+                assert!(matches!(map.get("label"), Some(Value::Str(l)) if l == "Save"));
+            }
+            other => panic!("unexpected info: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ui_render_snapshot() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let src = "w = Window()\nw.set_title(\"App\")\ncol = Column()\ncol.add(Button(\"OK\"))\ncol.add(Button(\"Cancel\"))\nw.add(col)\nsnap = ui_render(w.__id)";
+        crate::parser::parse_and_run(src, &mut env).unwrap();
+        let expected = "[Window] 'App' theme light\n  [Column] 2 children at (0, 0) with 5 px spacing\n    [Button] 'OK' at (0, 0)\n    [Button] 'Cancel' at (0, 0)";
+        assert!(matches!(env.get("snap"), Some(Value::Str(s)) if s == expected));
+    }
+
+    #[test]
+    fn test_window_stores_key_handler() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        crate::parser::parse_and_run("w = Window()\nw.on_key(\"handle_key\")", &mut env).unwrap();
+        let id = widget_id(&env, "w");
+        match get(&id) {
+            Some(UiObj::Window(w)) => assert_eq!(w.on_key.as_deref(), Some("handle_key")),
+            other => panic!("unexpected window: {:?}", other),
+        }
+        assert_eq!(get_callback(&id, "key").map(|cb| cb.func).as_deref(), Some("handle_key"));
+    }
+
+    #[test]
+    fn test_dropdown_default_selection() {
+        let mut env = Env::new();
+        crate::stdlib::register_stdlib(&mut env);
+        let options = ["red", "green", "blue"].iter().map(|o| Value::Str(o.to_string())).collect();
+        let dropdown = env.call("Dropdown", vec![Value::List(options)]).unwrap();
+        env.set("d".to_string(), dropdown);
+        match crate::eval::eval_expr_str("d.get_selected()", &mut env).unwrap() {
+            Value::Str(s) => assert_eq!(s, "red"),
+            other => panic!("unexpected selection: {:?}", other),
+        }
+    }
+}
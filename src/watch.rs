@@ -0,0 +1,176 @@
+//! `ferrum run --watch`: rerun a script whenever it or a module it imports
+//! changes on disk
+
+use anyhow::Result;
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Quiet period after the first change, so one save that touches a file
+/// several times reruns the script only once
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// The script plus every `.fm` module it imports, directly or through other
+/// modules. Builtin modules (imports with no file) are skipped, and an
+/// unparseable file is still watched so fixing it triggers a rerun.
+pub fn watched_paths(script: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![script.to_path_buf()];
+    let mut i = 0;
+    while i < paths.len() {
+        let imports = std::fs::read_to_string(&paths[i])
+            .ok()
+            .and_then(|src| crate::parser::parse_to_ast(&src).ok())
+            .map(|ast| imports(&ast.body))
+            .unwrap_or_default();
+        for name in imports {
+            // same lookup as Env::import_module: relative to the working directory
+            let path = PathBuf::from(if name.ends_with(".fm") { name } else { format!("{}.fm", name) });
+            if path.exists() && !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        i += 1;
+    }
+    paths
+}
+
+fn imports(stmts: &[crate::eval::Stmt]) -> Vec<String> {
+    use crate::eval::Stmt;
+
+    stmts
+        .iter()
+        .flat_map(|s| match s {
+            Stmt::Import(name) => vec![name.clone()],
+            Stmt::If(branches, else_block) => branches
+                .iter()
+                .flat_map(|(_, body)| imports(body))
+                .chain(imports(else_block))
+                .collect(),
+            Stmt::Else(body) | Stmt::While(_, body) | Stmt::For(_, body) | Stmt::Def(_, body) | Stmt::Class(_, body) | Stmt::Block(_, body) => imports(body),
+            Stmt::Try { body, except_body, finally_body, .. } => {
+                [body, except_body, finally_body].into_iter().flat_map(|b| imports(b)).collect()
+            }
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+/// Block until one of `paths` is modified, created or replaced, or until
+/// `timeout` passes. Returns whether a change was seen.
+///
+/// Parent directories are watched rather than the files themselves, since
+/// many editors save by writing a new file and renaming it into place.
+pub fn wait_for_change(paths: &[PathBuf], timeout: Option<Duration>) -> Result<bool> {
+    let names: Vec<PathBuf> = paths.iter().map(std::path::absolute).collect::<std::io::Result<_>>()?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            if !event.kind.is_access() {
+                let _ = tx.send(event.paths);
+            }
+        }
+    })?;
+    let mut dirs: Vec<&Path> = names.iter().filter_map(|p| p.parent()).collect();
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    let deadline = timeout.map(|t| std::time::Instant::now() + t);
+    loop {
+        let received = match deadline {
+            Some(deadline) => match rx.recv_timeout(deadline.saturating_duration_since(std::time::Instant::now())) {
+                Ok(changed) => changed,
+                Err(_) => return Ok(false),
+            },
+            None => match rx.recv() {
+                Ok(changed) => changed,
+                Err(_) => return Ok(false),
+            },
+        };
+        if received.iter().any(|p| names.iter().any(|n| n == p || n.file_name() == p.file_name() && n.parent() == p.parent())) {
+            while rx.recv_timeout(DEBOUNCE).is_ok() {}
+            return Ok(true);
+        }
+    }
+}
+
+/// Run `path` with `args`, then rerun it after every change until the
+/// process is interrupted. Script errors are printed rather than ending the watch.
+pub fn run_watch(path: &str, args: &[String]) -> Result<()> {
+    loop {
+        // clear the screen and move the cursor home
+        print!("\x1b[2J\x1b[H");
+        if let Err(e) = crate::interpreter::run_file(path, args) {
+            eprintln!("Error: {:#}", e);
+        }
+        let paths = watched_paths(Path::new(path));
+        println!("\n[watching {} file(s); Ctrl-C to exit]", paths.len());
+        wait_for_change(&paths, None)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_change_triggers_rerun() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("main.fm");
+        let other = dir.path().join("notes.txt");
+        std::fs::write(&script, "x = 1\n").unwrap();
+        let paths = vec![script.clone()];
+
+        // touching an unrelated file in the same directory is ignored
+        let writer_other = other.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            std::fs::write(writer_other, "unrelated").unwrap();
+        });
+        assert!(!wait_for_change(&paths, Some(Duration::from_millis(800))).unwrap());
+
+        let writer_script = script.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            std::fs::write(writer_script, "x = 2\n").unwrap();
+        });
+        assert!(wait_for_change(&paths, Some(Duration::from_secs(5))).unwrap());
+    }
+
+    #[test]
+    fn test_watched_paths_follow_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("main.fm");
+        std::fs::write(&script, "import math\nx = 1\n").unwrap();
+        // `math` is a builtin module with no file, so only the script is watched
+        assert_eq!(watched_paths(&script), vec![script]);
+    }
+
+    #[test]
+    fn test_module_in_another_directory_is_watched() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = tempfile::tempdir().unwrap();
+        let helper = lib.path().join("helper.fm");
+        let util = lib.path().join("util.fm");
+        std::fs::write(&util, "def twice(x):\n    return x * 2\n").unwrap();
+        std::fs::write(&helper, format!("import {}\n", util.display())).unwrap();
+        let script = dir.path().join("main.fm");
+        // imported without the extension, and util a second time through helper
+        let helper_name = lib.path().join("helper");
+        std::fs::write(&script, format!("import {}\nimport {}\n", helper_name.display(), util.display())).unwrap();
+
+        let paths = watched_paths(&script);
+        assert_eq!(paths, vec![script.clone(), helper.clone(), util.clone()]);
+
+        // the module's directory is watched alongside the script's
+        let writer = helper.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            std::fs::write(writer, "x = 1\n").unwrap();
+        });
+        assert!(wait_for_change(&paths, Some(Duration::from_secs(5))).unwrap());
+    }
+}